@@ -2,7 +2,11 @@
 //!
 //! Shortcuts for instancing common nodes.
 use nsi_core as nsi;
-use nsi_toolbelt::{append, generate_or_use_handle, node, rotation};
+use nsi_toolbelt::{
+    append, generate_or_use_handle, look_at_camera, multiplier_to_ev, node,
+    rotation, set_euler_rotation, set_exposure,
+};
+use std::{path::Path, process::Command};
 
 /// Creates a typical environment node.
 ///
@@ -29,7 +33,7 @@ pub fn environment(
     visible: Option<bool>,
 ) -> (String, String) {
     // Create a rotation transform – this is the handle we return.
-    let rotation = rotation(ctx, None, angle.unwrap_or(0.0), &[0.0, 1.0, 0.0]);
+    let rotation = rotation(ctx, None, angle.unwrap_or(0.0), [0.0, 1.0, 0.0]);
 
     let environment = generate_or_use_handle(handle, Some("environment"));
 
@@ -72,6 +76,42 @@ pub fn environment(
     (rotation, shader)
 }
 
+/// Sets the orientation of an environment.
+///
+/// The `angle` of [`environment()`] & friends only spins the environment
+/// around the up axis. This also tilts it, e.g. to level a horizon that was
+/// shot at an angle.
+///
+/// # Arguments
+/// * `rotation` – The rotation handle returned by [`environment()`],
+///   [`environment_texture()`] or [`environment_sky()`].
+///
+/// * `euler` – The angles around the X, Y (up) & Z axes, in degrees. They
+///   are applied in this order. See
+///   [`set_euler_rotation()`](nsi_toolbelt::set_euler_rotation()) for the
+///   details of the convention.
+///
+/// This replaces the `angle` the environment was created with.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_3delight::{environment_sky, set_environment_orientation};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let (rotation, _) = environment_sky(&ctx, None, None, None, None, None);
+///
+/// // Tilt the horizon by 5° towards the camera, then spin it by 90°.
+/// set_environment_orientation(&ctx, &rotation, [5.0, 90.0, 0.0]);
+/// ```
+#[inline]
+pub fn set_environment_orientation(
+    ctx: &nsi::Context,
+    rotation: &str,
+    euler: [f64; 3],
+) {
+    set_euler_rotation(ctx, rotation, &euler);
+}
+
 /// Creates a textured environment light.
 ///
 /// If `handle` is [`None`] a random handle is generated.
@@ -120,10 +160,10 @@ where
         shader.as_str(),
         &[
             nsi::string!("shaderfilename", "${DELIGHT}/osl/environmentLight"),
-            nsi::float!("intensity", 2.0f32.powf(exposure.unwrap_or(0.0))),
             nsi::string!("image", texture),
         ],
     );
+    set_exposure(ctx, shader.as_str(), exposure.unwrap_or(0.0));
 
     if let Some(args) = args {
         ctx.set_attribute(shader.as_str(), args);
@@ -132,6 +172,118 @@ where
     (rotation, shader)
 }
 
+/// The file extensions of the texture formats 3Delight can read.
+///
+/// All formats but `tdl` are converted to 3Delight's own tiled, mip-mapped
+/// `.tdl` format when a render starts.
+pub const TEXTURE_EXTENSIONS: [&str; 16] = [
+    "tdl", "tif", "tiff", "jpg", "jpeg", "hdr", "rgbe", "exr", "gif", "iff",
+    "sgi", "rgb", "pic", "psd", "tga", "png",
+];
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Creates a textured environment light after checking the texture.
+///
+/// This is [`environment_texture()`] with feedback about the `texture` file:
+///
+/// * If its extension is not one of [`TEXTURE_EXTENSIONS`], an error is
+///   reported.
+///
+/// * If it is not a `.tdl` file, a warning is reported that it will be
+///   converted when the render starts. For large maps this conversion can
+///   make the first render noticeably slow.
+///
+/// Messages are sent to the context's `"errorhandler"` via
+/// [`report()`](nsi::Context::report()).
+///
+/// # Arguments
+/// * `convert` – If `true`, a non-`.tdl` texture is converted up front with
+///   3Delight's `tdlmake` tool. The result is written next to `texture`, with
+///   a `.tdl` extension, and used instead. An existing `.tdl` file that is
+///   newer than `texture` is reused. If `tdlmake` can not be found or fails,
+///   `texture` is used as is.
+///
+/// See [`environment_texture()`] for the other arguments.
+///
+/// Returns `handle` and the handle of the created `shader`.
+#[allow(clippy::too_many_arguments)]
+pub fn environment_texture_checked<'a, 'b>(
+    ctx: &nsi::Context<'a>,
+    handle: Option<&str>,
+    texture: &str,
+    angle: Option<f64>,
+    exposure: Option<f32>,
+    visible: Option<bool>,
+    convert: bool,
+    args: Option<&nsi::ArgSlice<'b, 'a>>,
+) -> (String, String)
+where
+    'a: 'b,
+{
+    let path = Path::new(texture);
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let mut texture = texture.to_string();
+
+    if !TEXTURE_EXTENSIONS.contains(&extension.as_str()) {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Environment texture '{}' is not in a format 3Delight can \
+                read.",
+                texture
+            ),
+        );
+    } else if "tdl" != extension {
+        match convert.then(|| convert_to_tdl(path)).flatten() {
+            Some(converted) => texture = converted,
+            None => ctx.report(
+                log::Level::Warn,
+                &format!(
+                    "Environment texture '{}' will be converted to .tdl when \
+                    the render starts. Convert it with 'tdlmake -envlatl' \
+                    beforehand to speed this up.",
+                    texture
+                ),
+            ),
+        }
+    }
+
+    environment_texture(ctx, handle, &texture, angle, exposure, visible, args)
+}
+
+// Converts a lat-long texture to .tdl with tdlmake, unless there already is
+// an up-to-date one. Returns the path to the .tdl file.
+fn convert_to_tdl(path: &Path) -> Option<String> {
+    let tdl = path.with_extension("tdl");
+
+    let modified =
+        |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    if let (Some(source), Some(converted)) = (modified(path), modified(&tdl)) {
+        if source <= converted {
+            return tdl.to_str().map(|tdl| tdl.to_string());
+        }
+    }
+
+    let tdlmake = std::env::var_os("DELIGHT")
+        .map(|delight| Path::new(&delight).join("bin").join("tdlmake"))
+        .unwrap_or_else(|| "tdlmake".into());
+
+    Command::new(tdlmake)
+        .arg("-envlatl")
+        .arg(path)
+        .arg(&tdl)
+        .status()
+        .ok()
+        .filter(|status| status.success())
+        .and_then(|_| tdl.to_str().map(|tdl| tdl.to_string()))
+}
+
 /// **Convenience method; not part of the official ɴsɪ API.**
 ///
 /// Creates a physically plausible, procedural sky environment light.
@@ -166,11 +318,9 @@ where
     // Environment light attributes.
     ctx.set_attribute(
         shader.as_str(),
-        &[
-            nsi::string!("shaderfilename", "${DELIGHT}/osl/dlSky"),
-            nsi::float!("intensity", 2.0f32.powf(exposure.unwrap_or(0.0))),
-        ],
+        &[nsi::string!("shaderfilename", "${DELIGHT}/osl/dlSky")],
     );
+    set_exposure(ctx, shader.as_str(), exposure.unwrap_or(0.0));
 
     if let Some(args) = args {
         ctx.set_attribute(shader.as_str(), args);
@@ -178,3 +328,219 @@ where
 
     (rotation, shader)
 }
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Creates a spot light.
+///
+/// The light sits at the origin and shines down the negative Z axis. Use the
+/// returned transform handle to place it in the scene.
+///
+/// If `handle` is [`None`] a random handle is generated.
+///
+/// # Arguments
+/// * `cone_angle` – In degrees; the full opening angle of the light cone.
+///
+/// * `penumbra_angle` – In degrees; the angle over which the light falls off
+///   at the edge of the cone.
+///
+/// * `exposure` – Scales the intensity in [stops or EV values](https://en.wikipedia.org/wiki/Exposure_value).
+///
+/// Returns `handle` and the handle of the created `shader`.
+///
+/// Note that this instances a `spotLight` shader. Using the returned `shader`
+/// handle you can set more attributes on this node, e.g. a
+/// [gobo](set_gobo).
+pub fn spot_light(
+    ctx: &nsi::Context,
+    handle: Option<&str>,
+    cone_angle: f32,
+    penumbra_angle: Option<f32>,
+    exposure: Option<f32>,
+) -> (String, String) {
+    let transform = node(ctx, handle, nsi::node::TRANSFORM, None);
+
+    let light = node(
+        ctx,
+        None,
+        nsi::node::PARTICLES,
+        Some(&[
+            nsi::points!("P", &[0.0, 0.0, 0.0]),
+            nsi::floats!("width", &[0.01]),
+        ]),
+    );
+    append(ctx, &transform, None, &light);
+
+    let shader = node(
+        ctx,
+        None,
+        nsi::node::SHADER,
+        Some(&[
+            nsi::string!("shaderfilename", "${DELIGHT}/osl/spotLight"),
+            nsi::float!("coneAngle", cone_angle),
+            nsi::float!("penumbraAngle", penumbra_angle.unwrap_or(0.0)),
+        ]),
+    );
+    set_exposure(ctx, shader.as_str(), exposure.unwrap_or(0.0));
+
+    append(
+        ctx,
+        &light,
+        Some("geometryattributes"),
+        append(
+            ctx,
+            &node(
+                ctx,
+                None,
+                nsi::node::ATTRIBUTES,
+                Some(&[nsi::integer!("visibility.camera", false as _)]),
+            ),
+            Some("surfaceshader"),
+            shader.as_str(),
+        )
+        .0,
+    );
+
+    (transform, shader)
+}
+
+/// The intensities of the key, fill & rim lights of a
+/// [`three_point_lighting()`] rig, relative to the key light.
+const THREE_POINT_RATIOS: [f32; 3] = [1.0, 0.5, 0.75];
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Creates a studio three-point lighting rig around a bounding box.
+///
+/// This is meant for quickly previewing an asset. The rig consists of three
+/// [`spot_light()`]s aimed at the center of `target_bounding_box`:
+///
+/// | Light | Position (seen from the camera)          | Intensity ratio
+/// |-------|------------------------------------------|----------------
+/// | Key   | Front right, 45° to the side, 30° up.    | `1`
+/// | Fill  | Front left, 45° to the side, 15° up.     | `0.5`
+/// | Rim   | Behind & left, 150° to the side, 45° up. | `0.75`
+///
+/// The camera is assumed to look down the negative Z axis, with Y up. The
+/// lights are three times the radius of the box's bounding sphere away from
+/// its center and their cones cover that sphere.
+///
+/// The intensity of each light is compensated for its distance. A key
+/// light `intensity` of `1` hence lights the center of the box with roughly
+/// the same brightness, whatever the size of the box.
+///
+/// # Arguments
+/// * `target_bounding_box` – Axis-aligned bounding box in the form `[x_min,
+///   y_min, z_min, x_max, y_max, z_max]`.
+///
+/// * `intensity` – The intensity of the key light. The other lights follow
+///   the ratios above.
+///
+/// Returns the transform handles of the key, fill & rim light, in this
+/// order. Connect them to the scene, e.g. to the [`.root`](nsi::ROOT).
+///
+/// # Panics
+///
+/// If `target_bounding_box` is empty.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::append;
+/// # use nsi_3delight::three_point_lighting;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// for light in
+///     three_point_lighting(&ctx, &[-1.0, 0.0, -1.0, 1.0, 2.0, 1.0], 1.0)
+/// {
+///     append(&ctx, nsi::ROOT, None, &light);
+/// }
+/// ```
+pub fn three_point_lighting(
+    ctx: &nsi::Context,
+    target_bounding_box: &[f64; 6],
+    intensity: f32,
+) -> [String; 3] {
+    let [x_min, y_min, z_min, x_max, y_max, z_max] = *target_bounding_box;
+    let center = [
+        0.5 * (x_min + x_max),
+        0.5 * (y_min + y_max),
+        0.5 * (z_min + z_max),
+    ];
+    let radius = 0.5
+        * ((x_max - x_min).powi(2)
+            + (y_max - y_min).powi(2)
+            + (z_max - z_min).powi(2))
+        .sqrt();
+
+    assert!(
+        0.0 < radius,
+        "The bounding box {:?} of the three-point lighting rig is empty.",
+        target_bounding_box
+    );
+
+    let distance = 3.0 * radius;
+    let penumbra_angle = 10.0;
+    let cone_angle =
+        2.0 * (radius / distance).asin().to_degrees() as f32 + penumbra_angle;
+
+    [
+        ("key_light", 45.0f64, 30.0f64),
+        ("fill_light", -45.0, 15.0),
+        ("rim_light", -150.0, 45.0),
+    ]
+    .iter()
+    .zip(THREE_POINT_RATIOS)
+    .map(|(&(name, azimuth, elevation), ratio)| {
+        let (azimuth, elevation) =
+            (azimuth.to_radians(), elevation.to_radians());
+        let eye = [
+            center[0] + distance * azimuth.sin() * elevation.cos(),
+            center[1] + distance * elevation.sin(),
+            center[2] + distance * azimuth.cos() * elevation.cos(),
+        ];
+
+        let aim = generate_or_use_handle(None, Some(name));
+        look_at_camera(ctx, Some(&aim), eye, center, [0.0, 1.0, 0.0]);
+
+        let (light, _) = spot_light(
+            ctx,
+            None,
+            cone_angle,
+            Some(penumbra_angle),
+            Some(multiplier_to_ev(
+                intensity * ratio * (distance * distance) as f32,
+            )),
+        );
+        append(ctx, &aim, None, &light);
+
+        aim
+    })
+    .collect::<Vec<_>>()
+    .try_into()
+    .unwrap()
+}
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Projects a texture through the cone of a [`spot_light()`].
+///
+/// The texture is mapped onto the cross section of the cone and tints the
+/// light. The light's `"image"` parameter is set to `texture`.
+///
+/// # Arguments
+/// * `light_shader` – The `shader` handle returned by [`spot_light()`].
+///
+/// * `texture` – A texture map in one of these formats:
+///     * TIFF
+///     * JPEG
+///     * Radiance
+///     * OpenEXR
+///     * GIF
+///     * IFF
+///     * SGI
+///     * PIC
+///     * Photoshop PSD
+///     * TGA
+pub fn set_gobo(ctx: &nsi::Context, light_shader: &str, texture: &str) {
+    ctx.set_string(light_shader, "image", texture);
+}
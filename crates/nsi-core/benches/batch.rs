@@ -0,0 +1,112 @@
+//! Compares [`Context::batch()`](nsi::Context::batch()) against calling the
+//! API directly.
+//!
+//! Run with `cargo bench -p nsi-core --bench batch`.
+use nsi_core as nsi;
+use std::time::{Duration, Instant};
+
+/// Grid resolution; the mesh has `2 · GRID²` triangles, i.e. ~1M.
+const GRID: usize = 708;
+/// Number of nodes created in the many-small-nodes case.
+const NODES: usize = 100_000;
+
+fn triangle_grid() -> (Vec<f32>, Vec<i32>) {
+    let positions = (0..=GRID)
+        .flat_map(|y| (0..=GRID).flat_map(move |x| [x as f32, y as f32, 0.0]))
+        .collect();
+
+    let row = GRID as i32 + 1;
+    let indices = (0..GRID as i32)
+        .flat_map(|y| {
+            (0..GRID as i32).flat_map(move |x| {
+                let i = y * row + x;
+                [i, i + 1, i + row, i + 1, i + row + 1, i + row]
+            })
+        })
+        .collect();
+
+    (positions, indices)
+}
+
+fn mesh_args<'a, 'b>(
+    positions: &'a [f32],
+    indices: &'a [i32],
+    nvertices: &'a [i32],
+) -> [nsi::Arg<'a, 'b>; 3] {
+    [
+        nsi::integers!("nvertices", nvertices),
+        nsi::points!("P", positions),
+        nsi::integers!("P.indices", indices),
+    ]
+}
+
+fn time(name: &str, f: impl FnOnce(&nsi::Context)) -> Duration {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let start = Instant::now();
+    f(&ctx);
+    let elapsed = start.elapsed();
+
+    println!("{:<32} {:>10.2?}", name, elapsed);
+    elapsed
+}
+
+fn main() {
+    let (positions, indices) = triangle_grid();
+    let nvertices = vec![3; indices.len() / 3];
+
+    println!("mesh with {} triangles", nvertices.len());
+    time("direct", |ctx| {
+        ctx.create("mesh", nsi::MESH, None);
+        ctx.set_attribute("mesh", &mesh_args(&positions, &indices, &nvertices));
+        ctx.connect("mesh", None, nsi::ROOT, "objects", None);
+    });
+    time("batch", |ctx| {
+        ctx.batch(|batch| {
+            batch.create("mesh", nsi::MESH, None);
+            batch.set_attribute(
+                "mesh",
+                &mesh_args(&positions, &indices, &nvertices),
+            );
+            batch.connect("mesh", None, nsi::ROOT, "objects", None);
+        })
+    });
+
+    println!("{} transforms", NODES);
+    time("direct", |ctx| {
+        for i in 0..NODES {
+            let handle = format!("xform{}", i);
+            ctx.create(&handle, nsi::TRANSFORM, None);
+            ctx.set_attribute(
+                &handle,
+                &[nsi::double_matrix!(
+                    "transformationmatrix",
+                    &[
+                        1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0.,
+                        i as f64, 0., 0., 1.,
+                    ]
+                )],
+            );
+            ctx.connect(&handle, None, nsi::ROOT, "objects", None);
+        }
+    });
+    time("batch", |ctx| {
+        ctx.batch(|batch| {
+            for i in 0..NODES {
+                let handle = format!("xform{}", i);
+                batch.create(&handle, nsi::TRANSFORM, None);
+                batch.set_attribute(
+                    &handle,
+                    &[nsi::double_matrix!(
+                        "transformationmatrix",
+                        &[
+                            1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0.,
+                            i as f64, 0., 0., 1.,
+                        ]
+                    )],
+                );
+                batch.connect(&handle, None, nsi::ROOT, "objects", None);
+            }
+        })
+    });
+}
@@ -0,0 +1,40 @@
+//! Compares [`srgb_encode()`](nsi::output::srgb_encode()) against
+//! [`srgb_encode_parallel()`](nsi::output::srgb_encode_parallel()) for a
+//! single bucket and a whole image.
+//!
+//! Run with `cargo bench -p nsi-core --features rayon --bench srgb`. Without
+//! the `rayon` feature both functions are the same.
+use nsi_core as nsi;
+use std::time::{Duration, Instant};
+
+/// Number of times each conversion is repeated.
+const RUNS: u32 = 10;
+
+fn time(
+    name: &str,
+    pixels: usize,
+    encode: fn(&[f32], &mut [u8], usize, Option<usize>),
+) -> Duration {
+    let src = (0..pixels * 4)
+        .map(|i| (i % 1024) as f32 / 1023.0)
+        .collect::<Vec<_>>();
+    let mut dst = vec![0u8; src.len()];
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        encode(&src, &mut dst, 4, Some(3));
+    }
+    let elapsed = start.elapsed() / RUNS;
+
+    println!("{:<32} {:>10.2?}", name, elapsed);
+    elapsed
+}
+
+fn main() {
+    for (name, pixels) in [("16×16 bucket", 16 * 16), ("4K image", 3840 * 2160)]
+    {
+        println!("{}", name);
+        time("serial", pixels, nsi::output::srgb_encode);
+        time("parallel", pixels, nsi::output::srgb_encode_parallel);
+    }
+}
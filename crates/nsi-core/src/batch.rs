@@ -0,0 +1,391 @@
+//! Recording many scene edits and sending them to the renderer at once.
+use crate::{
+    argument::{get_c_param_vec, Arg, ArgDataMethods, ArgSlice, Type},
+    Context,
+};
+use nsi_sys::{NSIParam, NSIParamFlags};
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    fmt::Write,
+};
+
+/// Records scene edits as an in-memory ɴsɪ stream.
+///
+/// A `Batch` is handed to the closure passed to
+/// [`Context::batch()`]. It has the same methods as a [`Context`] for
+/// editing the scene. Instead of calling into the renderer right away, each
+/// call is appended to a buffer. When the closure returns, the buffer is sent
+/// to the renderer with a single call to
+/// [`evaluate()`](Context::evaluate()).
+///
+/// Attributes go through the same [checks](Context::set_attribute_check())
+/// and [tracing](Context::set_trace()) as with
+/// [`set_attribute()`](Context::set_attribute()). The recorded edits are also
+/// added to the context's Rust-side mirror of the scene, e.g. for
+/// [`validate()`](Context::validate()).
+///
+/// # Limitations
+///
+/// Some arguments can not be expressed in the stream. When a call contains
+/// any, the edits recorded so far are sent to the renderer and these
+/// arguments are set directly afterwards, so nothing is lost:
+///
+/// * Pointer arguments, i.e. [`Reference`](crate::Reference)s and
+///   [`Callback`](crate::Callback)s.
+///
+/// * Arguments with per-face/per-vertex granularity or linear interpolation
+///   [flags](crate::Arg::per_vertex()). The text format has no syntax for
+///   these.
+///
+/// * Floating point arguments with a value that is not finite, i.e. `NaN`
+///   or infinite. The text format has no syntax for these either.
+///
+/// The stream is written in the text format. Each value is thus formatted
+/// and parsed again. For a mesh whose positions are set per vertex, nothing
+/// is gained; see [`Context::batch()`].
+pub struct Batch<'c, 'a> {
+    ctx: &'c Context<'a>,
+    stream: String,
+}
+
+impl<'c, 'a> Batch<'c, 'a> {
+    #[inline]
+    pub(crate) fn new(ctx: &'c Context<'a>) -> Self {
+        Self {
+            ctx,
+            stream: String::new(),
+        }
+    }
+
+    /// See [`Context::create()`].
+    pub fn create(
+        &mut self,
+        handle: &str,
+        node_type: &str,
+        args: Option<&ArgSlice<'_, 'a>>,
+    ) {
+        let _ = writeln!(
+            self.stream,
+            "Create {} {}",
+            quote(handle),
+            quote(node_type)
+        );
+        self.ctx.trace_create(handle, node_type, &[]);
+        self.ctx.record_create(handle, node_type);
+
+        if let Some(args) = args {
+            self.set_attribute(handle, args);
+        }
+    }
+
+    /// See [`Context::delete()`].
+    ///
+    /// The `"recursive"` argument is honored; other arguments are ignored.
+    pub fn delete(&mut self, handle: &str, args: Option<&ArgSlice<'_, 'a>>) {
+        let recursive =
+            crate::argument::get_integer_arg(args, "recursive").unwrap_or(0);
+
+        let _ = writeln!(
+            self.stream,
+            "Delete {} \"recursive\" \"int\" 1 {}",
+            quote(handle),
+            recursive
+        );
+        self.ctx.trace_delete(handle);
+        self.ctx.graph().delete(handle, 0 != recursive);
+    }
+
+    /// See [`Context::set_attribute()`].
+    pub fn set_attribute(&mut self, handle: &str, args: &ArgSlice<'_, 'a>) {
+        self.set_attribute_impl("SetAttribute", handle, None, args);
+    }
+
+    /// See [`Context::set_attribute_at_time()`].
+    pub fn set_attribute_at_time(
+        &mut self,
+        handle: &str,
+        time: f64,
+        args: &ArgSlice<'_, 'a>,
+    ) {
+        self.set_attribute_impl("SetAttributeAtTime", handle, Some(time), args);
+    }
+
+    /// See [`Context::delete_attribute()`].
+    pub fn delete_attribute(&mut self, handle: &str, name: &str) {
+        let _ = writeln!(
+            self.stream,
+            "DeleteAttribute {} {}",
+            quote(handle),
+            quote(name)
+        );
+        self.ctx.trace_delete_attribute(handle, name);
+        self.ctx.graph().delete_attribute(handle, name);
+    }
+
+    /// See [`Context::connect()`].
+    ///
+    /// The `"strength"` and `"priority"` arguments are honored; other
+    /// arguments are ignored.
+    pub fn connect(
+        &mut self,
+        from: &str,
+        from_attr: Option<&str>,
+        to: &str,
+        to_attr: &str,
+        args: Option<&ArgSlice<'_, 'a>>,
+    ) {
+        let from_attr = from_attr.unwrap_or("");
+        let strength =
+            crate::argument::get_integer_arg(args, "strength").unwrap_or(0);
+
+        let honored = args
+            .into_iter()
+            .flatten()
+            .filter(|arg| {
+                matches!(arg.name(), "strength" | "priority")
+                    && Type::Integer == arg.data.type_()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let (_, _, params) = get_c_param_vec(Some(&honored));
+
+        let _ = write!(
+            self.stream,
+            "Connect {} {} {} {}",
+            quote(from),
+            quote(from_attr),
+            quote(to),
+            quote(to_attr)
+        );
+        for param in &params {
+            write_param(&mut self.stream, param);
+        }
+        self.stream.push('\n');
+
+        self.ctx
+            .trace_connect("Connect", from, from_attr, to, to_attr, &params);
+        self.ctx
+            .graph()
+            .connect(from, from_attr, to, to_attr, strength);
+    }
+
+    /// See [`Context::disconnect()`].
+    pub fn disconnect(
+        &mut self,
+        from: &str,
+        from_attr: Option<&str>,
+        to: &str,
+        to_attr: &str,
+    ) {
+        let from_attr = from_attr.unwrap_or("");
+
+        let _ = writeln!(
+            self.stream,
+            "Disconnect {} {} {} {}",
+            quote(from),
+            quote(from_attr),
+            quote(to),
+            quote(to_attr)
+        );
+        self.ctx
+            .trace_connect("Disconnect", from, from_attr, to, to_attr, &[]);
+        self.ctx.graph().disconnect(from, from_attr, to, to_attr);
+    }
+
+    /// Returns the ɴsɪ stream recorded so far.
+    pub fn stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Sends the recorded edits to the renderer and clears the buffer.
+    ///
+    /// This is called automatically when the closure passed to
+    /// [`Context::batch()`] returns.
+    pub fn flush(&mut self) {
+        if self.stream.is_empty() {
+            return;
+        }
+
+        self.ctx.evaluate_buffer(self.stream.as_bytes());
+
+        self.stream.clear();
+    }
+
+    fn set_attribute_impl(
+        &mut self,
+        command: &str,
+        handle: &str,
+        time: Option<f64>,
+        args: &ArgSlice<'_, 'a>,
+    ) {
+        let args = self.ctx.check_attributes(handle, args);
+        let (direct, streamed): (Vec<_>, Vec<_>) =
+            args.iter().cloned().partition(needs_direct_call);
+
+        if !streamed.is_empty() {
+            let (_, _, params) = get_c_param_vec(Some(&streamed));
+
+            self.ctx.trace_set_attribute(handle, time, &params);
+            self.ctx.record_attributes(handle, time, &streamed);
+
+            write_set_attribute(
+                &mut self.stream,
+                command,
+                handle,
+                time,
+                &params,
+            );
+            self.stream.push('\n');
+        }
+
+        if !direct.is_empty() {
+            self.flush();
+
+            match time {
+                Some(time) => {
+                    self.ctx.set_attribute_at_time(handle, time, &direct)
+                }
+                None => self.ctx.set_attribute(handle, &direct),
+            }
+        }
+    }
+}
+
+// Whether `arg` can not be written to the stream and has to be passed to the
+// renderer directly.
+fn needs_direct_call(arg: &Arg) -> bool {
+    let type_ = arg.data.type_();
+    let scalars = arg.data.len() * type_.component_count();
+    let data = arg.data.as_c_ptr();
+
+    // Safe as the data of an argument has `scalars` values of its type.
+    let is_finite = match type_ {
+        Type::Integer | Type::String | Type::Reference => true,
+        Type::Double | Type::DoubleMatrix => {
+            unsafe { std::slice::from_raw_parts(data as *const f64, scalars) }
+                .iter()
+                .all(|value| value.is_finite())
+        }
+        _ => unsafe { std::slice::from_raw_parts(data as *const f32, scalars) }
+            .iter()
+            .all(|value| value.is_finite()),
+    };
+
+    Type::Reference == type_
+        || !is_finite
+        || 0 != arg.flags
+            & (NSIParamFlags::PerFace
+                | NSIParamFlags::PerVertex
+                | NSIParamFlags::InterpolateLinear)
+                .bits()
+}
+
+pub(crate) fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Writes `command`, e.g. SetAttribute, with `handle`, the optional `time`
+// and `params` in ɴsɪ stream syntax. Shared with Context's tracing.
+pub(crate) fn write_set_attribute(
+    stream: &mut String,
+    command: &str,
+    handle: &str,
+    time: Option<f64>,
+    params: &[NSIParam],
+) {
+    stream.push_str(command);
+    stream.push(' ');
+    stream.push_str(&quote(handle));
+    if let Some(time) = time {
+        let _ = write!(stream, " {:?}", time);
+    }
+    for param in params {
+        write_param(stream, param);
+    }
+}
+
+// Writes `param`, preceded by a space, in ɴsɪ stream syntax.
+//
+// The data of `param` must be valid, i.e. what get_c_param_vec() or
+// Attribute::as_param() return.
+pub(crate) fn write_param(stream: &mut String, param: &NSIParam) {
+    // Safe as the name of a param is a valid C string.
+    let name = unsafe { CStr::from_ptr(param.name) }.to_string_lossy();
+    let _ = write!(stream, " {}", quote(&name));
+
+    let Some(type_) = Type::from_nsi_type(param.type_ as _) else {
+        return;
+    };
+    let type_name = match type_ {
+        Type::Float => "float",
+        Type::Double => "double",
+        Type::Integer => "int",
+        Type::String => "string",
+        Type::Color => "color",
+        Type::Point => "point",
+        Type::Vector => "vector",
+        Type::Normal => "normal",
+        Type::Matrix => "matrix",
+        Type::DoubleMatrix => "doublematrix",
+        Type::Reference => "pointer",
+    };
+    let count = param.count;
+
+    let array_length = if 0
+        != param.flags & NSIParamFlags::IsArray.bits() as c_int
+    {
+        let array_length = param.arraylength.max(1) as usize;
+        let _ =
+            write!(stream, " \"{}[{}]\" {}", type_name, array_length, count);
+        array_length
+    } else {
+        let _ = write!(stream, " \"{}\" {}", type_name, count);
+        1
+    };
+
+    let data = param.data;
+    let scalars = count * array_length * type_.component_count();
+
+    stream.push_str(" [");
+    // Safe as the data of a param has `scalars` values of its type.
+    match type_ {
+        Type::Double | Type::DoubleMatrix => {
+            unsafe { std::slice::from_raw_parts(data as *const f64, scalars) }
+                .iter()
+                .for_each(|value| {
+                    let _ = write!(stream, " {:?}", value);
+                })
+        }
+        Type::Integer => {
+            unsafe { std::slice::from_raw_parts(data as *const i32, scalars) }
+                .iter()
+                .for_each(|value| {
+                    let _ = write!(stream, " {}", value);
+                })
+        }
+        Type::String => unsafe {
+            std::slice::from_raw_parts(data as *const *const c_char, scalars)
+        }
+        .iter()
+        .for_each(|&value| {
+            let _ = write!(
+                stream,
+                " {}",
+                quote(&unsafe { CStr::from_ptr(value) }.to_string_lossy())
+            );
+        }),
+        Type::Reference => unsafe {
+            std::slice::from_raw_parts(data as *const *const c_void, scalars)
+        }
+        .iter()
+        .for_each(|value| {
+            let _ = write!(stream, " {:p}", *value);
+        }),
+        _ => unsafe { std::slice::from_raw_parts(data as *const f32, scalars) }
+            .iter()
+            .for_each(|value| {
+                let _ = write!(stream, " {:?}", value);
+            }),
+    }
+    stream.push_str(" ]");
+}
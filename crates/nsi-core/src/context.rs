@@ -7,10 +7,16 @@ use null_terminated_str::NullTerminatedStr;
 use rclite::Arc;
 #[allow(unused_imports)]
 use std::{
+    collections::HashMap,
     ffi::{c_char, CStr, CString},
+    hash::{Hash, Hasher},
     marker::PhantomData,
     ops::Drop,
     os::raw::{c_int, c_void},
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+        Condvar, Mutex, MutexGuard,
+    },
 };
 use ustr::Ustr;
 
@@ -19,9 +25,33 @@ use ustr::Ustr;
 ///
 /// We wrap this in an [`Arc`] in [`Context`] to make sure drop() is only
 /// called when the last clone ceases existing.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Debug)]
 struct InnerContext<'a> {
     context: NSIContext,
+    // Whether NSIEnd() is called on drop. False for contexts the renderer
+    // passes to a callback.
+    owned: bool,
+    // Whether an interactive render is running.
+    interactive: AtomicBool,
+    // Whether a render started on this context is running. render_status()
+    // clears this and notifies render_stopped once the render has stopped.
+    render_running: Mutex<bool>,
+    render_stopped: Condvar,
+    // Whether renders are started in progressive mode by default.
+    progressive: AtomicBool,
+    // The AttributeCheck applied in set_attribute*().
+    attribute_check: AtomicU8,
+    // Mirror of the nodes & connections made through this context.
+    graph: Mutex<graph::Graph>,
+    // Whether API calls are sent to the trace_sink.
+    trace: AtomicBool,
+    // Receives the traced API calls. If None, they are report()ed.
+    trace_sink: Mutex<Option<TraceSink<'a>>>,
+    // Passed to the renderer as "errorhandlerdata" if the context was
+    // created with an "errorhandler".
+    error_sink: Option<Box<ErrorSink>>,
+    #[cfg(debug_assertions)]
+    render_tracker: RenderTracker,
     // _marker needs to be invariant in 'a.
     // See "Making a struct outlive a parameter given to a method of
     // that struct": https://stackoverflow.com/questions/62374326/
@@ -31,10 +61,158 @@ struct InnerContext<'a> {
 unsafe impl<'a> Send for InnerContext<'a> {}
 unsafe impl<'a> Sync for InnerContext<'a> {}
 
+impl<'a> InnerContext<'a> {
+    #[inline]
+    fn new(context: NSIContext, error_sink: Option<Box<ErrorSink>>) -> Self {
+        Self {
+            context,
+            owned: true,
+            error_sink,
+            interactive: AtomicBool::new(false),
+            render_running: Mutex::new(false),
+            render_stopped: Condvar::new(),
+            progressive: AtomicBool::new(false),
+            attribute_check: AtomicU8::new(AttributeCheck::Off as _),
+            graph: Mutex::new(graph::Graph::default()),
+            trace: AtomicBool::new(false),
+            trace_sink: Mutex::new(None),
+            #[cfg(debug_assertions)]
+            render_tracker: RenderTracker::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn set_render_stopped(&self) {
+        *self.render_running.lock().unwrap() = false;
+        self.render_stopped.notify_all();
+    }
+
+    /// A context the renderer owns, e.g. one it passes to a procedural.
+    ///
+    /// This does not allocate and dropping it does not end the context.
+    #[inline]
+    fn borrowed(context: NSIContext) -> Self {
+        let mut inner = Self::new(context, None);
+        inner.owned = false;
+
+        inner
+    }
+}
+
+impl<'a> PartialEq for InnerContext<'a> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.context == other.context
+    }
+}
+
+impl<'a> Eq for InnerContext<'a> {}
+
+impl<'a> Hash for InnerContext<'a> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.context.hash(state);
+    }
+}
+
 impl<'a> Drop for InnerContext<'a> {
     #[inline]
     fn drop(&mut self) {
-        NSI_API.NSIEnd(self.context);
+        if self.owned {
+            #[cfg(debug_assertions)]
+            self.render_tracker.check();
+
+            NSI_API.NSIEnd(self.context);
+        }
+    }
+}
+
+/// How a [`Context`] refers to its [`InnerContext`].
+#[derive(Debug)]
+enum InnerRef<'a> {
+    Owned(Arc<InnerContext<'a>>),
+    // An InnerContext::borrowed() on the stack of a callback trampoline.
+    Borrowed(*const InnerContext<'a>),
+}
+
+impl<'a> std::ops::Deref for InnerRef<'a> {
+    type Target = InnerContext<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            InnerRef::Owned(inner) => inner,
+            // The trampoline outlives the callback it passes the context to.
+            InnerRef::Borrowed(inner) => unsafe { &**inner },
+        }
+    }
+}
+
+// The closure passed to Context::set_trace_sink().
+struct TraceSink<'a>(Box<dyn FnTrace<'a>>);
+
+impl std::fmt::Debug for TraceSink<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TraceSink")
+    }
+}
+
+/// Sits between the renderer and the [`FnError`] closure of a context.
+///
+/// This counts warnings & errors and allows muting them while the context
+/// probes the renderer, e.g. in
+/// [`supports_node_type()`](Context::supports_node_type()).
+#[derive(Debug)]
+struct ErrorSink {
+    // The Box<dyn FnError> passed as "errorhandler".
+    fn_error: *const c_void,
+    errors: AtomicUsize,
+    muted: AtomicBool,
+}
+
+/// Keeps track of what happened on a context in debug builds so we can warn
+/// about common mistakes when it is dropped.
+///
+/// The warnings are sent to the `"errorhandler"` passed to
+/// [`Context::new()`] or, if there is none, to the [`log`] crate.
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+struct RenderTracker {
+    error_handler: AtomicPtr<c_void>,
+    render_started: AtomicBool,
+    render_waited: AtomicBool,
+    output_driver_created: AtomicBool,
+}
+
+#[cfg(debug_assertions)]
+impl RenderTracker {
+    fn check(&self) {
+        if self.render_started.load(Ordering::Relaxed) {
+            if !self.render_waited.load(Ordering::Relaxed) {
+                self.warn(
+                    "A render was started on this context but never waited \
+                    for or stopped. Call render_control() with Action::Wait \
+                    before dropping the context.",
+                );
+            }
+        } else if self.output_driver_created.load(Ordering::Relaxed) {
+            self.warn(
+                "Output drivers were created on this context but no render \
+                was ever started. Call render_control() with Action::Start.",
+            );
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        let payload = self.error_handler.load(Ordering::Relaxed);
+
+        if payload.is_null() {
+            log::warn!("{}", message);
+        } else {
+            let fn_error = unsafe { &*(payload as *const Box<dyn FnError>) };
+            fn_error(log::Level::Warn, 0, message);
+        }
     }
 }
 
@@ -56,11 +234,55 @@ impl<'a> Drop for InnerContext<'a> {
 /// use a context directly this is not an issue but when you want to reference
 /// it somewhere the same rules as with all references apply.
 ///
+/// Attribute data that is kept around together with a stored context can
+/// be held as [`OwnedArg`]s and passed to
+/// [`set_attribute_owned()`](Context::set_attribute_owned()).
+///
+/// ## Debugging
+/// In debug builds a context warns, when it is dropped, if a render was
+/// started but never waited for or if output drivers were created but no
+/// render was ever started. The warning goes to the `"errorhandler"` the
+/// context was created with or to the [`log`] crate if there is none.
+/// In release builds this tracking is compiled out.
+///
 /// ## Further Reading
 /// See the [ɴꜱɪ documentation on context
 /// handling](https://nsi.readthedocs.io/en/latest/c-api.html#context-handling).
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct Context<'a>(Arc<InnerContext<'a>>);
+#[derive(Debug)]
+pub struct Context<'a>(InnerRef<'a>);
+
+impl<'a> Clone for Context<'a> {
+    /// Clones the context.
+    ///
+    /// A clone of a context the renderer passed to a callback, e.g. to a
+    /// [procedural](crate::procedural!), does not end the context when
+    /// dropped. It is only valid as long as the renderer keeps that context
+    /// alive.
+    fn clone(&self) -> Self {
+        match &self.0 {
+            InnerRef::Owned(inner) => Self(InnerRef::Owned(inner.clone())),
+            InnerRef::Borrowed(inner) => Self(InnerRef::Owned(Arc::new(
+                InnerContext::borrowed(unsafe { &**inner }.context),
+            ))),
+        }
+    }
+}
+
+impl<'a> PartialEq for Context<'a> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        *self.0 == *other.0
+    }
+}
+
+impl<'a> Eq for Context<'a> {}
+
+impl<'a> Hash for Context<'a> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
 
 unsafe impl<'a> Send for Context<'a> {}
 unsafe impl<'a> Sync for Context<'a> {}
@@ -75,10 +297,7 @@ impl<'a> From<Context<'a>> for NSIContext {
 impl<'a> From<NSIContext> for Context<'a> {
     #[inline]
     fn from(context: NSIContext) -> Self {
-        Self(Arc::new(InnerContext {
-            context,
-            _marker: PhantomData,
-        }))
+        Self(InnerRef::Owned(Arc::new(InnerContext::new(context, None))))
     }
 }
 
@@ -99,6 +318,7 @@ impl<'a> Context<'a> {
     /// # Error
     /// If this method fails for some reason, it returns [`None`].
     #[inline]
+    #[must_use]
     pub fn new(args: Option<&ArgSlice<'_, 'a>>) -> Option<Self> {
         let (_, _, mut args_out) = get_c_param_vec(args);
 
@@ -107,28 +327,40 @@ impl<'a> Context<'a> {
                 as extern "C" fn(*mut c_void, c_int, c_int, *const c_char),
         );
 
-        if let Some(args) = args {
-            if let Some(arg) = args
-                .iter()
-                .find(|arg| Ustr::from("errorhandler") == arg.name)
-            {
-                args_out.push(nsi_sys::NSIParam {
-                    name: Ustr::from("errorhandler").as_char_ptr(),
-                    data: &fn_pointer as *const _ as _,
-                    type_: NSIType::Pointer as _,
-                    arraylength: 0,
-                    count: 1,
-                    flags: 0,
-                });
-                args_out.push(nsi_sys::NSIParam {
-                    name: Ustr::from("errorhandlerdata").as_char_ptr(),
-                    data: &arg.data.as_c_ptr() as *const _ as _,
-                    type_: NSIType::Pointer as _,
-                    arraylength: 1,
-                    count: 1,
-                    flags: 0,
-                });
-            }
+        let error_sink = args
+            .and_then(|args| {
+                args.iter()
+                    .find(|arg| Ustr::from("errorhandler") == arg.name)
+            })
+            .map(|arg| {
+                Box::new(ErrorSink {
+                    fn_error: arg.data.as_c_ptr(),
+                    errors: AtomicUsize::new(0),
+                    muted: AtomicBool::new(false),
+                })
+            });
+        // This needs to live until NSIBegin() was called.
+        let error_sink_ptr: *const c_void = error_sink
+            .as_deref()
+            .map_or(std::ptr::null(), |error_sink| error_sink as *const _ as _);
+
+        if error_sink.is_some() {
+            args_out.push(nsi_sys::NSIParam {
+                name: Ustr::from("errorhandler").as_char_ptr(),
+                data: &fn_pointer as *const _ as _,
+                type_: NSIType::Pointer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            });
+            args_out.push(nsi_sys::NSIParam {
+                name: Ustr::from("errorhandlerdata").as_char_ptr(),
+                data: &error_sink_ptr as *const _ as _,
+                type_: NSIType::Pointer as _,
+                arraylength: 1,
+                count: 1,
+                flags: 0,
+            });
         }
 
         let context = NSI_API.NSIBegin(args_out.len() as _, args_out.as_ptr());
@@ -136,11 +368,106 @@ impl<'a> Context<'a> {
         if 0 == context {
             None
         } else {
-            Some(Self(Arc::new(InnerContext {
-                context,
-                _marker: PhantomData,
-            })))
+            let inner_context = InnerContext::new(context, error_sink);
+
+            #[cfg(debug_assertions)]
+            if let Some(arg) = args.and_then(|args| {
+                args.iter()
+                    .find(|arg| Ustr::from("errorhandler") == arg.name)
+            }) {
+                inner_context
+                    .render_tracker
+                    .error_handler
+                    .store(arg.data.as_c_ptr() as _, Ordering::Relaxed);
+            }
+
+            Some(Self(InnerRef::Owned(Arc::new(inner_context))))
+        }
+    }
+
+    /// Creates an ɴsɪ context like [`new()`](Context::new()) but explains
+    /// what went wrong if it can not.
+    ///
+    /// [`new()`](Context::new()) panics when the renderer library can not
+    /// be loaded. This returns a [`ContextError::LibraryNotFound`] instead.
+    /// It lists every location that was tried and why loading from there
+    /// failed. Its [`Display`](std::fmt::Display) output is meant to be
+    /// shown to users as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// match nsi::Context::new_or_explain(None) {
+    ///     Ok(ctx) => {
+    ///         // Build the scene and render.
+    ///     }
+    ///     Err(error) => eprintln!("{}", error),
+    /// }
+    /// ```
+    pub fn new_or_explain(
+        args: Option<&ArgSlice<'_, 'a>>,
+    ) -> Result<Self, ContextError> {
+        if let Err(error) = NSI_API_LOAD.as_ref() {
+            return Err(error.clone());
         }
+
+        Self::new(args).ok_or(ContextError::BeginFailed)
+    }
+
+    /// Returns a [`ContextBuilder`] for creating a context from named
+    /// options.
+    ///
+    /// Use [`new()`](Context::new()) for the simple case.
+    #[inline]
+    pub fn builder() -> ContextBuilder<'a> {
+        ContextBuilder::new()
+    }
+
+    /// Creates a context that writes the scene to an ɴsɪ stream instead of
+    /// rendering it.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` -- The file to write to. The special values `"stdout"`
+    ///   and `"stderr"` write to the resp. standard stream.
+    ///
+    /// * `format` -- Whether to write human-readable or binary ɴsɪ.
+    ///
+    /// * `compression` -- Whether to compress the stream.
+    ///
+    /// All combinations of `format` and `compression` are valid. A
+    /// compressed stream is a gzip file that the renderer reads back
+    /// transparently, e.g. via [`evaluate()`](Context::evaluate()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// let path = std::env::temp_dir().join("scene.nsi.gz");
+    ///
+    /// let ctx = nsi::Context::new_stream(
+    ///     path.to_str().unwrap(),
+    ///     nsi::StreamFormat::Binary,
+    ///     nsi::StreamCompression::Gzip,
+    /// )
+    /// .expect("Could not create ɴsɪ stream.");
+    /// ```
+    /// # Error
+    /// If this method fails for some reason, it returns [`None`].
+    #[inline]
+    #[must_use]
+    pub fn new_stream(
+        filename: &str,
+        format: StreamFormat,
+        compression: StreamCompression,
+    ) -> Option<Self> {
+        Self::new(Some(&[
+            nsi::string!("type", "apistream"),
+            nsi::string!("streamfilename", filename),
+            nsi::string!("streamformat", format.as_str()),
+            nsi::string!("streamcompression", compression.as_str()),
+        ]))
     }
 
     /// Creates a new node.
@@ -165,6 +492,11 @@ impl<'a> Context<'a> {
     /// * `args` -- A [`slice`](std::slice) of optional [`Arg`] arguments.
     ///   *There are no optional arguments defined as of now*.
     ///
+    /// The built-in nodes [`.root`](node::ROOT), [`.global`](node::GLOBAL)
+    /// and [`.all`](node::ALL) always exist and can not be created. Trying
+    /// to is [reported](Context::report()) as an error and nothing is sent
+    /// to the renderer.
+    ///
     /// ```
     /// # use nsi_core as nsi;
     /// // Create a context to send the scene to.
@@ -180,9 +512,23 @@ impl<'a> Context<'a> {
         node_type: &str,
         args: Option<&ArgSlice<'_, 'a>>,
     ) {
+        if graph::BUILT_IN.contains(&handle) {
+            self.report(
+                log::Level::Error,
+                &format!("Can not create '{}'; it is a built-in node.", handle),
+            );
+            return;
+        }
+
         let handle = HandleString::from(handle);
         let node_type = Ustr::from(node_type);
-        let (args_len, args_ptr, _args_out) = get_c_param_vec(args);
+        let (args_len, args_ptr, args_out) = get_c_param_vec(args);
+
+        self.trace_create(handle.as_str(), node_type.as_str(), &args_out);
+        self.record_create(handle.as_str(), node_type.as_str());
+        if let Some(args) = args {
+            self.record_attributes(handle.as_str(), None, args);
+        }
 
         NSI_API.NSICreate(
             self.0.context,
@@ -193,6 +539,73 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Calls `f` with a context for `context`, a context the renderer
+    /// passed to a callback.
+    ///
+    /// This does not allocate and does not end `context`.
+    pub(crate) fn with_borrowed<R>(
+        context: NSIContext,
+        f: impl FnOnce(&Context) -> R,
+    ) -> R {
+        let inner = InnerContext::borrowed(context);
+
+        f(&Context(InnerRef::Borrowed(&inner)))
+    }
+
+    #[inline]
+    pub(crate) fn graph(&self) -> MutexGuard<'_, graph::Graph> {
+        self.0.graph.lock().unwrap()
+    }
+
+    /// Submits an in-memory ɴsɪ stream to the renderer.
+    pub(crate) fn evaluate_buffer(&self, buffer: &[u8]) {
+        let apistream = Ustr::from("apistream");
+        let buffer_ptr = buffer.as_ptr() as *const c_void;
+        let size = buffer.len() as c_int;
+
+        let params = [
+            nsi_sys::NSIParam {
+                name: Ustr::from("type").as_char_ptr(),
+                data: &apistream.as_char_ptr() as *const _ as _,
+                type_: NSIType::String as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            },
+            nsi_sys::NSIParam {
+                name: Ustr::from("buffer").as_char_ptr(),
+                data: &buffer_ptr as *const _ as _,
+                type_: NSIType::Pointer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            },
+            nsi_sys::NSIParam {
+                name: Ustr::from("size").as_char_ptr(),
+                data: &size as *const _ as _,
+                type_: NSIType::Integer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            },
+        ];
+
+        NSI_API.NSIEvaluate(self.0.context, params.len() as _, params.as_ptr());
+    }
+
+    /// Records a newly created node in the scene mirror.
+    pub(crate) fn record_create(&self, handle: &str, node_type: &str) {
+        #[cfg(debug_assertions)]
+        if node::OUTPUT_DRIVER == node_type {
+            self.0
+                .render_tracker
+                .output_driver_created
+                .store(true, Ordering::Relaxed);
+        }
+
+        self.0.graph.lock().unwrap().create(handle, node_type);
+    }
+
     /// This function deletes a node from the scene. All connections to and from
     /// the node are also deleted.
     ///
@@ -224,6 +637,12 @@ impl<'a> Context<'a> {
         let handle = HandleString::from(handle);
         let (args_len, args_ptr, _args_out) = get_c_param_vec(args);
 
+        self.trace_delete(handle.as_str());
+        self.0.graph.lock().unwrap().delete(
+            handle.as_str(),
+            get_integer_arg(args, "recursive").is_some_and(|r| 0 != r),
+        );
+
         NSI_API.NSIDelete(
             self.0.context,
             handle.as_char_ptr(),
@@ -255,7 +674,12 @@ impl<'a> Context<'a> {
     #[inline]
     pub fn set_attribute(&self, handle: &str, args: &ArgSlice<'_, 'a>) {
         let handle = HandleString::from(handle);
-        let (args_len, args_ptr, _args_out) = get_c_param_vec(Some(args));
+        let args = self.check_attributes(handle.as_str(), args);
+        let args = args.as_ref();
+        let (args_len, args_ptr, args_out) = get_c_param_vec(Some(args));
+
+        self.trace_set_attribute(handle.as_str(), None, &args_out);
+        self.record_attributes(handle.as_str(), None, args);
 
         NSI_API.NSISetAttribute(
             self.0.context,
@@ -265,111 +689,922 @@ impl<'a> Context<'a> {
         );
     }
 
-    /// This function sets time-varying attributes (i.e. motion blurred).
-    ///
-    /// The `time` argument specifies at which time the attribute is being
-    /// defined.
+    /// Sets attributes on a node from [`OwnedArg`]s.
     ///
-    /// It is not required to set time-varying attributes in any
-    /// particular order. In most uses, attributes that are motion blurred must
-    /// have the same specification throughout the time range.
+    /// This is the same as [`set_attribute()`](Context::set_attribute())
+    /// but the arguments own their data. They can thus be collected ahead of
+    /// time, e.g. in a struct that also stores the context, without tying
+    /// them to the lifetime of whatever they were computed from.
     ///
-    /// A notable  exception is the `P` attribute on [`particles`
-    /// node](`node::PARTICLES`) which can be of different size for each
-    /// time step because of appearing or disappearing particles. Setting an
-    /// attribute using this function replaces any value previously set by
-    /// [`set_attribute()`](Context::set_attribute()).
+    /// The renderer copies all data before this returns. The arguments are
+    /// dropped afterwards.
     ///
     /// # Arguments
     ///
     /// * `handle` -- A handle to a node previously created with
     ///   [`create()`](Context::create()).
     ///
-    /// * `time` -- The time at which to set the value.
+    /// * `args` -- The arguments to set.
     ///
-    /// * `args` -- A [`slice`](std::slice) of optional [`Arg`] arguments.
-    #[inline]
-    pub fn set_attribute_at_time(
-        &self,
-        handle: &str,
-        time: f64,
-        args: &ArgSlice<'_, 'a>,
-    ) {
-        let handle = HandleString::from(handle);
-        let (args_len, args_ptr, _args_out) = get_c_param_vec(Some(args));
-
-        NSI_API.NSISetAttributeAtTime(
-            self.0.context,
-            handle.as_char_ptr(),
-            time,
-            args_len,
-            args_ptr,
-        );
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// struct Camera {
+    ///     ctx: nsi::Context<'static>,
+    ///     pending: Vec<nsi::OwnedArg>,
+    /// }
+    ///
+    /// impl Camera {
+    ///     fn set_fov(&mut self, fov: f32) {
+    ///         self.pending.push(nsi::OwnedArg::new("fov", fov.into()));
+    ///     }
+    ///
+    ///     fn flush(&mut self) {
+    ///         self.ctx.set_attribute_owned(
+    ///             "camera",
+    ///             std::mem::take(&mut self.pending),
+    ///         );
+    ///     }
+    /// }
+    /// ```
+    pub fn set_attribute_owned(&self, handle: &str, args: Vec<OwnedArg>) {
+        let args = args.iter().map(OwnedArg::as_arg).collect::<Vec<_>>();
+        self.set_attribute(handle, &args);
     }
 
-    /// This function deletes any attribute with a name which matches
-    /// the `name` argument on the specified object. There is no way to
-    /// delete an attribute only for a specific time value.
+    /// Returns the value of the attribute `name` of the node `handle`.
     ///
-    /// Deleting an attribute resets it to its default value.
+    /// This reads the context's Rust-side mirror of the scene. The value is
+    /// the one last set through this context, e.g. with
+    /// [`set_attribute()`](Context::set_attribute()). It is not what the
+    /// renderer evaluates: defaults of attributes never set, values from
+    /// [`evaluate()`](Context::evaluate()) or connections driving the
+    /// attribute are not seen. For time-sampled attributes this is the
+    /// sample set last.
     ///
-    /// For example, after deleting the `transformationmatrix` attribute
-    /// on a [`transform` node](`node::TRANSFORM`), the transform will be an
-    /// identity. Deleting a previously set attribute on a [`shader`
-    /// node](`node::SHADER`) will default to whatever is declared inside
-    /// the shader.
+    /// A single value comes back as the scalar variant of
+    /// [`OwnedArgData`], even if it was set as a one-element array.
+    ///
+    /// Returns [`None`] if the attribute was never set, was deleted, or
+    /// holds references or callbacks. Also for arrays of more than 16
+    /// scalars unless the context was built with
+    /// [`mirror_data()`](crate::ContextBuilder::mirror_data()).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+    /// ctx.set_attribute("camera", &[nsi::float!("fov", 35.0)]);
+    ///
+    /// let fov = ctx.get_attribute("camera", "fov").unwrap();
+    /// assert_eq!(&nsi::OwnedArgData::Float(35.0), fov.data());
+    /// ```
+    pub fn get_attribute(&self, handle: &str, name: &str) -> Option<OwnedArg> {
+        self.graph()
+            .attributes(handle, name)
+            .last()
+            .and_then(graph::Attribute::to_owned_arg)
+    }
+
+    /// Sets attributes on a node from raw [`FfiParam`]s.
+    ///
+    /// This is an escape hatch for data that does not fit the argument
+    /// macros, e.g. when it is already laid out in memory the way the
+    /// renderer expects it. For everything else, use
+    /// [`set_attribute()`](Context::set_attribute()).
     ///
     /// # Arguments
     ///
     /// * `handle` -- A handle to a node previously created with
     ///   [`create()`](Context::create()).
     ///
-    /// * `name` -- The name of the attribute to be deleted/reset.
-    #[inline]
-    pub fn delete_attribute(&self, handle: &str, name: &str) {
+    /// * `params` -- The parameters to set as attributes.
+    ///
+    /// # Safety
+    ///
+    /// For each of the `params`:
+    ///
+    /// * `name` must point to a valid, null-terminated C string.
+    ///
+    /// * `type_` must be a valid [`NSIType`](nsi_sys::NSIType).
+    ///
+    /// * `data` must point to `count` × `arraylength` (or just `count`, if
+    ///   the [`IsArray`](nsi_sys::NSIParamFlags::IsArray) flag is not set)
+    ///   elements of `type_`. The data must be aligned for the element's
+    ///   scalar type, i.e. [`f32`], [`f64`], [`i32`] or a pointer. Strings
+    ///   are given as pointers to null-terminated C strings.
+    ///
+    /// * All pointers must stay valid for the duration of this call. The
+    ///   renderer copies the data, except for
+    ///   [`Pointer`](nsi_sys::NSIType::Pointer)s, which must stay valid for
+    ///   as long as the renderer may use them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("mesh", nsi::MESH, None);
+    ///
+    /// let nvertices = [4i32];
+    ///
+    /// unsafe {
+    ///     ctx.set_attribute_raw(
+    ///         "mesh",
+    ///         &[nsi::FfiParam {
+    ///             name: b"nvertices\0".as_ptr() as _,
+    ///             data: nvertices.as_ptr() as _,
+    ///             type_: nsi_sys::NSIType::Integer as _,
+    ///             arraylength: 0,
+    ///             count: nvertices.len() as _,
+    ///             flags: 0,
+    ///         }],
+    ///     );
+    /// }
+    /// ```
+    pub unsafe fn set_attribute_raw(&self, handle: &str, params: &[FfiParam]) {
         let handle = HandleString::from(handle);
-        let name = Ustr::from(name);
 
-        NSI_API.NSIDeleteAttribute(
+        self.trace_set_attribute(handle.as_str(), None, params);
+
+        {
+            let mut graph = self.0.graph.lock().unwrap();
+            let copy_all = graph.mirror_data;
+
+            for param in params {
+                if let Some(type_) = Type::from_nsi_type(param.type_ as _) {
+                    let array_length = if 0
+                        != param.flags & NSIParamFlags::IsArray.bits() as c_int
+                    {
+                        param.arraylength.max(1) as usize
+                    } else {
+                        1
+                    };
+
+                    graph.set_attribute(
+                        handle.as_str(),
+                        graph::Attribute::from_raw(
+                            CStr::from_ptr(param.name)
+                                .to_str()
+                                .unwrap_or_default()
+                                .into(),
+                            None,
+                            type_,
+                            array_length,
+                            param.count * array_length,
+                            param.flags as _,
+                            param.data,
+                            copy_all,
+                        ),
+                    );
+                }
+            }
+        }
+
+        NSI_API.NSISetAttribute(
             self.0.context,
             handle.as_char_ptr(),
-            name.as_char_ptr(),
+            params.len() as _,
+            params.as_ptr(),
         );
     }
 
-    /// Create a connection between two elements.
+    /// Sets a single [`Integer`] attribute on a node.
     ///
-    /// It is not an error to create a connection which already exists
-    /// or to remove a connection which does not exist but the nodes
-    /// on which the connection is performed must exist.
+    /// This is a shortcut for calling
+    /// [`set_attribute()`](Context::set_attribute()) with a single
+    /// [`integer!`](crate::integer) argument that skips building an
+    /// [`ArgSlice`].
     ///
     /// # Arguments
     ///
-    /// * `from` -- The handle of the node from which the connection is made.
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
     ///
-    /// * `from_attr` -- The name of the attribute from which the connection is
-    ///   made. If this is an empty string then the connection is made from the
-    ///   node instead of from a specific attribute of the node.
+    /// * `name` -- The name of the attribute.
     ///
-    ///   If this is `None` the node itself will be connected.
+    /// * `value` -- The value of the attribute.
     ///
-    /// * `to` -- The handle of the node to which the connection is made.
+    /// # Examples
     ///
-    /// * `to_attr` -- The name of the attribute to which the connection is
-    ///   made. If this is an empty string then the connection is made to the
-    ///   node instead of to a specific attribute of the node.
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("attributes", nsi::ATTRIBUTES, None);
+    /// ctx.set_integer("attributes", "visibility.camera", 0);
+    /// ```
+    #[inline]
+    pub fn set_integer(&self, handle: &str, name: &str, value: i32) {
+        self.set_single_attribute(
+            handle,
+            name,
+            Type::Integer,
+            &value as *const i32 as _,
+        );
+    }
+
+    /// Sets a single [`Float`] attribute on a node.
     ///
-    /// # Optional Arguments
+    /// This is a shortcut for calling
+    /// [`set_attribute()`](Context::set_attribute()) with a single
+    /// [`float!`](crate::float) argument.
     ///
-    /// * `"value"` -- This can be used to change the value of a node's
-    ///   attribute in some contexts. Refer to guidelines on inter-object
-    ///   visibility for more information about the utility of this parameter.
+    /// # Arguments
     ///
-    /// * `"priority"` ([`Integer`]) -- When connecting attribute nodes,
-    ///   indicates in which order the nodes should be considered when
-    ///   evaluating the value of an attribute.
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
     ///
-    /// * `"strength"` ([`Integer`]) -- A connection with a `strength` greater
+    /// * `name` -- The name of the attribute.
+    ///
+    /// * `value` -- The value of the attribute.
+    #[inline]
+    pub fn set_float(&self, handle: &str, name: &str, value: f32) {
+        self.set_single_attribute(
+            handle,
+            name,
+            Type::Float,
+            &value as *const f32 as _,
+        );
+    }
+
+    /// Sets a single [`String`] attribute on a node.
+    ///
+    /// This is a shortcut for calling
+    /// [`set_attribute()`](Context::set_attribute()) with a single
+    /// [`string!`](crate::string) argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `name` -- The name of the attribute.
+    ///
+    /// * `value` -- The value of the attribute.
+    #[inline]
+    pub fn set_string(&self, handle: &str, name: &str, value: &str) {
+        let value = CString::new(value).unwrap();
+        let value_ptr = value.as_ptr();
+
+        self.set_single_attribute(
+            handle,
+            name,
+            Type::String,
+            &value_ptr as *const *const c_char as _,
+        );
+    }
+
+    /// Sets a single [`Color`] attribute on a node.
+    ///
+    /// This is a shortcut for calling
+    /// [`set_attribute()`](Context::set_attribute()) with a single
+    /// [`color!`](crate::color) argument.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `name` -- The name of the attribute.
+    ///
+    /// * `value` -- The linear `rgb` value of the attribute.
+    #[inline]
+    pub fn set_color(&self, handle: &str, name: &str, value: [f32; 3]) {
+        self.set_single_attribute(
+            handle,
+            name,
+            Type::Color,
+            value.as_ptr() as _,
+        );
+    }
+
+    // Sends a single, non-array parameter straight to NSISetAttribute().
+    #[inline]
+    fn set_single_attribute(
+        &self,
+        handle: &str,
+        name: &str,
+        type_: Type,
+        data: *const c_void,
+    ) {
+        if let Some((check, node_type)) = self.attribute_check_for(handle) {
+            if !self.check_attribute(check, node_type, handle, name) {
+                return;
+            }
+        }
+
+        let handle = HandleString::from(handle);
+        let name = Ustr::from(name);
+
+        let param = nsi_sys::NSIParam {
+            name: name.as_char_ptr(),
+            data,
+            type_: type_ as _,
+            arraylength: 0,
+            count: 1,
+            flags: 0,
+        };
+
+        self.trace_set_attribute(
+            handle.as_str(),
+            None,
+            std::slice::from_ref(&param),
+        );
+        self.0
+            .graph
+            .lock()
+            .unwrap()
+            .set_attribute(handle.as_str(), unsafe {
+                graph::Attribute::from_raw(
+                    name, None, type_, 1, 1, 0, data, false,
+                )
+            });
+
+        NSI_API.NSISetAttribute(
+            self.0.context,
+            handle.as_char_ptr(),
+            1,
+            &param,
+        );
+    }
+
+    /// Sets how attribute names are checked by
+    /// [`set_attribute()`](Context::set_attribute()) and
+    /// [`set_attribute_at_time()`](Context::set_attribute_at_time()).
+    ///
+    /// The names are checked against the
+    /// [`default_attributes()`](NodeType::default_attributes()) of the node's
+    /// type. This catches typos like `"resolutoin"` early. The type is taken
+    /// from the context's mirror of the scene, so only nodes created through
+    /// this context are checked.
+    ///
+    /// Of the built-in nodes only [`.global`](node::GLOBAL) is checked.
+    /// Attributes on [`shader`](node::SHADER)s, on node types without known
+    /// attributes and on nodes of non-standard types are never checked. The
+    /// check also applies to [`set_integer()`](Context::set_integer()) and
+    /// the other single attribute setters. Note that the list of known
+    /// attributes is
+    /// not exhaustive and geometry accepts arbitrary primitive variables.
+    /// [`AttributeCheck::Deny`] is therefore best used on scenes that only
+    /// use the standard attributes.
+    ///
+    /// The default is [`AttributeCheck::Off`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.set_attribute_check(nsi::AttributeCheck::Warn);
+    ///
+    /// ctx.create("screen", nsi::SCREEN, None);
+    /// // Reports: unknown attribute 'resolutoin' on screen 'screen'.
+    /// ctx.set_attribute(
+    ///     "screen",
+    ///     &[nsi::integers!("resolutoin", &[640, 480]).array_len(2)],
+    /// );
+    /// ```
+    #[inline]
+    pub fn set_attribute_check(&self, check: AttributeCheck) {
+        self.0.attribute_check.store(check as _, Ordering::Relaxed);
+    }
+
+    /// Enables or disables tracing of the calls made on this context.
+    ///
+    /// While enabled, every [`create()`](Context::create()),
+    /// [`delete()`](Context::delete()),
+    /// [`set_attribute()`](Context::set_attribute()),
+    /// [`set_attribute_at_time()`](Context::set_attribute_at_time()),
+    /// [`delete_attribute()`](Context::delete_attribute()),
+    /// [`connect()`](Context::connect()) and
+    /// [`disconnect()`](Context::disconnect()) is described in one line of
+    /// ɴsɪ stream syntax, the same as [`Batch::stream()`], e.g.
+    /// `SetAttribute "mesh" "nvertices" "int" 1 [ 3 ]`. The line is sent to the
+    /// closure given to [`set_trace_sink()`](Context::set_trace_sink()) or,
+    /// if there is none, [reported](Context::report()) at
+    /// [`Trace`](log::Level::Trace) level.
+    ///
+    /// The calls still reach the renderer. This is handy to find out e.g.
+    /// why an object does not show up: a connection to the wrong slot or a
+    /// typo in a handle is easy to spot in the trace.
+    ///
+    /// Arguments are traced with all their values. Pointers, e.g.
+    /// [`Reference`](crate::Reference)s, are traced as their address with the
+    /// type `"pointer"`.
+    ///
+    /// # Performance
+    ///
+    /// While disabled, tracing costs next to nothing. While enabled, each
+    /// call allocates and formats a string and calls the sink. For scenes
+    /// with many thousands of nodes, or big meshes whose every vertex becomes
+    /// part of a line, this is noticeable; don't leave tracing on in
+    /// production.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.set_trace_sink(|call: &str| eprintln!("nsi: {}", call));
+    /// ctx.set_trace(true);
+    ///
+    /// // Prints `nsi: Create "plane" "plane"`.
+    /// ctx.create("plane", nsi::PLANE, None);
+    /// ```
+    #[inline]
+    pub fn set_trace(&self, enabled: bool) {
+        self.0.trace.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Sets the closure that receives the calls traced while
+    /// [`set_trace()`](Context::set_trace()) is enabled.
+    ///
+    /// This replaces any sink set before. The sink must not call back into
+    /// this context.
+    pub fn set_trace_sink<F>(&self, sink: F)
+    where
+        F: FnTrace<'a>,
+    {
+        *self.0.trace_sink.lock().unwrap() = Some(TraceSink(Box::new(sink)));
+    }
+
+    // Sends the line returned by `call` to the trace sink, if tracing is
+    // enabled.
+    fn trace<F>(&self, call: F)
+    where
+        F: FnOnce() -> std::string::String,
+    {
+        if !self.0.trace.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let call = call();
+
+        match &*self.0.trace_sink.lock().unwrap() {
+            Some(sink) => (sink.0)(&call),
+            None => self.report(log::Level::Trace, &call),
+        }
+    }
+
+    // The trace_*() methods trace one API call each. They are shared by all
+    // code paths making the resp. call, e.g. a Batch.
+
+    pub(crate) fn trace_create(
+        &self,
+        handle: &str,
+        node_type: &str,
+        params: &[NSIParam],
+    ) {
+        self.trace(|| {
+            let mut call = format!("Create \"{}\" \"{}\"", handle, node_type);
+            params
+                .iter()
+                .for_each(|param| batch::write_param(&mut call, param));
+
+            call
+        });
+    }
+
+    pub(crate) fn trace_delete(&self, handle: &str) {
+        self.trace(|| format!("Delete \"{}\"", handle));
+    }
+
+    // Traces SetAttribute or, with a time, SetAttributeAtTime.
+    pub(crate) fn trace_set_attribute(
+        &self,
+        handle: &str,
+        time: Option<f64>,
+        params: &[NSIParam],
+    ) {
+        self.trace(|| {
+            let mut call = std::string::String::new();
+            batch::write_set_attribute(
+                &mut call,
+                match time {
+                    Some(_) => "SetAttributeAtTime",
+                    None => "SetAttribute",
+                },
+                handle,
+                time,
+                params,
+            );
+
+            call
+        });
+    }
+
+    pub(crate) fn trace_delete_attribute(&self, handle: &str, name: &str) {
+        self.trace(|| format!("DeleteAttribute \"{}\" \"{}\"", handle, name));
+    }
+
+    // Traces Connect or Disconnect, given as `command`.
+    pub(crate) fn trace_connect(
+        &self,
+        command: &str,
+        from: &str,
+        from_attr: &str,
+        to: &str,
+        to_attr: &str,
+        params: &[NSIParam],
+    ) {
+        self.trace(|| {
+            let mut call = format!(
+                "{} \"{}\" \"{}\" \"{}\" \"{}\"",
+                command, from, from_attr, to, to_attr
+            );
+            params
+                .iter()
+                .for_each(|param| batch::write_param(&mut call, param));
+
+            call
+        });
+    }
+
+    // Applies the AttributeCheck to args. Returns the args to send.
+    pub(crate) fn check_attributes<'s, 'b>(
+        &self,
+        handle: &str,
+        args: &'s ArgSlice<'b, 'a>,
+    ) -> std::borrow::Cow<'s, ArgSlice<'b, 'a>> {
+        let Some((check, node_type)) = self.attribute_check_for(handle) else {
+            return args.into();
+        };
+
+        if args
+            .iter()
+            .all(|arg| node_type.accepts_attribute(arg.name()))
+        {
+            return args.into();
+        }
+
+        let args = args
+            .iter()
+            .filter(|arg| {
+                self.check_attribute(check, node_type, handle, arg.name())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        args.into()
+    }
+
+    // Returns the AttributeCheck to apply to attributes of `handle` and the
+    // type of the node. None if there is nothing to check.
+    fn attribute_check_for(
+        &self,
+        handle: &str,
+    ) -> Option<(AttributeCheck, NodeType)> {
+        let check = match self.0.attribute_check.load(Ordering::Relaxed) {
+            check if AttributeCheck::Warn as u8 == check => {
+                AttributeCheck::Warn
+            }
+            check if AttributeCheck::Deny as u8 == check => {
+                AttributeCheck::Deny
+            }
+            _ => return None,
+        };
+
+        // Only .global of the built-in nodes has attributes to check.
+        let node_type = match self.0.graph.lock().unwrap().node_type(handle) {
+            Some(node_type) => NodeType::from_name(node_type)?,
+            None if node::GLOBAL == handle => NodeType::Global,
+            None => return None,
+        };
+
+        match node_type {
+            NodeType::Shader => None,
+            // Nothing is known about these.
+            _ if node_type.default_attributes().is_empty() => None,
+            _ => Some((check, node_type)),
+        }
+    }
+
+    // Reports the attribute `name` if it is unknown. Returns whether to send
+    // it.
+    fn check_attribute(
+        &self,
+        check: AttributeCheck,
+        node_type: NodeType,
+        handle: &str,
+        name: &str,
+    ) -> bool {
+        if node_type.accepts_attribute(name) {
+            return true;
+        }
+
+        self.report(
+            match check {
+                AttributeCheck::Deny => log::Level::Error,
+                _ => log::Level::Warn,
+            },
+            &format!(
+                "unknown attribute '{}' on {} '{}'",
+                name, node_type, handle
+            ),
+        );
+
+        AttributeCheck::Deny != check
+    }
+
+    // Records attributes in the graph mirror.
+    #[inline]
+    pub(crate) fn record_attributes(
+        &self,
+        handle: &str,
+        time: Option<f64>,
+        args: &ArgSlice<'_, 'a>,
+    ) {
+        let mut graph = self.0.graph.lock().unwrap();
+        let copy_all = graph.mirror_data;
+
+        for attribute in args
+            .iter()
+            .filter_map(|arg| graph::Attribute::from_arg(arg, time, copy_all))
+        {
+            graph.set_attribute(handle, attribute);
+        }
+    }
+
+    /// This function sets time-varying attributes (i.e. motion blurred).
+    ///
+    /// The `time` argument specifies at which time the attribute is being
+    /// defined.
+    ///
+    /// It is not required to set time-varying attributes in any
+    /// particular order. In most uses, attributes that are motion blurred must
+    /// have the same specification throughout the time range.
+    ///
+    /// A notable  exception is the `P` attribute on [`particles`
+    /// node](`node::PARTICLES`) which can be of different size for each
+    /// time step because of appearing or disappearing particles. Setting an
+    /// attribute using this function replaces any value previously set by
+    /// [`set_attribute()`](Context::set_attribute()).
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `time` -- The time at which to set the value.
+    ///
+    /// * `args` -- A [`slice`](std::slice) of optional [`Arg`] arguments.
+    #[inline]
+    pub fn set_attribute_at_time(
+        &self,
+        handle: &str,
+        time: f64,
+        args: &ArgSlice<'_, 'a>,
+    ) {
+        let handle = HandleString::from(handle);
+        let args = self.check_attributes(handle.as_str(), args);
+        let args = args.as_ref();
+        let (args_len, args_ptr, args_out) = get_c_param_vec(Some(args));
+
+        self.trace_set_attribute(handle.as_str(), Some(time), &args_out);
+        self.record_attributes(handle.as_str(), Some(time), args);
+
+        NSI_API.NSISetAttributeAtTime(
+            self.0.context,
+            handle.as_char_ptr(),
+            time,
+            args_len,
+            args_ptr,
+        );
+    }
+
+    /// Sets the number of motion segments used for a motion blurred node.
+    ///
+    /// A node whose attributes were set at `n + 1` times via
+    /// [`set_attribute_at_time()`](Context::set_attribute_at_time()) moves
+    /// along `n` segments during the shutter interval. E.g. two samples, at
+    /// shutter open and close, describe linear motion and one segment. More
+    /// segments make curved motion, e.g. of a spinning wheel, follow its
+    /// path more closely. Deforming geometry needs the same number of
+    /// samples for all its motion blurred attributes.
+    ///
+    /// This writes `segments` to the node's `"motionblur.segments"`
+    /// attribute. Renderers that derive the segments from the samples
+    /// ignore it.
+    ///
+    /// If an attribute of the node was set at a number of times that does
+    /// not match `segments`, a warning is [reported](Context::report()).
+    /// Set the samples before calling this.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `segments` -- The number of motion segments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("mesh", nsi::MESH, None);
+    ///
+    /// for (time, y) in [(0.0, 0.0), (0.5, 0.5), (1.0, 0.0)] {
+    ///     ctx.set_attribute_at_time(
+    ///         "mesh",
+    ///         time,
+    ///         &[nsi::points!("P", &[0., y, 0., 1., y, 0., 1., 1. + y, 0.])],
+    ///     );
+    /// }
+    ///
+    /// // Three samples, two segments.
+    /// ctx.set_motion_segments("mesh", 2);
+    /// ```
+    pub fn set_motion_segments(&self, handle: &str, segments: u32) {
+        let mismatches = {
+            let graph = self.graph();
+
+            graph
+                .time_samples(handle)
+                .into_iter()
+                .filter(|(_, samples)| *samples != segments as usize + 1)
+                .collect::<Vec<_>>()
+        };
+
+        for (name, samples) in mismatches {
+            self.report(
+                log::Level::Warn,
+                &format!(
+                    "'{}' of '{}' has {} time samples but {} motion segments \
+                     need {}.",
+                    name,
+                    handle,
+                    samples,
+                    segments,
+                    segments + 1
+                ),
+            );
+        }
+
+        self.set_integer(handle, "motionblur.segments", segments as _);
+    }
+
+    /// This function deletes any attribute with a name which matches
+    /// the `name` argument on the specified object. There is no way to
+    /// delete an attribute only for a specific time value.
+    ///
+    /// Deleting an attribute resets it to its default value.
+    ///
+    /// For a motion blurred attribute, all time samples set with
+    /// [`set_attribute_at_time()`](Context::set_attribute_at_time()) are
+    /// removed. To change a single sample, set it again at the same time
+    /// instead.
+    ///
+    /// See also [`reset_to_default()`](Context::reset_to_default()), which
+    /// does the same under a name that says so.
+    ///
+    /// For example, after deleting the `transformationmatrix` attribute
+    /// on a [`transform` node](`node::TRANSFORM`), the transform will be an
+    /// identity. Deleting a previously set attribute on a [`shader`
+    /// node](`node::SHADER`) will default to whatever is declared inside
+    /// the shader.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `name` -- The name of the attribute to be deleted/reset.
+    #[inline]
+    pub fn delete_attribute(&self, handle: &str, name: &str) {
+        let handle = HandleString::from(handle);
+        let name = Ustr::from(name);
+
+        self.trace_delete_attribute(handle.as_str(), name.as_str());
+        self.0
+            .graph
+            .lock()
+            .unwrap()
+            .delete_attribute(handle.as_str(), name.as_str());
+
+        NSI_API.NSIDeleteAttribute(
+            self.0.context,
+            handle.as_char_ptr(),
+            name.as_char_ptr(),
+        );
+    }
+
+    /// Makes the node `handle` visible to the kinds of rays in `flags` and
+    /// invisible to all others.
+    ///
+    /// Only the `"visibility.*"` attributes that differ from
+    /// [`visibility_for()`](Context::visibility_for()) are set.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to an [`attributes`](node::ATTRIBUTES) node.
+    ///
+    /// * `flags` -- The rays the node is visible to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("attributes", nsi::ATTRIBUTES, None);
+    ///
+    /// // Only cast shadows.
+    /// ctx.set_visibility("attributes", nsi::VisibilityFlags::SHADOW);
+    /// ```
+    pub fn set_visibility(&self, handle: &str, flags: VisibilityFlags) {
+        let changed = self.visibility_for(handle) ^ flags;
+
+        let args = changed
+            .iter()
+            .map(|flag| {
+                Arg::new(
+                    flag.attribute_name(),
+                    ArgData::from(Integer::new(flags.contains(flag) as _)),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if !args.is_empty() {
+            self.set_attribute(handle, &args);
+        }
+    }
+
+    /// Returns the kinds of rays the node `handle` is visible to.
+    ///
+    /// This is read from the context's mirror of the scene. A
+    /// `"visibility.*"` attribute that was never set counts as visible. The
+    /// overall `"visibility"` attribute and attributes set via
+    /// [`evaluate()`](Context::evaluate()) are not taken into account.
+    pub fn visibility_for(&self, handle: &str) -> VisibilityFlags {
+        let graph = self.graph();
+
+        VisibilityFlags::all()
+            .iter()
+            .filter(|flag| {
+                graph
+                    .integer_attribute(handle, flag.attribute_name())
+                    .is_none_or(|visible| 0 != visible)
+            })
+            .collect()
+    }
+
+    /// Resets the attribute `name` on a node to its default value.
+    ///
+    /// This is [`delete_attribute()`](Context::delete_attribute()) under a
+    /// name that describes what happens. E.g. resetting the
+    /// `"transformationmatrix"` of a [`transform`](node::TRANSFORM) makes it
+    /// an identity. Resetting a parameter of a [`shader`](node::SHADER) makes
+    /// it use the default declared inside the shader.
+    ///
+    /// All time samples of a motion blurred attribute are reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()).
+    ///
+    /// * `name` -- The name of the attribute to reset.
+    #[inline]
+    pub fn reset_to_default(&self, handle: &str, name: &str) {
+        self.delete_attribute(handle, name);
+    }
+
+    /// Create a connection between two elements.
+    ///
+    /// It is not an error to create a connection which already exists
+    /// or to remove a connection which does not exist but the nodes
+    /// on which the connection is performed must exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` -- The handle of the node from which the connection is made.
+    ///
+    /// * `from_attr` -- The name of the attribute from which the connection is
+    ///   made. If this is an empty string then the connection is made from the
+    ///   node instead of from a specific attribute of the node.
+    ///
+    ///   If this is `None` the node itself will be connected.
+    ///
+    /// * `to` -- The handle of the node to which the connection is made.
+    ///
+    /// * `to_attr` -- The name of the attribute to which the connection is
+    ///   made. If this is an empty string then the connection is made to the
+    ///   node instead of to a specific attribute of the node.
+    ///
+    /// # Optional Arguments
+    ///
+    /// * `"value"` -- This can be used to change the value of a node's
+    ///   attribute in some contexts. Refer to guidelines on inter-object
+    ///   visibility for more information about the utility of this parameter.
+    ///
+    /// * `"priority"` ([`Integer`]) -- When connecting attribute nodes,
+    ///   indicates in which order the nodes should be considered when
+    ///   evaluating the value of an attribute.
+    ///
+    /// * `"strength"` ([`Integer`]) -- A connection with a `strength` greater
     ///   than `0` will *block* the progression of a recursive
     ///   [`delete()`](Context::delete()).
     #[inline]
@@ -385,7 +1620,23 @@ impl<'a> Context<'a> {
         let from_attr = Ustr::from(from_attr.unwrap_or(""));
         let to = HandleString::from(to);
         let to_attr = Ustr::from(to_attr);
-        let (args_len, args_ptr, _args_out) = get_c_param_vec(args);
+        let (args_len, args_ptr, args_out) = get_c_param_vec(args);
+
+        self.trace_connect(
+            "Connect",
+            from.as_str(),
+            from_attr.as_str(),
+            to.as_str(),
+            to_attr.as_str(),
+            &args_out,
+        );
+        self.0.graph.lock().unwrap().connect(
+            from.as_str(),
+            from_attr.as_str(),
+            to.as_str(),
+            to_attr.as_str(),
+            get_integer_arg(args, "strength").unwrap_or_default(),
+        );
 
         NSI_API.NSIConnect(
             self.0.context,
@@ -398,6 +1649,36 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Connects the node `handle` to the `"objects"` of the scene's
+    /// [`.root`](node::ROOT).
+    ///
+    /// This is the same as
+    /// `connect(handle, None, nsi::ROOT, "objects", None)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("plane", nsi::PLANE, None);
+    /// ctx.attach_to_root("plane");
+    /// ```
+    #[inline]
+    pub fn attach_to_root(&self, handle: &str) {
+        self.attach_to_root_slot(handle, "objects");
+    }
+
+    /// Connects the node `handle` to the attribute `slot` of the scene's
+    /// [`.root`](node::ROOT).
+    ///
+    /// E.g. use `"geometryattributes"` to connect an
+    /// [`attributes`](node::ATTRIBUTES) node that applies to the whole
+    /// scene.
+    #[inline]
+    pub fn attach_to_root_slot(&self, handle: &str, slot: &str) {
+        self.connect(handle, None, node::ROOT, slot, None);
+    }
+
     /// This function removes a connection between two elements.
     ///
     /// The handle for either node may be the special value
@@ -426,6 +1707,21 @@ impl<'a> Context<'a> {
         let to = HandleString::from(to);
         let to_attr = Ustr::from(to_attr);
 
+        self.trace_connect(
+            "Disconnect",
+            from.as_str(),
+            from_attr.as_str(),
+            to.as_str(),
+            to_attr.as_str(),
+            &[],
+        );
+        self.0.graph.lock().unwrap().disconnect(
+            from.as_str(),
+            from_attr.as_str(),
+            to.as_str(),
+            to_attr.as_str(),
+        );
+
         NSI_API.NSIDisconnect(
             self.0.context,
             from.as_char_ptr(),
@@ -435,6 +1731,385 @@ impl<'a> Context<'a> {
         );
     }
 
+    /// Shows or hides the node `handle` without deleting it.
+    ///
+    /// This does not set a flag on the node. Disabling disconnects `handle`
+    /// from the `"objects"` of all its parents, e.g. [`.root`](node::ROOT)
+    /// or a [`transform`](node::TRANSFORM). The connections are remembered
+    /// in the context's mirror of the scene. Enabling connects `handle`
+    /// again the same way. The node, its attributes and everything connected
+    /// to it stay as they are. This is cheap enough to toggle the visibility
+    /// of objects in an [interactive session](Context::interactive_session()).
+    ///
+    /// Only the `"strength"` of a connection is restored, other arguments it
+    /// was made with are not. A node that was disabled and is then deleted is
+    /// forgotten. Enabling a node that was not disabled does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("teapot", nsi::MESH, None);
+    /// ctx.connect("teapot", None, nsi::ROOT, "objects", None);
+    ///
+    /// // Hide the teapot ...
+    /// ctx.set_enabled("teapot", false);
+    /// // ... and show it again.
+    /// ctx.set_enabled("teapot", true);
+    /// ```
+    pub fn set_enabled(&self, handle: &str, enabled: bool) {
+        if enabled {
+            let parents = self.graph().disabled.remove(handle);
+
+            for c in parents.into_iter().flatten() {
+                let strength = [integer!("strength", c.strength)];
+                self.connect(
+                    &c.from,
+                    Some(&c.from_attr),
+                    &c.to,
+                    &c.to_attr,
+                    (0 != c.strength).then_some(&strength[..]),
+                );
+            }
+        } else {
+            let parents = {
+                let mut graph = self.graph();
+                let parents = graph
+                    .connections
+                    .iter()
+                    .filter(|c| c.from == handle && "objects" == c.to_attr)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                graph
+                    .disabled
+                    .entry(handle.to_string())
+                    .or_default()
+                    .extend(parents.iter().cloned());
+                parents
+            };
+
+            for c in &parents {
+                self.disconnect(&c.from, Some(&c.from_attr), &c.to, &c.to_attr);
+            }
+        }
+    }
+
+    /// Returns the position of the origin of the node `handle` in world
+    /// space.
+    ///
+    /// The position is computed from the `"transformationmatrix"` of
+    /// `handle`, if it is a [`transform`](node::TRANSFORM), and of all
+    /// transforms above it, up to the [`.root`](node::ROOT). Where a node
+    /// is connected to several parents, the first connection is followed.
+    /// Time samples other than the first one are ignored.
+    ///
+    /// This only reflects nodes, attributes & connections made through this
+    /// context. Anything the renderer got elsewhere, e.g. from an archive
+    /// read with [`evaluate()`](Context::evaluate()), is not seen.
+    ///
+    /// Returns [`None`] if `handle` is not connected to the `.root`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("xform", nsi::TRANSFORM, None);
+    /// ctx.set_attribute(
+    ///     "xform",
+    ///     &[nsi::double_matrix!(
+    ///         "transformationmatrix",
+    ///         &[
+    ///             1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 1., 2., 3., 1.,
+    ///         ]
+    ///     )],
+    /// );
+    /// ctx.connect("xform", None, nsi::ROOT, "objects", None);
+    ///
+    /// ctx.create("teapot", nsi::MESH, None);
+    /// ctx.connect("teapot", None, "xform", "objects", None);
+    ///
+    /// assert_eq!(Some([1.0, 2.0, 3.0]), ctx.world_position("teapot"));
+    /// ```
+    pub fn world_position(&self, handle: &str) -> Option<[f64; 3]> {
+        let matrix = self.graph().world_matrix(handle)?;
+
+        Some([matrix[12], matrix[13], matrix[14]])
+    }
+
+    /// Focuses `camera` on the node `target`.
+    ///
+    /// This sets the camera's `"depthoffield.focaldistance"` to the distance
+    /// between the origins of `camera` and `target` in world space, as
+    /// returned by [`world_position()`](Context::world_position()).
+    ///
+    /// The distance is computed once, when this is called. Moving the
+    /// camera or the target later does not change the focus; call this again
+    /// in that case.
+    ///
+    /// Depth of field still needs to be turned on with the camera's
+    /// `"depthoffield.enable"`.
+    ///
+    /// Returns the focus distance. If either node is not connected to the
+    /// [`.root`](node::ROOT), nothing is set and [`None`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("camera_xform", nsi::TRANSFORM, None);
+    /// ctx.set_attribute(
+    ///     "camera_xform",
+    ///     &[nsi::double_matrix!(
+    ///         "transformationmatrix",
+    ///         &[
+    ///             1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 0., 5., 1.,
+    ///         ]
+    ///     )],
+    /// );
+    /// ctx.connect("camera_xform", None, nsi::ROOT, "objects", None);
+    ///
+    /// ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+    /// ctx.connect("camera", None, "camera_xform", "objects", None);
+    ///
+    /// ctx.create("teapot", nsi::MESH, None);
+    /// ctx.connect("teapot", None, nsi::ROOT, "objects", None);
+    ///
+    /// assert_eq!(Some(5.0), ctx.focus_on("camera", "teapot"));
+    /// ```
+    pub fn focus_on(&self, camera: &str, target: &str) -> Option<f64> {
+        let from = self.world_position(camera)?;
+        let to = self.world_position(target)?;
+
+        let distance = from
+            .iter()
+            .zip(to)
+            .map(|(from, to)| (from - to).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        self.set_attribute(
+            camera,
+            &[double!("depthoffield.focaldistance", distance)],
+        );
+
+        Some(distance)
+    }
+
+    /// Creates a copy of the subtree ending in `handle`.
+    ///
+    /// The subtree is `handle` and all nodes connected to it, directly or
+    /// indirectly, e.g. the objects below a [`transform`](node::TRANSFORM)
+    /// and their attributes & shaders. Each node is copied with all its
+    /// attributes, including time samples. The connections between the
+    /// nodes are recreated between the copies. Connections to nodes outside
+    /// the subtree are not -- connect the returned handle where you need it.
+    ///
+    /// The handles of the copies are those of the originals prefixed with
+    /// `new_prefix`.
+    ///
+    /// This is useful when making variations of an object. E.g. to change
+    /// an attribute or a shader parameter on one copy. If copies only
+    /// differ in their transformation, use an
+    /// [`instances`](node::INSTANCES) node instead.
+    ///
+    /// The copy is made from a Rust-side mirror of the scene. Only nodes,
+    /// attributes and connections made through this context are copied.
+    /// Anything loaded via [`evaluate()`](Context::evaluate()) is not.
+    /// [`Callback`] attributes and arguments given to
+    /// [`connect()`](Context::connect()), other than `"strength"`, are not
+    /// copied either. Neither are arrays of more than 16 scalars, e.g. the
+    /// `"P"` of a mesh, unless the context was built with
+    /// [`mirror_data()`](crate::ContextBuilder::mirror_data()). A warning is
+    /// reported for each attribute skipped this way.
+    ///
+    /// Returns the handle of the copy of `handle`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("xform", nsi::TRANSFORM, None);
+    /// ctx.create("sphere", nsi::PARTICLES, None);
+    /// ctx.set_attribute(
+    ///     "sphere",
+    ///     &[
+    ///         nsi::points!("P", &[0.0, 0.0, 0.0]),
+    ///         nsi::floats!("width", &[1.0]),
+    ///     ],
+    /// );
+    /// ctx.connect("sphere", None, "xform", "objects", None);
+    ///
+    /// // Creates `copy_xform` & `copy_sphere`.
+    /// let copy = ctx.duplicate_subtree("xform", "copy_");
+    /// ctx.connect(&copy, None, nsi::ROOT, "objects", None);
+    /// ```
+    pub fn duplicate_subtree(
+        &self,
+        handle: &str,
+        new_prefix: &str,
+    ) -> std::string::String {
+        let mut graph = self.0.graph.lock().unwrap();
+        let (nodes, connections) = graph.subtree(handle);
+
+        let new_handle = |handle: &str| {
+            HandleString::from(format!("{}{}", new_prefix, handle).as_str())
+        };
+
+        for (handle, node) in nodes {
+            let handle = new_handle(&handle);
+            let node_type = Ustr::from(node.node_type.as_str());
+
+            self.trace_create(handle.as_str(), node_type.as_str(), &[]);
+            NSI_API.NSICreate(
+                self.0.context,
+                handle.as_char_ptr(),
+                node_type.as_char_ptr(),
+                0,
+                std::ptr::null(),
+            );
+
+            for attribute in &node.attributes {
+                let Some(param) = attribute.as_param() else {
+                    self.report(
+                        log::Level::Warn,
+                        &format!(
+                            "'{}' of the copy '{}' is not set; its value is \
+                             not mirrored. See ContextBuilder::mirror_data().",
+                            attribute.name,
+                            handle.as_str()
+                        ),
+                    );
+                    continue;
+                };
+
+                self.trace_set_attribute(
+                    handle.as_str(),
+                    attribute.time,
+                    std::slice::from_ref(&param),
+                );
+                match attribute.time {
+                    Some(time) => NSI_API.NSISetAttributeAtTime(
+                        self.0.context,
+                        handle.as_char_ptr(),
+                        time,
+                        1,
+                        &param,
+                    ),
+                    None => NSI_API.NSISetAttribute(
+                        self.0.context,
+                        handle.as_char_ptr(),
+                        1,
+                        &param,
+                    ),
+                }
+            }
+
+            graph.nodes.insert(handle.as_str().to_string(), node);
+        }
+
+        for connection in connections {
+            let from = new_handle(connection.from.as_str());
+            let from_attr = Ustr::from(connection.from_attr.as_str());
+            let to = new_handle(connection.to.as_str());
+            let to_attr = Ustr::from(connection.to_attr.as_str());
+
+            let param = nsi_sys::NSIParam {
+                name: Ustr::from("strength").as_char_ptr(),
+                data: &connection.strength as *const _ as _,
+                type_: NSIType::Integer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            };
+            let params = if 0 != connection.strength {
+                std::slice::from_ref(&param)
+            } else {
+                &[]
+            };
+
+            self.trace_connect(
+                "Connect",
+                from.as_str(),
+                from_attr.as_str(),
+                to.as_str(),
+                to_attr.as_str(),
+                params,
+            );
+            NSI_API.NSIConnect(
+                self.0.context,
+                from.as_char_ptr(),
+                from_attr.as_char_ptr(),
+                to.as_char_ptr(),
+                to_attr.as_char_ptr(),
+                params.len() as _,
+                params.as_ptr(),
+            );
+
+            graph.connect(
+                from.as_str(),
+                &connection.from_attr,
+                to.as_str(),
+                &connection.to_attr,
+                connection.strength,
+            );
+        }
+
+        format!("{}{}", new_prefix, handle)
+    }
+
+    /// Attaches the metadata `key` with `value` to the node `handle`.
+    ///
+    /// Metadata is for tools, e.g. to store the color or a note an editor
+    /// shows for a node. It is kept in the context's mirror of the scene
+    /// only. It is never sent to the renderer and hence not written to ɴsɪ
+    /// streams either.
+    ///
+    /// Setting a `key` a second time replaces its value. Metadata is removed
+    /// with the node and copied by
+    /// [`duplicate_subtree()`](Context::duplicate_subtree()).
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` -- A handle to a node previously created with
+    ///   [`create()`](Context::create()). Metadata on any other handle,
+    ///   e.g. [`.root`](node::ROOT), is ignored.
+    ///
+    /// * `key` -- The name of the metadata.
+    ///
+    /// * `value` -- The value of the metadata.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("key_light", nsi::TRANSFORM, None);
+    /// ctx.set_metadata("key_light", "ui.color", "#ffcc00");
+    ///
+    /// assert_eq!(
+    ///     Some("#ffcc00"),
+    ///     ctx.metadata("key_light").get("ui.color").map(|v| v.as_str())
+    /// );
+    /// ```
+    pub fn set_metadata(&self, handle: &str, key: &str, value: &str) {
+        self.graph().set_metadata(handle, key, value);
+    }
+
+    /// Returns a copy of all metadata attached to the node `handle` via
+    /// [`set_metadata()`](Context::set_metadata()).
+    ///
+    /// This is empty if the node has no metadata or does not exist.
+    pub fn metadata(
+        &self,
+        handle: &str,
+    ) -> HashMap<std::string::String, std::string::String> {
+        self.graph().metadata(handle).cloned().unwrap_or_default()
+    }
+
     /// This function includes a block of interface calls from an external
     /// source into the current scene. It blends together the concepts of a
     /// file include, commonly known as an *archive*, with that of
@@ -476,19 +2151,164 @@ impl<'a> Context<'a> {
     /// * `"script"` ([`String`]) – A valid Lua script to execute when `"type"`
     ///   is set to `"lua"`.
     ///
-    /// * `"buffer"` ([`String`]) – A memory block that contain ɴꜱɪ commands to
-    ///   execute.
+    /// * `"buffer"` ([`String`]) – A memory block that contain ɴꜱɪ commands to
+    ///   execute.
+    ///
+    /// * `"backgroundload"` ([`Integer`]) – If this is nonzero, the object may
+    ///   be loaded in a separate thread, at some later time. This requires that
+    ///   further interface calls not directly reference objects defined in the
+    ///   included file. The only guarantee is that the file will be loaded
+    ///   before rendering begins.
+    #[inline]
+    pub fn evaluate(&self, args: &ArgSlice<'_, 'a>) {
+        let (args_len, args_ptr, _args_out) = get_c_param_vec(Some(args));
+
+        NSI_API.NSIEvaluate(self.0.context, args_len, args_ptr);
+    }
+
+    /// Returns the [`PixelFormat`](output::PixelFormat) the callbacks of an
+    /// output driver connected to the output layers of `screen` will receive.
+    ///
+    /// This allows allocating buffers and checking for expected layers
+    /// before a render is started.
+    ///
+    /// The format is derived from the context's mirror of the scene, i.e.
+    /// from the [`outputlayer`](node::OUTPUT_LAYER)s connected to `screen`'s
+    /// `"outputlayers"`, their `"variablename"`, `"layertype"` and
+    /// `"withalpha"`. Layers are ordered by `"sortkey"`, then by the order in
+    /// which they were connected. Output layers created via
+    /// [`evaluate()`](Context::evaluate()) are not seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("screen", nsi::SCREEN, None);
+    /// ctx.create(
+    ///     "beauty",
+    ///     nsi::OUTPUT_LAYER,
+    ///     Some(&[
+    ///         nsi::string!("variablename", "Ci"),
+    ///         nsi::integer!("withalpha", 1),
+    ///     ]),
+    /// );
+    /// ctx.connect("beauty", None, "screen", "outputlayers", None);
+    ///
+    /// assert_eq!(4, ctx.probe_pixel_format("screen").channels());
+    /// ```
+    #[cfg(feature = "output")]
+    pub fn probe_pixel_format(&self, screen: &str) -> output::PixelFormat {
+        let graph = self.graph();
+
+        let mut layers = graph
+            .inputs(screen, "outputlayers")
+            .filter(|layer| Some(node::OUTPUT_LAYER) == graph.node_type(layer))
+            .collect::<Vec<_>>();
+        layers.sort_by_key(|layer| {
+            graph.integer_attribute(layer, "sortkey").unwrap_or(0)
+        });
+
+        pixel_format_of(&graph, &layers)
+    }
+
+    /// Connects several [`outputlayer`](node::OUTPUT_LAYER)s to one
+    /// [`outputdriver`](node::OUTPUT_DRIVER), in order, and returns the
+    /// [`PixelFormat`](output::PixelFormat) the driver will receive.
+    ///
+    /// The `"sortkey"` of each layer is set to its index in `layers` and
+    /// the layers are (re)connected to `driver`'s `"outputdrivers"` in that
+    /// order. The first layer hence starts at channel offset `0` and each
+    /// following layer right after the one before.
+    ///
+    /// The format is derived from the context's mirror of the scene, like
+    /// with [`probe_pixel_format()`](Context::probe_pixel_format()). It only
+    /// contains `layers`, not other layers that may feed `driver`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+    /// ctx.create("beauty", nsi::OUTPUT_LAYER, None);
+    /// ctx.create(
+    ///     "normal",
+    ///     nsi::OUTPUT_LAYER,
+    ///     Some(&[
+    ///         nsi::string!("variablename", "N_world"),
+    ///         nsi::string!("layertype", "vector"),
+    ///     ]),
+    /// );
+    ///
+    /// let pixel_format =
+    ///     ctx.connect_layers_to_driver("driver", &["beauty", "normal"]);
+    ///
+    /// assert_eq!(3, pixel_format[1].offset());
+    /// ```
+    #[cfg(feature = "output")]
+    pub fn connect_layers_to_driver(
+        &self,
+        driver: &str,
+        layers: &[&str],
+    ) -> output::PixelFormat {
+        for layer in layers {
+            self.disconnect(driver, None, layer, "outputdrivers");
+        }
+
+        for (sort_key, layer) in layers.iter().enumerate() {
+            self.set_attribute(layer, &[integer!("sortkey", sort_key as _)]);
+            self.connect(driver, None, layer, "outputdrivers", None);
+        }
+
+        pixel_format_of(&self.graph(), layers)
+    }
+
+    /// Records scene edits and sends them to the renderer in one go.
+    ///
+    /// The calls made on the [`Batch`] inside `f` are serialized into an
+    /// in-memory ɴsɪ stream in the text format. When `f` returns, the stream is submitted with a
+    /// single [`evaluate()`](Context::evaluate()) call of type
+    /// `"apistream"`.
+    ///
+    /// This trades one call across the FFI boundary per edit for the cost of
+    /// formatting the edits as text and the renderer parsing them. It pays
+    /// off for scenes made of many small nodes, when each call carries little
+    /// data. For a few nodes carrying large amounts of data, e.g. a single
+    /// mesh with millions of triangles, calling [`create()`](Context::create())
+    /// and [`set_attribute()`](Context::set_attribute()) directly is faster as
+    /// the data is handed to the renderer without being copied or formatted.
+    /// The `batch` benchmark of this crate measures both cases.
     ///
-    /// * `"backgroundload"` ([`Integer`]) – If this is nonzero, the object may
-    ///   be loaded in a separate thread, at some later time. This requires that
-    ///   further interface calls not directly reference objects defined in the
-    ///   included file. The only guarantee is that the file will be loaded
-    ///   before rendering begins.
-    #[inline]
-    pub fn evaluate(&self, args: &ArgSlice<'_, 'a>) {
-        let (args_len, args_ptr, _args_out) = get_c_param_vec(Some(args));
-
-        NSI_API.NSIEvaluate(self.0.context, args_len, args_ptr);
+    /// See [`Batch`] for limitations.
+    ///
+    /// # Example
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.batch(|batch| {
+    ///     for i in 0..1000 {
+    ///         let handle = format!("particle_{}", i);
+    ///
+    ///         batch.create(&handle, nsi::PARTICLES, None);
+    ///         batch.set_attribute(
+    ///             &handle,
+    ///             &[
+    ///                 nsi::points!("P", &[i as f32, 0.0, 0.0]),
+    ///                 nsi::floats!("width", &[0.5]),
+    ///             ],
+    ///         );
+    ///         batch.connect(&handle, None, nsi::ROOT, "objects", None);
+    ///     }
+    /// });
+    /// ```
+    pub fn batch<F>(&self, f: F)
+    where
+        F: FnOnce(&mut Batch<'_, 'a>),
+    {
+        let mut batch = Batch::new(self);
+        f(&mut batch);
+        batch.flush();
     }
 
     /// This function is the only control function of the API.
@@ -518,6 +2338,12 @@ impl<'a> Context<'a> {
     /// * `"callback"` ([`FnStatus`]) -- A closure that will be called be when
     ///   the status of the render changes.
     ///
+    /// * `"action"` ([`String`]) -- The action as a string, e.g. `"start"`.
+    ///   If given, this overrides the `action` argument. An unknown action is
+    ///   [reported](Context::report()) as an error and nothing is sent to the
+    ///   renderer. Valid strings are those accepted by [`Action`]'s
+    ///   `TryFrom<&str>` implementation.
+    ///
     ///   # Example
     ///
     ///   ```
@@ -550,47 +2376,146 @@ impl<'a> Context<'a> {
     ) {
         let (_, _, mut args_out) = get_c_param_vec(args);
 
+        // An "action" string in args overrides the action parameter.
+        let action = match args.and_then(|args| {
+            args.iter()
+                .position(|arg| Ustr::from("action") == arg.name)
+                .map(|index| (index, &args[index]))
+        }) {
+            Some((index, arg)) => {
+                let action_str = if Type::String == arg.data.type_() {
+                    unsafe {
+                        CStr::from_ptr(
+                            *(arg.data.as_c_ptr() as *const *const c_char),
+                        )
+                    }
+                    .to_str()
+                    .unwrap_or_default()
+                } else {
+                    ""
+                };
+
+                match Action::try_from(action_str) {
+                    Ok(action) => {
+                        args_out.remove(index);
+                        action
+                    }
+                    Err(error) => {
+                        self.report(log::Level::Error, &error.to_string());
+                        return;
+                    }
+                }
+            }
+            None => action,
+        };
+
         let fn_pointer: nsi_sys::NSIRenderStopped =
             Some(render_status as extern "C" fn(*mut c_void, c_int, c_int));
 
         args_out.push(nsi_sys::NSIParam {
             name: Ustr::from("action").as_char_ptr(),
-            data: &Ustr::from(match action {
-                Action::Start => "start",
-                Action::Wait => "wait",
-                Action::Synchronize => "synchronize",
-                Action::Suspend => "suspend",
-                Action::Resume => "resume",
-                Action::Stop => "stop",
-            })
-            .as_char_ptr() as *const _ as _,
+            data: &Ustr::from(action.as_str()).as_char_ptr() as *const _ as _,
             type_: NSIType::String as _,
             arraylength: 0,
             count: 1,
             flags: 0,
         });
 
-        if let Some(args) = args {
-            if let Some(arg) =
-                args.iter().find(|arg| Ustr::from("callback") == arg.name)
-            {
-                args_out.push(nsi_sys::NSIParam {
-                    name: Ustr::from("stoppedcallback").as_char_ptr(),
-                    data: &fn_pointer as *const _ as _,
-                    type_: NSIType::Pointer as _,
-                    arraylength: 0,
-                    count: 1,
-                    flags: 0,
-                });
-                args_out.push(nsi_sys::NSIParam {
-                    name: Ustr::from("stoppedcallbackdata").as_char_ptr(),
-                    data: &arg.data.as_c_ptr() as *const _ as _,
-                    type_: NSIType::Pointer as _,
-                    arraylength: 1,
-                    count: 1,
-                    flags: 0,
-                });
+        // This needs to live until NSIRenderControl() was called.
+        let progressive: c_int = 1;
+
+        if Action::Start == action
+            && self.0.progressive.load(Ordering::Relaxed)
+            && !args.is_some_and(|args| {
+                args.iter().any(|arg| Ustr::from("progressive") == arg.name)
+            })
+        {
+            args_out.push(nsi_sys::NSIParam {
+                name: Ustr::from("progressive").as_char_ptr(),
+                data: &progressive as *const _ as _,
+                type_: NSIType::Integer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            });
+        }
+
+        let fn_status = args.and_then(|args| {
+            args.iter().find(|arg| Ustr::from("callback") == arg.name)
+        });
+
+        // This needs to live until NSIRenderControl() was called. It is
+        // freed by render_status() once the render has stopped.
+        //
+        // A render is always started with a payload so wait_timeout() learns
+        // when it stops.
+        let status_payload: *const c_void =
+            if Action::Start == action || fn_status.is_some() {
+                Box::into_raw(Box::new(StatusPayload {
+                    inner: match &self.0 {
+                        InnerRef::Owned(inner) => Arc::as_ptr(inner) as _,
+                        InnerRef::Borrowed(_) => std::ptr::null(),
+                    },
+                    fn_status: fn_status.map_or(std::ptr::null_mut(), |arg| {
+                        arg.data.as_c_ptr() as _
+                    }),
+                })) as _
+            } else {
+                std::ptr::null()
+            };
+
+        if !status_payload.is_null() {
+            args_out.push(nsi_sys::NSIParam {
+                name: Ustr::from("stoppedcallback").as_char_ptr(),
+                data: &fn_pointer as *const _ as _,
+                type_: NSIType::Pointer as _,
+                arraylength: 0,
+                count: 1,
+                flags: 0,
+            });
+            args_out.push(nsi_sys::NSIParam {
+                name: Ustr::from("stoppedcallbackdata").as_char_ptr(),
+                data: &status_payload as *const _ as _,
+                type_: NSIType::Pointer as _,
+                arraylength: 1,
+                count: 1,
+                flags: 0,
+            });
+        }
+
+        match action {
+            Action::Start => self.0.interactive.store(
+                get_integer_arg(args, "interactive").is_some_and(|i| 0 != i),
+                Ordering::Relaxed,
+            ),
+            Action::Wait | Action::Stop => {
+                self.0.interactive.store(false, Ordering::Relaxed)
+            }
+            _ => (),
+        }
+
+        #[cfg(debug_assertions)]
+        match action {
+            Action::Start => {
+                self.0
+                    .render_tracker
+                    .render_started
+                    .store(true, Ordering::Relaxed);
+                self.0
+                    .render_tracker
+                    .render_waited
+                    .store(false, Ordering::Relaxed);
             }
+            Action::Wait | Action::Stop => self
+                .0
+                .render_tracker
+                .render_waited
+                .store(true, Ordering::Relaxed),
+            _ => (),
+        }
+
+        if Action::Start == action {
+            *self.0.render_running.lock().unwrap() = true;
         }
 
         NSI_API.NSIRenderControl(
@@ -598,6 +2523,447 @@ impl<'a> Context<'a> {
             args_out.len() as _,
             args_out.as_ptr(),
         );
+
+        // The render has stopped once a wait returns, with or without a
+        // status callback.
+        if Action::Wait == action {
+            self.0.set_render_stopped();
+        }
+    }
+
+    /// Blocks until the render has finished or `timeout` has passed.
+    ///
+    /// This is [`render_control()`](Context::render_control()) with
+    /// [`Action::Wait`] with an upper bound on the time spent waiting,
+    /// e.g. to limit the time a render farm job may take.
+    ///
+    /// The context learns that the render has stopped from the renderer's
+    /// status callback, which every render started with
+    /// [`Action::Start`] has. Nothing blocks in the renderer while waiting.
+    /// If `timeout` passes and `stop_on_timeout` is `true`, the render is
+    /// stopped with [`Action::Stop`] and this returns once it has stopped.
+    /// Output drivers receive the pixels rendered so far, i.e. the image may
+    /// be partial.
+    ///
+    /// If `stop_on_timeout` is `false`, the render keeps running. Call this
+    /// again or [`render_control()`](Context::render_control()) with
+    /// [`Action::Wait`] or [`Action::Stop`] before dropping the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// use std::time::Duration;
+    ///
+    /// ctx.render_control(nsi::Action::Start, None);
+    ///
+    /// // Give up after one hour.
+    /// if nsi::WaitResult::TimedOut
+    ///     == ctx.wait_timeout(Duration::from_secs(60 * 60), true)
+    /// {
+    ///     eprintln!("Render took too long, the image is incomplete.");
+    /// }
+    /// ```
+    pub fn wait_timeout(
+        &self,
+        timeout: std::time::Duration,
+        stop_on_timeout: bool,
+    ) -> WaitResult {
+        let timed_out = self
+            .0
+            .render_stopped
+            .wait_timeout_while(
+                self.0.render_running.lock().unwrap(),
+                timeout,
+                |running| *running,
+            )
+            .unwrap()
+            .1
+            .timed_out();
+
+        if timed_out {
+            if !stop_on_timeout {
+                return WaitResult::TimedOut;
+            }
+
+            self.render_control(Action::Stop, None);
+        }
+
+        // Returns right away as the render has stopped.
+        self.render_control(Action::Wait, None);
+
+        if timed_out {
+            WaitResult::TimedOut
+        } else {
+            WaitResult::Completed
+        }
+    }
+
+    /// Configures progressive rendering.
+    ///
+    /// If `enabled` is `true`, all subsequent renders started with
+    /// [`render_control()`](Context::render_control()) and
+    /// [`Action::Start`] are progressive, unless the `"progressive"`
+    /// argument is given there explicitly.
+    ///
+    /// If `max_passes` is given, it is written to the `"progressive.maxpasses"`
+    /// attribute of the [`.global`](node::GLOBAL) node to limit the number
+    /// of refinement passes the renderer makes over the image.
+    ///
+    /// # Output Drivers
+    ///
+    /// In a progressive render the image is refined in several passes. The
+    /// [`FnWrite`](crate::output::FnWrite) closure of an
+    /// [`FERRIS`](crate::output::FERRIS) output driver is therefore called
+    /// several times for the same pixels, once per pass. Each call delivers
+    /// the latest, more refined, values. The
+    /// [`FnFinish`](crate::output::FnFinish) closure is still only called
+    /// once, with the final image.
+    #[inline]
+    pub fn set_progressive(&self, enabled: bool, max_passes: Option<i32>) {
+        self.0.progressive.store(enabled, Ordering::Relaxed);
+
+        if let Some(max_passes) = max_passes {
+            self.set_integer(node::GLOBAL, "progressive.maxpasses", max_passes);
+        }
+    }
+
+    /// Applies all scene edits made since the last synchronization to an
+    /// interactive render.
+    ///
+    /// This is a shortcut for calling
+    /// [`render_control()`](Context::render_control()) with
+    /// [`Action::Synchronize`].
+    #[inline]
+    pub fn synchronize(&self) {
+        self.render_control(Action::Synchronize, None);
+    }
+
+    /// Starts an interactive render that is kept alive across scene edits.
+    ///
+    /// See [`InteractiveSession`] for how to edit the scene while it is
+    /// being rendered.
+    #[inline]
+    pub fn interactive_session(&self) -> InteractiveSession<'_, 'a> {
+        InteractiveSession::new(self)
+    }
+
+    /// Starts building a node of type `node_type`.
+    ///
+    /// This is a shortcut for the usual
+    /// [`create()`](Context::create()),
+    /// [`set_attribute()`](Context::set_attribute()) &
+    /// [`connect()`](Context::connect()) sequence. See [`NodeBuilder`].
+    #[inline]
+    pub fn build<'b>(&self, node_type: NodeType) -> NodeBuilder<'_, 'a, 'b> {
+        NodeBuilder::new(self, node_type)
+    }
+
+    /// Pushes all scene edits made so far to the renderer.
+    ///
+    /// Calls like [`set_attribute()`](Context::set_attribute()) or
+    /// [`connect()`](Context::connect()) take effect on the scene
+    /// immediately. But a running interactive render only picks them up
+    /// once it is [synchronized](Context::synchronize()).
+    ///
+    /// If this context has an interactive render running, i.e. one started
+    /// with the `"interactive"` argument set to `1`, this calls
+    /// [`synchronize()`](Context::synchronize()). Otherwise it does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// # ctx.create("shader", nsi::SHADER, None);
+    /// ctx.render_control(
+    ///     nsi::Action::Start,
+    ///     Some(&[nsi::integer!("interactive", true as _)]),
+    /// );
+    ///
+    /// // Edit the scene.
+    /// ctx.set_color("shader", "i_color", [1.0, 0.0, 0.0]);
+    ///
+    /// // Make the running render pick up the edit.
+    /// ctx.flush();
+    ///
+    /// ctx.render_control(nsi::Action::Stop, None);
+    /// ctx.render_control(nsi::Action::Wait, None);
+    /// ```
+    #[inline]
+    pub fn flush(&self) {
+        if self.0.interactive.load(Ordering::Relaxed) {
+            self.synchronize();
+        }
+    }
+
+    /// Checks the scene for common mistakes.
+    ///
+    /// This looks for:
+    /// * [Output drivers](node::OUTPUT_DRIVER) that are not connected to
+    ///   any [output layer](node::OUTPUT_LAYER).
+    /// * [Screens](node::SCREEN) that are not connected to any camera.
+    /// * Geometry that has no [`attributes`](node::ATTRIBUTES) node with a
+    ///   `"surfaceshader"` connected, neither directly nor through any
+    ///   transform above it.
+    /// * Connections from or to nodes that were never created or were
+    ///   deleted.
+    ///
+    /// The check runs on a Rust-side mirror of the scene. It only knows
+    /// about nodes and connections made through this context. Anything
+    /// loaded via [`evaluate()`](Context::evaluate()) is not seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+    ///
+    /// for issue in ctx.validate() {
+    ///     eprintln!("{}", issue);
+    /// }
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        self.0.graph.lock().unwrap().validate()
+    }
+
+    /// Returns whether the renderer supports nodes of type `node_type`.
+    ///
+    /// ɴsɪ has no API to query the capabilities of a renderer. This is a
+    /// best-effort check:
+    ///
+    /// * The [node types](node) of the official ɴsɪ specification are
+    ///   assumed to be supported.
+    ///
+    /// * For any other type a throwaway node of that type is created and
+    ///   deleted right away. The node is never connected to the scene. If
+    ///   the renderer reports a warning or an error while doing so, the type
+    ///   is considered unsupported. These messages are not passed on to the
+    ///   context's `"errorhandler"`.
+    ///
+    /// Detecting that the renderer complained requires the context to have
+    /// been created with an `"errorhandler"`. Without one, this returns
+    /// `false` for all types outside the specification.
+    ///
+    /// Renderers that only check node types when rendering starts, e.g.
+    /// when the context writes an ɴsɪ stream to a file, will report all
+    /// types as supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// let error_handler =
+    ///     nsi::ErrorCallback::new(|_: log::Level, _: i32, _: &str| {});
+    ///
+    /// let ctx = nsi::Context::new(Some(&[nsi::callback!(
+    ///     "errorhandler",
+    ///     error_handler
+    /// )]))
+    /// .unwrap();
+    ///
+    /// assert!(ctx.supports_node_type(nsi::MESH));
+    ///
+    /// if !ctx.supports_node_type("fancyhair") {
+    ///     // Fall back to curves.
+    /// }
+    /// ```
+    pub fn supports_node_type(&self, node_type: &str) -> bool {
+        const SPECIFICATION: [&str; 24] = [
+            node::SET,
+            node::SHADER,
+            node::ATTRIBUTES,
+            node::TRANSFORM,
+            node::INSTANCES,
+            node::PLANE,
+            node::MESH,
+            node::FACESET,
+            node::CURVES,
+            node::PARTICLES,
+            node::PROCEDURAL,
+            node::VOLUME,
+            node::ENVIRONMENT,
+            node::ORTHOGRAPHIC_CAMERA,
+            node::PERSPECTIVE_CAMERA,
+            node::FISHEYE_CAMERA,
+            node::CYLINDRICAL_CAMERA,
+            node::SPHERICAL_CAMERA,
+            node::OUTPUT_DRIVER,
+            node::OUTPUT_LAYER,
+            node::SCREEN,
+            node::ROOT,
+            node::GLOBAL,
+            node::ALL,
+        ];
+
+        if SPECIFICATION.contains(&node_type) {
+            return true;
+        }
+
+        let error_sink = match &self.0.error_sink {
+            Some(error_sink) => error_sink,
+            None => return false,
+        };
+
+        let handle = HandleString::from(
+            format!("__nsi_supports_node_type_{}", node_type).as_str(),
+        );
+        let node_type = Ustr::from(node_type);
+
+        error_sink.muted.store(true, Ordering::Relaxed);
+        let errors = error_sink.errors.load(Ordering::Relaxed);
+
+        NSI_API.NSICreate(
+            self.0.context,
+            handle.as_char_ptr(),
+            node_type.as_char_ptr(),
+            0,
+            std::ptr::null(),
+        );
+
+        let supported = errors == error_sink.errors.load(Ordering::Relaxed);
+
+        NSI_API.NSIDelete(
+            self.0.context,
+            handle.as_char_ptr(),
+            0,
+            std::ptr::null(),
+        );
+
+        error_sink.muted.store(false, Ordering::Relaxed);
+
+        supported
+    }
+
+    /// Sends a message to the `"errorhandler"` of this context.
+    ///
+    /// If the context was created without an `"errorhandler"`, the message is
+    /// sent to the [`log`] crate.
+    ///
+    /// This lets helpers built on top of a context report problems to the
+    /// same place the renderer does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// ctx.report(log::Level::Warn, "No lights in the scene.");
+    /// ```
+    pub fn report(&self, level: log::Level, message: &str) {
+        match &self.0.error_sink {
+            Some(error_sink) => {
+                if log::Level::Warn >= level {
+                    error_sink.errors.fetch_add(1, Ordering::Relaxed);
+                }
+
+                let fn_error = unsafe {
+                    &*(error_sink.fn_error as *const Box<dyn FnError>)
+                };
+                fn_error(level, 0, message);
+            }
+            None => log::log!(level, "{}", message),
+        }
+    }
+}
+
+/// An error when creating a [`Context`] with
+/// [`new_or_explain()`](Context::new_or_explain()).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContextError {
+    /// The renderer library could not be loaded.
+    ///
+    /// Each attempt is a location that was tried and the reason loading
+    /// from there failed.
+    LibraryNotFound {
+        attempts: Vec<(std::string::String, std::string::String)>,
+    },
+    /// The library was loaded but the renderer did not create a context,
+    /// e.g. because no license was available or an argument was invalid.
+    BeginFailed,
+}
+
+impl std::fmt::Display for ContextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextError::LibraryNotFound { attempts } => {
+                writeln!(f, "Could not load lib3delight. Tried:")?;
+                for (location, reason) in attempts {
+                    writeln!(f, "  * {}: {}", location, reason)?;
+                }
+                write!(
+                    f,
+                    "Install 3Delight from https://www.3delight.com/ or set \
+                     $DELIGHT to the directory it is installed in."
+                )
+            }
+            ContextError::BeginFailed => {
+                write!(f, "The renderer could not create a context.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContextError {}
+
+/// How attribute names are checked when they are set on a node.
+///
+/// See [`Context::set_attribute_check()`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AttributeCheck {
+    /// Attribute names are not checked.
+    #[default]
+    Off,
+    /// Unknown attribute names are reported as warnings but still set.
+    Warn,
+    /// Unknown attribute names are reported as errors and not set.
+    Deny,
+}
+
+/// The format of an ɴsɪ stream written by a context created with
+/// [`new_stream()`](Context::new_stream()).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Human-readable ɴsɪ (`"nsi"`).
+    #[default]
+    Ascii,
+    /// Binary ɴsɪ (`"binarynsi"`). Smaller and faster to read back.
+    Binary,
+}
+
+impl StreamFormat {
+    /// Returns the value of the `"streamformat"` argument of
+    /// [`Context::new()`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamFormat::Ascii => "nsi",
+            StreamFormat::Binary => "binarynsi",
+        }
+    }
+}
+
+/// The compression of an ɴsɪ stream written by a context created with
+/// [`new_stream()`](Context::new_stream()).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StreamCompression {
+    /// No compression.
+    #[default]
+    None,
+    /// Compress the stream with gzip.
+    Gzip,
+}
+
+impl StreamCompression {
+    /// Returns the value of the `"streamcompression"` argument of
+    /// [`Context::new()`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamCompression::None => "",
+            StreamCompression::Gzip => "gzip",
+        }
     }
 }
 
@@ -622,6 +2988,75 @@ pub enum Action {
     Stop,
 }
 
+impl Action {
+    /// Returns the string ɴsɪ uses for this action.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Wait => "wait",
+            Action::Synchronize => "synchronize",
+            Action::Suspend => "suspend",
+            Action::Resume => "resume",
+            Action::Stop => "stop",
+        }
+    }
+}
+
+impl From<Action> for &'static str {
+    #[inline]
+    fn from(action: Action) -> Self {
+        action.as_str()
+    }
+}
+
+impl TryFrom<&str> for Action {
+    type Error = UnknownActionError;
+
+    /// Converts an ɴsɪ action string, e.g. `"start"`, into an [`Action`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// assert_eq!(Ok(nsi::Action::Start), nsi::Action::try_from("start"));
+    /// assert!(nsi::Action::try_from("strat").is_err());
+    /// ```
+    fn try_from(action: &str) -> Result<Self, Self::Error> {
+        match action {
+            "start" => Ok(Action::Start),
+            "wait" => Ok(Action::Wait),
+            "synchronize" => Ok(Action::Synchronize),
+            "suspend" => Ok(Action::Suspend),
+            "resume" => Ok(Action::Resume),
+            "stop" => Ok(Action::Stop),
+            _ => Err(UnknownActionError(action.to_string())),
+        }
+    }
+}
+
+/// The error returned when converting an unknown action string into an
+/// [`Action`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownActionError(pub std::string::String);
+
+impl std::fmt::Display for UnknownActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown render action '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownActionError {}
+
+/// The outcome of [`Context::wait_timeout()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WaitResult {
+    /// The render finished before the timeout.
+    Completed,
+    /// The timeout passed before the render finished.
+    TimedOut,
+}
+
 /// The status of a *interactive* render session.
 #[repr(i32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, num_enum::FromPrimitive)]
@@ -688,6 +3123,7 @@ unsafe impl Send for StatusCallback<'static> {}
 unsafe impl Sync for StatusCallback<'static> {}
 
 impl<'a> StatusCallback<'a> {
+    #[must_use]
     pub fn new<F>(fn_status: F) -> Self
     where
         F: FnStatus<'a>,
@@ -703,6 +3139,16 @@ impl CallbackPtr for StatusCallback<'_> {
     }
 }
 
+// What render_control() passes to the renderer as "stoppedcallbackdata".
+#[derive(Clone, Copy)]
+struct StatusPayload {
+    // The InnerContext of the context the render was started on. Null if
+    // that context was itself borrowed from the renderer.
+    inner: *const c_void,
+    // The Box<dyn FnStatus> of the StatusCallback, if any.
+    fn_status: *mut c_void,
+}
+
 // Trampoline function for the FnStatus callback.
 #[no_mangle]
 pub(crate) extern "C" fn render_status(
@@ -710,23 +3156,81 @@ pub(crate) extern "C" fn render_status(
     context: nsi_sys::NSIContext,
     status: c_int,
 ) {
-    if !payload.is_null() {
-        let fn_status =
-            unsafe { Box::from_raw(payload as *mut Box<dyn FnStatus>) };
-        let ctx = Context(Arc::new(InnerContext {
-            context,
-            _marker: PhantomData,
-        }));
+    if payload.is_null() {
+        return;
+    }
+
+    let status = RenderStatus::from(status);
+
+    let StatusPayload { inner, fn_status } =
+        *unsafe { &*(payload as *const StatusPayload) };
+
+    // Null if the render was started without a status callback.
+    if !fn_status.is_null() {
+        let fn_status = unsafe { &*(fn_status as *const Box<dyn FnStatus>) };
+
+        if inner.is_null() {
+            Context::with_borrowed(context, |ctx| fn_status(ctx, status));
+        } else {
+            // Borrows the context the render was started on. The renderer
+            // only calls this while that context exists as dropping it ends
+            // the render. ManuallyDrop leaves its reference count alone.
+            let ctx =
+                std::mem::ManuallyDrop::new(Context(InnerRef::Owned(unsafe {
+                    Arc::from_raw(inner as *const InnerContext)
+                })));
+
+            fn_status(&ctx, status);
+        }
+    }
 
-        fn_status(&ctx, status.into());
+    // There is no further status once the render has stopped.
+    if matches!(status, RenderStatus::Completed | RenderStatus::Aborted) {
+        drop(unsafe { Box::from_raw(payload as *mut StatusPayload) });
+
+        if !inner.is_null() {
+            unsafe { &*(inner as *const InnerContext) }.set_render_stopped();
+        }
 
-        // We must not call drop() on this context.
-        // This is safe as Context doesn't allocate and this one is on the stack
-        // anyway.
-        std::mem::forget(ctx);
+        if !fn_status.is_null() {
+            drop(unsafe { Box::from_raw(fn_status as *mut Box<dyn FnStatus>) });
+        }
     }
 }
 
+/// A closure which receives the calls traced on a [`Context`].
+///
+/// See [`Context::set_trace()`].
+pub trait FnTrace<'a>: Fn(&str) + 'a {}
+
+#[doc(hidden)]
+impl<'a, T: Fn(&str) + 'a + for<'r> Fn(&'r str)> FnTrace<'a> for T {}
+
+/// Returns the calls `f` makes on a new context, one per line, in ɴsɪ
+/// stream syntax. See [`Context::set_trace()`].
+///
+/// This is used by the tests of this and dependent crates.
+#[doc(hidden)]
+pub fn record_stream(f: impl FnOnce(&Context)) -> std::string::String {
+    let ctx = Context::new(None).expect("Could not create NSI context.");
+
+    let stream =
+        std::sync::Arc::new(std::sync::Mutex::new(std::string::String::new()));
+    let sink = stream.clone();
+    ctx.set_trace_sink(move |call: &str| {
+        let mut stream = sink.lock().unwrap();
+        stream.push_str(call);
+        stream.push('\n');
+    });
+
+    ctx.set_trace(true);
+    f(&ctx);
+    ctx.set_trace(false);
+
+    let stream = stream.lock().unwrap().clone();
+    stream
+}
+
 /// A closure which is called to inform about the errors during scene defintion
 /// or a render.
 ///
@@ -782,6 +3286,7 @@ unsafe impl Send for ErrorCallback<'static> {}
 unsafe impl Sync for ErrorCallback<'static> {}
 
 impl<'a> ErrorCallback<'a> {
+    #[must_use]
     pub fn new<F>(fn_error: F) -> Self
     where
         F: FnError<'a>,
@@ -806,8 +3311,20 @@ pub(crate) extern "C" fn error_handler(
     message: *const c_char,
 ) {
     if !payload.is_null() {
+        let error_sink = unsafe { &*(payload as *const ErrorSink) };
+
+        if NSIErrorLevel::Warning as c_int <= level {
+            error_sink.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if error_sink.muted.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // We must not take ownership here as the renderer may call us many
+        // times.
         let fn_error =
-            unsafe { Box::from_raw(payload as *mut Box<dyn FnError>) };
+            unsafe { &*(error_sink.fn_error as *const Box<dyn FnError>) };
 
         let message = unsafe {
             NullTerminatedStr::from_cstr_unchecked(CStr::from_ptr(message as _))
@@ -823,3 +3340,46 @@ pub(crate) extern "C" fn error_handler(
         fn_error(level, code as _, message.as_ref());
     }
 }
+
+// Returns the pixel format of the output `layers`, in the given order.
+#[cfg(feature = "output")]
+fn pixel_format_of(
+    graph: &graph::Graph,
+    layers: &[&str],
+) -> output::PixelFormat {
+    let mut names = Vec::new();
+
+    for (index, layer) in layers.iter().enumerate() {
+        let name = graph
+            .string_attribute(layer, "variablename")
+            .unwrap_or("Ci");
+
+        // The renderer names channels "<name>.<index>.<channel>" and
+        // scalars "<name>.000". A lone "a" is the alpha of the layer
+        // before it.
+        match graph
+            .string_attribute(layer, "layertype")
+            .unwrap_or("color")
+        {
+            "scalar" => names.push(format!("{}.000", name)),
+            layertype => {
+                let channels: &[&str] = match layertype {
+                    "vector" | "normal" | "point" => &["x", "y", "z"],
+                    "quad" => &["r", "g", "b", "a"],
+                    _ => &["r", "g", "b"],
+                };
+                names.extend(channels.iter().map(|channel| {
+                    format!("{}.{:03}.{}", name, index + 1, channel)
+                }));
+            }
+        }
+
+        if 0 != graph.integer_attribute(layer, "withalpha").unwrap_or(0) {
+            names.push("a".to_string());
+        }
+    }
+
+    output::PixelFormat::from_channel_names(
+        &names.iter().map(|name| name.as_str()).collect::<Vec<_>>(),
+    )
+}
@@ -0,0 +1,207 @@
+//! Creating a [`Context`] from discoverable options.
+use crate::{
+    node::GLOBAL, Arg, ArgData, Callback, Context, ErrorCallback, FnError,
+    Integer, StreamCompression, StreamFormat, String,
+};
+
+/// An error when building a [`Context`] with a [`ContextBuilder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContextBuilderError {
+    /// The `option` only applies to rendering but the context writes a
+    /// [`stream()`](ContextBuilder::stream()).
+    RenderOptionOnStream { option: &'static str },
+    /// The renderer could not create the context.
+    CreationFailed,
+}
+
+impl std::fmt::Display for ContextBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextBuilderError::RenderOptionOnStream { option } => {
+                write!(f, "'{}' has no effect on an ɴsɪ stream", option)
+            }
+            ContextBuilderError::CreationFailed => {
+                write!(f, "the renderer could not create the context")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContextBuilderError {}
+
+/// Builder for a [`Context`].
+///
+/// This is an alternative to passing `"type"`, `"streamfilename"`,
+/// `"errorhandler"` etc. arguments to [`Context::new()`]. Options that are
+/// not set are left at the renderer's defaults.
+///
+/// [`build()`](ContextBuilder::build()) checks that the options make sense
+/// together. E.g. [`threads()`](ContextBuilder::threads()) has no effect on
+/// a context that writes a [`stream()`](ContextBuilder::stream()).
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let ctx = nsi::Context::builder()
+///     .threads(-1)
+///     .license_wait(true)
+///     .error_handler(|level: log::Level, _: i32, message: &str| {
+///         println!("{}: {}", level, message)
+///     })
+///     .build()
+///     .expect("Could not create ɴsɪ context.");
+/// ```
+#[derive(Default)]
+pub struct ContextBuilder<'a> {
+    threads: Option<i32>,
+    license_wait: Option<bool>,
+    stream: Option<(std::string::String, StreamFormat, StreamCompression)>,
+    error_handler: Option<ErrorCallback<'a>>,
+    mirror_data: bool,
+}
+
+impl<'a> ContextBuilder<'a> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of threads used for rendering.
+    ///
+    /// This is [`.global`](crate::GLOBAL)'s `"numberofthreads"`. `0` uses all
+    /// cores. A negative value uses all but that many cores.
+    #[inline]
+    pub fn threads(mut self, threads: i32) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Sets whether to wait for a license to become available, instead of
+    /// failing, when rendering.
+    ///
+    /// This is [`.global`](crate::GLOBAL)'s `"license.wait"`.
+    #[inline]
+    pub fn license_wait(mut self, wait: bool) -> Self {
+        self.license_wait = Some(wait);
+        self
+    }
+
+    /// Writes the scene to an ɴsɪ stream instead of rendering it.
+    ///
+    /// See [`Context::new_stream()`] for the arguments.
+    #[inline]
+    pub fn stream(
+        mut self,
+        filename: &str,
+        format: StreamFormat,
+        compression: StreamCompression,
+    ) -> Self {
+        self.stream = Some((filename.to_string(), format, compression));
+        self
+    }
+
+    /// Sets the closure that is called with errors, warnings and infos
+    /// from the renderer.
+    ///
+    /// This is [`Context::new()`]'s `"errorhandler"`. See [`FnError`].
+    #[inline]
+    pub fn error_handler<F>(mut self, fn_error: F) -> Self
+    where
+        F: FnError<'a>,
+    {
+        self.error_handler = Some(ErrorCallback::new(fn_error));
+        self
+    }
+
+    /// Sets whether the context's Rust-side mirror of the scene keeps a copy
+    /// of every attribute value.
+    ///
+    /// By default only values of up to 16 scalars, e.g. a matrix, are
+    /// copied. That is all [`validate()`](Context::validate()) and the
+    /// helpers reading the mirror need. Large arrays, e.g. the `"P"` of a
+    /// mesh, are not copied so their memory is not held twice.
+    ///
+    /// Enable this if [`get_attribute()`](Context::get_attribute()) or
+    /// [`duplicate_subtree()`](Context::duplicate_subtree()) need to see
+    /// such arrays.
+    #[inline]
+    pub fn mirror_data(mut self, mirror_data: bool) -> Self {
+        self.mirror_data = mirror_data;
+        self
+    }
+
+    /// Creates the context.
+    ///
+    /// # Errors
+    ///
+    /// If the options do not make sense together or if the renderer could
+    /// not create the context.
+    pub fn build(self) -> Result<Context<'a>, ContextBuilderError> {
+        if self.stream.is_some() {
+            if self.threads.is_some() {
+                return Err(ContextBuilderError::RenderOptionOnStream {
+                    option: "threads",
+                });
+            }
+            if self.license_wait.is_some() {
+                return Err(ContextBuilderError::RenderOptionOnStream {
+                    option: "license_wait",
+                });
+            }
+        }
+
+        let mut args = Vec::new();
+
+        if let Some((filename, format, compression)) = &self.stream {
+            args.push(Arg::new(
+                "type",
+                ArgData::from(String::new("apistream")),
+            ));
+            args.push(Arg::new(
+                "streamfilename",
+                ArgData::from(String::new(filename.as_str())),
+            ));
+            args.push(Arg::new(
+                "streamformat",
+                ArgData::from(String::new(format.as_str())),
+            ));
+            args.push(Arg::new(
+                "streamcompression",
+                ArgData::from(String::new(compression.as_str())),
+            ));
+        }
+
+        if let Some(error_handler) = self.error_handler {
+            args.push(Arg::new(
+                "errorhandler",
+                ArgData::from(Callback::new(error_handler)),
+            ));
+        }
+
+        let ctx = Context::new(Some(&args))
+            .ok_or(ContextBuilderError::CreationFailed)?;
+        ctx.graph().mirror_data = self.mirror_data;
+
+        let mut global = Vec::new();
+
+        if let Some(threads) = self.threads {
+            global.push(Arg::new(
+                "numberofthreads",
+                ArgData::from(Integer::new(threads)),
+            ));
+        }
+        if let Some(wait) = self.license_wait {
+            global.push(Arg::new(
+                "license.wait",
+                ArgData::from(Integer::new(wait as _)),
+            ));
+        }
+
+        if !global.is_empty() {
+            ctx.set_attribute(GLOBAL, &global);
+        }
+
+        Ok(ctx)
+    }
+}
@@ -104,13 +104,33 @@ static DELIGHT_LIB: &str = "lib3delight.dylib";
 static DELIGHT_LIB: &str = "3Delight.dll";
 
 impl DynamicApi {
-    #[inline]
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        match unsafe { Container::load(DELIGHT_APP_PATH) }
-            .or_else(|_| unsafe { Container::load(DELIGHT_LIB) })
-            .or_else(|_| match env::var("DELIGHT") {
-                Err(e) => Err(Box::new(e) as _),
-                Ok(delight) => unsafe {
+    /// Tries the install location, the library search path and `$DELIGHT`,
+    /// in that order.
+    ///
+    /// On failure, the error lists each location tried and why it failed.
+    pub fn new() -> Result<Self, ContextError> {
+        let mut attempts = Vec::new();
+
+        let mut api = load(
+            DELIGHT_APP_PATH.to_string(),
+            Path::new(DELIGHT_APP_PATH),
+            &mut attempts,
+        );
+
+        if api.is_none() {
+            api = load(
+                format!("{} (library search path)", DELIGHT_LIB),
+                Path::new(DELIGHT_LIB),
+                &mut attempts,
+            );
+        }
+
+        if api.is_none() {
+            match env::var("DELIGHT") {
+                Err(e) => {
+                    attempts.push(("$DELIGHT".to_string(), e.to_string()))
+                }
+                Ok(delight) => {
                     #[cfg(any(target_os = "linux", target_os = "macos"))]
                     let path =
                         Path::new(&delight).join("lib").join(DELIGHT_LIB);
@@ -118,12 +138,18 @@ impl DynamicApi {
                     let path =
                         Path::new(&delight).join("bin").join(DELIGHT_LIB);
 
-                    Container::load(path)
+                    api = load(
+                        format!("{} ($DELIGHT)", path.display()),
+                        &path,
+                        &mut attempts,
+                    );
                 }
-                .map_err(|e| Box::new(e) as _),
-            }) {
-            Err(e) => Err(e),
-            Ok(api) => {
+            }
+        }
+
+        match api {
+            None => Err(ContextError::LibraryNotFound { attempts }),
+            Some(api) => {
                 let api = DynamicApi { api };
 
                 #[cfg(feature = "output")]
@@ -141,6 +167,22 @@ impl DynamicApi {
     }
 }
 
+// Loads the library at `path`. On failure, records the `location` and the
+// reason in `attempts`.
+fn load(
+    location: std::string::String,
+    path: &Path,
+    attempts: &mut Vec<(std::string::String, std::string::String)>,
+) -> Option<Container<CApi>> {
+    match unsafe { Container::load(path) } {
+        Ok(api) => Some(api),
+        Err(e) => {
+            attempts.push((location, e.to_string()));
+            None
+        }
+    }
+}
+
 impl TryFrom<&Path> for DynamicApi {
     type Error = dlopen2::Error;
 
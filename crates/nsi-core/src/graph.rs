@@ -0,0 +1,880 @@
+//! A Rust-side mirror of the node graph of a [`Context`](crate::Context).
+//!
+//! ɴsɪ has no API to query the scene back from the renderer. To still be
+//! able to reason about a scene, a context records the nodes it creates and
+//! the connections it makes. This is used e.g. by
+//! [`Context::validate()`](crate::Context::validate()).
+//!
+//! Only nodes and connections made through the context itself are recorded.
+//! Attribute values are only copied in full if the context was built with
+//! [`mirror_data()`](crate::ContextBuilder::mirror_data()).
+//! Anything read via [`evaluate()`](crate::Context::evaluate()), e.g. from an
+//! ɴsɪ stream or a Lua script, is invisible to the mirror.
+use crate::{
+    argument::{Arg, ArgData, ArgDataMethods, OwnedArg, OwnedArgData, Type},
+    node,
+};
+use nsi_sys::NSIParam;
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_void, CStr, CString},
+};
+use ustr::Ustr;
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The scene will render but likely not as intended.
+    Warning,
+    /// The scene is broken, e.g. it references nodes that do not exist.
+    Error,
+}
+
+/// A problem found by [`Context::validate()`](crate::Context::validate()).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidationIssue {
+    /// How serious the issue is.
+    pub severity: Severity,
+    /// A human readable description of the issue.
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Error => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+/// Node types that are rendered as geometry and need a material.
+const GEOMETRY: [&str; 5] = [
+    node::MESH,
+    node::PLANE,
+    node::CURVES,
+    node::PARTICLES,
+    node::ENVIRONMENT,
+];
+
+/// Node types that a [`screen`](node::SCREEN) can be connected to.
+const CAMERAS: [&str; 5] = [
+    node::ORTHOGRAPHIC_CAMERA,
+    node::PERSPECTIVE_CAMERA,
+    node::FISHEYE_CAMERA,
+    node::CYLINDRICAL_CAMERA,
+    node::SPHERICAL_CAMERA,
+];
+
+/// Attribute values with more scalars than this are only copied into the
+/// mirror if [`Graph::mirror_data`] is set. This keeps e.g. matrices.
+pub(crate) const MIRRORED_SCALARS: usize = 16;
+
+const IDENTITY: [f64; 16] = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+    1.0,
+];
+
+/// Returns `a · b` of two row-major 4×4 matrices.
+fn multiply(a: &[f64; 16], b: &[f64; 16]) -> [f64; 16] {
+    std::array::from_fn(|i| {
+        let (row, column) = (i / 4, i % 4);
+        (0..4).map(|k| a[row * 4 + k] * b[k * 4 + column]).sum()
+    })
+}
+
+/// Handles of nodes that exist without being created.
+pub(crate) const BUILT_IN: [&str; 3] = [node::ROOT, node::GLOBAL, node::ALL];
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) node_type: String,
+    pub(crate) attributes: Vec<Attribute>,
+    // User metadata; never sent to the renderer.
+    pub(crate) metadata: HashMap<String, String>,
+}
+
+/// An owned copy of an attribute value set on a node.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    pub(crate) name: Ustr,
+    // The time of the sample if set via set_attribute_at_time().
+    pub(crate) time: Option<f64>,
+    type_: Type,
+    array_length: usize,
+    // Number of elements of type_.
+    len: usize,
+    flags: i32,
+    data: AttributeData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AttributeData {
+    Floats(Vec<f32>),
+    Doubles(Vec<f64>),
+    Integers(Vec<i32>),
+    Strings(Vec<CString>, Vec<*const c_char>),
+    Pointers(Vec<*const c_void>),
+    // A single Reference; the pointer is the data passed to the renderer.
+    Pointer(*const c_void),
+    // The value was not copied; see MIRRORED_SCALARS.
+    Omitted,
+}
+
+impl Attribute {
+    /// Copies the data of `arg`.
+    ///
+    /// Unless `copy_all` is set, data with more than [`MIRRORED_SCALARS`]
+    /// scalars is not copied.
+    ///
+    /// Returns [`None`] for [`Callback`]s as these are owned by the
+    /// renderer once passed.
+    pub(crate) fn from_arg(
+        arg: &Arg,
+        time: Option<f64>,
+        copy_all: bool,
+    ) -> Option<Self> {
+        match arg.data {
+            ArgData::Callback(_) => return None,
+            // The payload pointer of a Reference is passed as is, not a
+            // pointer to it.
+            ArgData::Reference(_) => {
+                return Some(Self {
+                    name: arg.name,
+                    time,
+                    type_: Type::Reference,
+                    array_length: arg.array_length,
+                    len: 1,
+                    flags: arg.flags,
+                    data: AttributeData::Pointer(arg.data.as_c_ptr()),
+                })
+            }
+            _ => (),
+        }
+
+        Some(unsafe {
+            Self::from_raw(
+                arg.name,
+                time,
+                arg.data.type_(),
+                arg.array_length,
+                arg.data.len(),
+                arg.flags,
+                arg.data.as_c_ptr(),
+                copy_all,
+            )
+        })
+    }
+
+    /// Copies `len` elements of `type_` from `data`.
+    ///
+    /// See [`from_arg()`](Attribute::from_arg()) for `copy_all`.
+    ///
+    /// # Safety
+    /// `data` must point to at least `len` elements of `type_`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn from_raw(
+        name: Ustr,
+        time: Option<f64>,
+        type_: Type,
+        array_length: usize,
+        len: usize,
+        flags: i32,
+        data: *const c_void,
+        copy_all: bool,
+    ) -> Self {
+        let scalars = len * type_.component_count();
+
+        let data = match type_ {
+            _ if !copy_all && MIRRORED_SCALARS < scalars => {
+                AttributeData::Omitted
+            }
+            Type::Double | Type::DoubleMatrix => AttributeData::Doubles(
+                std::slice::from_raw_parts(data as *const f64, scalars)
+                    .to_vec(),
+            ),
+            Type::Integer => AttributeData::Integers(
+                std::slice::from_raw_parts(data as *const i32, scalars)
+                    .to_vec(),
+            ),
+            Type::String => {
+                let strings = std::slice::from_raw_parts(
+                    data as *const *const c_char,
+                    scalars,
+                )
+                .iter()
+                .map(|&s| CStr::from_ptr(s).to_owned())
+                .collect::<Vec<_>>();
+                let pointers = strings.iter().map(|s| s.as_ptr()).collect();
+
+                AttributeData::Strings(strings, pointers)
+            }
+            Type::Reference => AttributeData::Pointers(
+                std::slice::from_raw_parts(
+                    data as *const *const c_void,
+                    scalars,
+                )
+                .to_vec(),
+            ),
+            _ => AttributeData::Floats(
+                std::slice::from_raw_parts(data as *const f32, scalars)
+                    .to_vec(),
+            ),
+        };
+
+        Self {
+            name,
+            time,
+            type_,
+            array_length,
+            len,
+            flags,
+            data,
+        }
+    }
+
+    /// Returns a copy of this attribute as an [`OwnedArg`].
+    ///
+    /// A single value that was not set as an array comes back as the
+    /// scalar variant of [`OwnedArgData`], e.g. as a
+    /// [`Float`](OwnedArgData::Float) even if it was set with
+    /// [`floats!`](crate::floats!).
+    ///
+    /// Returns [`None`] for [`Reference`](Type::Reference)s.
+    pub(crate) fn to_owned_arg(&self) -> Option<OwnedArg> {
+        let single = 1 == self.len && 1 == self.array_length;
+
+        let data = match (&self.data, self.type_) {
+            (AttributeData::Floats(data), Type::Float) => match single {
+                true => OwnedArgData::Float(data[0]),
+                false => OwnedArgData::Floats(data.clone()),
+            },
+            (AttributeData::Floats(data), Type::Color) => match single {
+                true => OwnedArgData::Color(data[..3].try_into().ok()?),
+                false => OwnedArgData::Colors(data.clone()),
+            },
+            (AttributeData::Floats(data), Type::Point) => match single {
+                true => OwnedArgData::Point(data[..3].try_into().ok()?),
+                false => OwnedArgData::Points(data.clone()),
+            },
+            (AttributeData::Floats(data), Type::Vector) => match single {
+                true => OwnedArgData::Vector(data[..3].try_into().ok()?),
+                false => OwnedArgData::Vectors(data.clone()),
+            },
+            (AttributeData::Floats(data), Type::Normal) => match single {
+                true => OwnedArgData::Normal(data[..3].try_into().ok()?),
+                false => OwnedArgData::Normals(data.clone()),
+            },
+            (AttributeData::Floats(data), Type::Matrix) => match single {
+                true => OwnedArgData::Matrix(data[..16].try_into().ok()?),
+                false => OwnedArgData::Matrices(data.clone()),
+            },
+            (AttributeData::Doubles(data), Type::Double) => match single {
+                true => OwnedArgData::Double(data[0]),
+                false => OwnedArgData::Doubles(data.clone()),
+            },
+            (AttributeData::Doubles(data), Type::DoubleMatrix) => {
+                match single {
+                    true => {
+                        OwnedArgData::DoubleMatrix(data[..16].try_into().ok()?)
+                    }
+                    false => OwnedArgData::DoubleMatrices(data.clone()),
+                }
+            }
+            (AttributeData::Integers(data), _) => match single {
+                true => OwnedArgData::Integer(data[0]),
+                false => OwnedArgData::Integers(data.clone()),
+            },
+            (AttributeData::Strings(strings, _), _) => {
+                let mut strings = strings
+                    .iter()
+                    .map(|s| s.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>();
+
+                match single {
+                    true => OwnedArgData::String(strings.pop()?),
+                    false => OwnedArgData::Strings(strings),
+                }
+            }
+            _ => return None,
+        };
+
+        Some(OwnedArg {
+            name: self.name,
+            data,
+            array_length: self.array_length,
+            flags: self.flags,
+        })
+    }
+
+    /// Returns an [`NSIParam`] pointing at the data of this attribute.
+    ///
+    /// Returns [`None`] if the data was not copied.
+    pub(crate) fn as_param(&self) -> Option<NSIParam> {
+        Some(NSIParam {
+            name: self.name.as_char_ptr(),
+            data: match &self.data {
+                AttributeData::Floats(data) => data.as_ptr() as _,
+                AttributeData::Doubles(data) => data.as_ptr() as _,
+                AttributeData::Integers(data) => data.as_ptr() as _,
+                AttributeData::Strings(_, pointers) => pointers.as_ptr() as _,
+                AttributeData::Pointers(data) => data.as_ptr() as _,
+                AttributeData::Pointer(data) => *data,
+                AttributeData::Omitted => return None,
+            },
+            type_: self.type_ as _,
+            arraylength: self.array_length as _,
+            count: (self.len / self.array_length) as _,
+            flags: self.flags as _,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Connection {
+    pub(crate) from: String,
+    pub(crate) from_attr: String,
+    pub(crate) to: String,
+    pub(crate) to_attr: String,
+    pub(crate) strength: i32,
+}
+
+// Identifies a connection: (from, from_attr, to, to_attr).
+type ConnectionKey = (String, String, String, String);
+
+impl Connection {
+    fn key(&self) -> ConnectionKey {
+        (
+            self.from.clone(),
+            self.from_attr.clone(),
+            self.to.clone(),
+            self.to_attr.clone(),
+        )
+    }
+}
+
+/// The connections of a [`Graph`], in the order they were made.
+///
+/// Connections are indexed by their ends. Making or removing a connection
+/// does not need to look at all others.
+#[derive(Debug, Default)]
+pub(crate) struct Connections {
+    // None for removed connections until the list is compacted.
+    list: Vec<Option<Connection>>,
+    // Index into list.
+    index: HashMap<ConnectionKey, usize>,
+}
+
+impl Connections {
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Connection> {
+        self.list.iter().flatten()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Adds `connection`, replacing one between the same ends.
+    fn insert(&mut self, connection: Connection) {
+        let key = connection.key();
+
+        if let Some(index) = self.index.insert(key, self.list.len()) {
+            self.list[index] = None;
+        }
+        self.list.push(Some(connection));
+        self.compact();
+    }
+
+    fn remove(&mut self, from: &str, from_attr: &str, to: &str, to_attr: &str) {
+        let key = (
+            from.to_string(),
+            from_attr.to_string(),
+            to.to_string(),
+            to_attr.to_string(),
+        );
+
+        if let Some(index) = self.index.remove(&key) {
+            self.list[index] = None;
+            self.compact();
+        }
+    }
+
+    /// Removes all connections for which `keep` returns `false`.
+    fn retain(&mut self, mut keep: impl FnMut(&Connection) -> bool) {
+        for slot in &mut self.list {
+            if let Some(connection) = slot.as_ref().filter(|c| !keep(c)) {
+                self.index.remove(&connection.key());
+                *slot = None;
+            }
+        }
+        self.compact();
+    }
+
+    // Drops removed connections once they make up half of the list.
+    fn compact(&mut self) {
+        if self.list.len() < 2 * self.index.len() {
+            return;
+        }
+
+        self.list.retain(Option::is_some);
+        for (index, connection) in self.list.iter().flatten().enumerate() {
+            *self.index.get_mut(&connection.key()).unwrap() = index;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Graph {
+    pub(crate) nodes: HashMap<String, Node>,
+    pub(crate) connections: Connections,
+    // Connections of nodes to their parents' "objects", removed by
+    // Context::set_enabled(), keyed by node.
+    pub(crate) disabled: HashMap<String, Vec<Connection>>,
+    // Whether attribute values are copied in full; see MIRRORED_SCALARS.
+    pub(crate) mirror_data: bool,
+}
+
+impl Graph {
+    pub(crate) fn create(&mut self, handle: &str, node_type: &str) {
+        self.nodes.insert(
+            handle.to_string(),
+            Node {
+                node_type: node_type.to_string(),
+                attributes: Vec::new(),
+                metadata: HashMap::new(),
+            },
+        );
+    }
+
+    /// Records `attribute`.
+    ///
+    /// A value without a time replaces all values of the same name. A time
+    /// sample replaces the sample at the same time and any value without a
+    /// time.
+    pub(crate) fn set_attribute(&mut self, handle: &str, attribute: Attribute) {
+        if let Some(node) = self.nodes.get_mut(handle) {
+            node.attributes.retain(|a| {
+                a.name != attribute.name
+                    || (attribute.time.is_some()
+                        && a.time.is_some()
+                        && a.time != attribute.time)
+            });
+            node.attributes.push(attribute);
+        }
+    }
+
+    /// Mirrors `NSIDeleteAttribute()`; removes all time samples.
+    pub(crate) fn delete_attribute(&mut self, handle: &str, name: &str) {
+        if let Some(node) = self.nodes.get_mut(handle) {
+            node.attributes.retain(|a| a.name != name);
+        }
+    }
+
+    /// Sets the metadata `key` on `handle` to `value`.
+    pub(crate) fn set_metadata(
+        &mut self,
+        handle: &str,
+        key: &str,
+        value: &str,
+    ) {
+        if let Some(node) = self.nodes.get_mut(handle) {
+            node.metadata.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    pub(crate) fn metadata(
+        &self,
+        handle: &str,
+    ) -> Option<&HashMap<String, String>> {
+        self.nodes.get(handle).map(|node| &node.metadata)
+    }
+
+    /// Returns all values, i.e. all time samples, of attribute `name` on
+    /// `handle`.
+    pub(crate) fn attributes<'g>(
+        &'g self,
+        handle: &str,
+        name: &'g str,
+    ) -> impl Iterator<Item = &'g Attribute> {
+        self.nodes
+            .get(handle)
+            .into_iter()
+            .flat_map(|node| node.attributes.iter())
+            .filter(move |a| a.name == name)
+    }
+
+    /// Returns the first string of attribute `name` on `handle`, ignoring
+    /// time samples.
+    #[cfg(feature = "output")]
+    pub(crate) fn string_attribute(
+        &self,
+        handle: &str,
+        name: &str,
+    ) -> Option<&str> {
+        self.untimed_attribute(handle, name)
+            .and_then(|a| match &a.data {
+                AttributeData::Strings(strings, _) => strings.first(),
+                _ => None,
+            })
+            .and_then(|string| string.to_str().ok())
+    }
+
+    /// Returns the first integer of attribute `name` on `handle`, ignoring
+    /// time samples.
+    pub(crate) fn integer_attribute(
+        &self,
+        handle: &str,
+        name: &str,
+    ) -> Option<i32> {
+        self.untimed_attribute(handle, name)
+            .and_then(|a| match &a.data {
+                AttributeData::Integers(integers) => integers.first(),
+                _ => None,
+            })
+            .copied()
+    }
+
+    /// Returns the 4×4 matrix of attribute `name` on `handle`, as
+    /// [`f64`]s.
+    ///
+    /// The value without a time is used if there is one, the earliest time
+    /// sample otherwise.
+    pub(crate) fn matrix_attribute(
+        &self,
+        handle: &str,
+        name: &str,
+    ) -> Option<[f64; 16]> {
+        let attribute = self.untimed_attribute(handle, name).or_else(|| {
+            self.attributes(handle, name).min_by(|a, b| {
+                a.time
+                    .unwrap_or_default()
+                    .total_cmp(&b.time.unwrap_or_default())
+            })
+        })?;
+
+        match &attribute.data {
+            AttributeData::Doubles(data) => data.get(..16)?.try_into().ok(),
+            AttributeData::Floats(data) => Some(
+                <[f32; 16]>::try_from(data.get(..16)?).ok()?.map(f64::from),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns the matrix transforming the local space of `handle` to world
+    /// space.
+    ///
+    /// This walks the `"objects"` connections up to the
+    /// [`.root`](node::ROOT) and multiplies the `"transformationmatrix"` of
+    /// each node on the way, including `handle`'s own. If a node is
+    /// connected to several parents, the first connection made is followed.
+    ///
+    /// Returns [`None`] if `handle` is not connected to the root.
+    pub(crate) fn world_matrix(&self, handle: &str) -> Option<[f64; 16]> {
+        let mut matrix = IDENTITY;
+        let mut current = handle;
+        let mut visited = Vec::new();
+
+        while node::ROOT != current {
+            if visited.contains(&current) {
+                return None;
+            }
+            visited.push(current);
+
+            if let Some(local) =
+                self.matrix_attribute(current, "transformationmatrix")
+            {
+                matrix = multiply(&matrix, &local);
+            }
+
+            current = self
+                .connections
+                .iter()
+                .find(|c| c.from == current && "objects" == c.to_attr)?
+                .to
+                .as_str();
+        }
+
+        Some(matrix)
+    }
+
+    fn untimed_attribute(
+        &self,
+        handle: &str,
+        name: &str,
+    ) -> Option<&Attribute> {
+        self.nodes.get(handle).and_then(|node| {
+            node.attributes
+                .iter()
+                .find(|a| a.name == name && a.time.is_none())
+        })
+    }
+
+    /// Returns the handles of all nodes connected to `handle`'s `to_attr`, in
+    /// the order the connections were made.
+    #[cfg(any(test, feature = "output"))]
+    pub(crate) fn inputs<'g>(
+        &'g self,
+        handle: &'g str,
+        to_attr: &'g str,
+    ) -> impl Iterator<Item = &'g str> {
+        self.connections
+            .iter()
+            .filter(move |c| c.to == handle && c.to_attr == to_attr)
+            .map(|c| c.from.as_str())
+    }
+
+    /// Returns the name and number of time samples of each attribute on
+    /// `handle` that was set at specific times.
+    pub(crate) fn time_samples(&self, handle: &str) -> Vec<(Ustr, usize)> {
+        let mut samples = Vec::<(Ustr, usize)>::new();
+
+        for attribute in self
+            .nodes
+            .get(handle)
+            .into_iter()
+            .flat_map(|node| node.attributes.iter())
+            .filter(|a| a.time.is_some())
+        {
+            match samples.iter_mut().find(|(name, _)| *name == attribute.name) {
+                Some((_, count)) => *count += 1,
+                None => samples.push((attribute.name, 1)),
+            }
+        }
+
+        samples
+    }
+
+    /// Mirrors `NSIDelete()`, including the rules for recursive deletion.
+    pub(crate) fn delete(&mut self, handle: &str, recursive: bool) {
+        let mut deleted = vec![handle.to_string()];
+        let mut index = 0;
+
+        while recursive && index < deleted.len() {
+            let target = deleted[index].clone();
+            index += 1;
+
+            let candidates = self
+                .connections
+                .iter()
+                .filter(|c| c.to == target && 0 == c.strength)
+                .map(|c| c.from.clone())
+                .collect::<Vec<_>>();
+
+            for candidate in candidates {
+                if !deleted.contains(&candidate)
+                    && !BUILT_IN.contains(&candidate.as_str())
+                    && self
+                        .connections
+                        .iter()
+                        .filter(|c| c.from == candidate)
+                        .all(|c| deleted.contains(&c.to))
+                {
+                    deleted.push(candidate);
+                }
+            }
+        }
+
+        for handle in &deleted {
+            self.nodes.remove(handle);
+            self.disabled.remove(handle);
+        }
+        self.connections
+            .retain(|c| !deleted.contains(&c.from) && !deleted.contains(&c.to));
+    }
+
+    pub(crate) fn connect(
+        &mut self,
+        from: &str,
+        from_attr: &str,
+        to: &str,
+        to_attr: &str,
+        strength: i32,
+    ) {
+        self.connections.insert(Connection {
+            from: from.to_string(),
+            from_attr: from_attr.to_string(),
+            to: to.to_string(),
+            to_attr: to_attr.to_string(),
+            strength,
+        });
+    }
+
+    pub(crate) fn disconnect(
+        &mut self,
+        from: &str,
+        from_attr: &str,
+        to: &str,
+        to_attr: &str,
+    ) {
+        if node::ALL == from || node::ALL == to {
+            self.connections.retain(|c| {
+                !((node::ALL == from || c.from == from)
+                    && c.from_attr == from_attr
+                    && (node::ALL == to || c.to == to)
+                    && c.to_attr == to_attr)
+            });
+        } else {
+            self.connections.remove(from, from_attr, to, to_attr);
+        }
+    }
+
+    /// Returns all nodes upstream of `handle`, including `handle` itself, and
+    /// all connections between them.
+    ///
+    /// These are the nodes that would be removed by a recursive
+    /// [`delete()`](Graph::delete()) ignoring `strength`.
+    pub(crate) fn subtree(
+        &self,
+        handle: &str,
+    ) -> (Vec<(String, Node)>, Vec<Connection>) {
+        let mut handles = vec![handle.to_string()];
+        let mut index = 0;
+
+        while index < handles.len() {
+            let target = handles[index].clone();
+            index += 1;
+
+            for c in self.connections.iter().filter(|c| c.to == target) {
+                if !handles.contains(&c.from)
+                    && !BUILT_IN.contains(&c.from.as_str())
+                {
+                    handles.push(c.from.clone());
+                }
+            }
+        }
+
+        (
+            handles
+                .iter()
+                .filter_map(|h| {
+                    self.nodes.get(h).map(|node| (h.clone(), node.clone()))
+                })
+                .collect(),
+            self.connections
+                .iter()
+                .filter(|c| {
+                    handles.contains(&c.from) && handles.contains(&c.to)
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub(crate) fn node_type(&self, handle: &str) -> Option<&str> {
+        self.nodes.get(handle).map(|node| node.node_type.as_str())
+    }
+
+    fn has_input(
+        &self,
+        handle: &str,
+        to_attr: &str,
+        from_types: &[&str],
+    ) -> bool {
+        self.connections.iter().any(|c| {
+            c.to == handle
+                && c.to_attr == to_attr
+                && self
+                    .node_type(&c.from)
+                    .is_some_and(|node_type| from_types.contains(&node_type))
+        })
+    }
+
+    /// Returns `true` if an [`attributes`](node::ATTRIBUTES) node with a
+    /// `"surfaceshader"` is connected to `handle` or to any transform above
+    /// it.
+    fn has_material(&self, handle: &str, visited: &mut Vec<String>) -> bool {
+        if visited.iter().any(|h| h == handle) {
+            return false;
+        }
+        visited.push(handle.to_string());
+
+        self.connections
+            .iter()
+            .filter(|c| c.to == handle && "geometryattributes" == c.to_attr)
+            .any(|c| self.has_input(&c.from, "surfaceshader", &[node::SHADER]))
+            || self
+                .connections
+                .iter()
+                .filter(|c| c.from == handle && "objects" == c.to_attr)
+                .any(|c| self.has_material(&c.to, visited))
+    }
+
+    pub(crate) fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        let mut handles = self.nodes.keys().collect::<Vec<_>>();
+        handles.sort();
+
+        for handle in handles {
+            let node_type = self.nodes[handle].node_type.as_str();
+
+            if node::OUTPUT_DRIVER == node_type
+                && !self.connections.iter().any(|c| {
+                    c.from == *handle
+                        && "outputdrivers" == c.to_attr
+                        && self.node_type(&c.to) == Some(node::OUTPUT_LAYER)
+                })
+            {
+                issues.push(ValidationIssue {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "Output driver '{}' is not connected to any output \
+                        layer.",
+                        handle
+                    ),
+                });
+            }
+
+            if node::SCREEN == node_type
+                && !self.connections.iter().any(|c| {
+                    c.from == *handle
+                        && "screens" == c.to_attr
+                        && self
+                            .node_type(&c.to)
+                            .is_some_and(|to_type| CAMERAS.contains(&to_type))
+                })
+            {
+                issues.push(ValidationIssue {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "Screen '{}' is not connected to any camera.",
+                        handle
+                    ),
+                });
+            }
+
+            if GEOMETRY.contains(&node_type)
+                && !self.has_material(handle, &mut Vec::new())
+            {
+                issues.push(ValidationIssue {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "Geometry '{}' ({}) has no material assigned.",
+                        handle, node_type
+                    ),
+                });
+            }
+        }
+
+        for c in self.connections.iter() {
+            for handle in [&c.from, &c.to] {
+                if !BUILT_IN.contains(&handle.as_str())
+                    && !self.nodes.contains_key(handle)
+                {
+                    issues.push(ValidationIssue {
+                        severity: Severity::Error,
+                        message: format!(
+                            "Connection '{}.{}' → '{}.{}' references \
+                            unknown node '{}'.",
+                            c.from, c.from_attr, c.to, c.to_attr, handle
+                        ),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
@@ -14,4 +14,9 @@ impl HandleString {
     pub(crate) fn as_char_ptr(&self) -> *const c_char {
         self.0.as_ptr()
     }
+
+    #[inline(always)]
+    pub(crate) fn as_str(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
 }
@@ -15,4 +15,9 @@ impl HandleString {
     pub(crate) fn as_char_ptr(&self) -> *const c_char {
         self.0.as_char_ptr()
     }
+
+    #[inline(always)]
+    pub(crate) fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
 }
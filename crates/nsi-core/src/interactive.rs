@@ -0,0 +1,193 @@
+//! Editing a scene while it is being rendered.
+use crate::{
+    Action, Arg, ArgData, Callback, Context, Integer, RenderStatus,
+    StatusCallback,
+};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+/// An interactive render that is kept alive across scene edits.
+///
+/// Created with [`Context::interactive_session()`]. The render is started
+/// right away and runs until the session is [stopped](Self::stop()) or
+/// dropped.
+///
+/// Scene edits made through [`edit()`](Self::edit()) are pushed to the
+/// running render once the closure returns. Edits made directly on the
+/// context are picked up on the next [`refresh()`](Self::refresh()).
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("shader", nsi::SHADER, None);
+/// let session = ctx.interactive_session();
+///
+/// for red in [0.25, 0.5, 1.0] {
+///     session.edit(|ctx| {
+///         ctx.set_color("shader", "i_color", [red, 0.0, 0.0]);
+///     });
+/// }
+///
+/// // Blocks until the render has stopped.
+/// session.stop();
+/// ```
+///
+/// # Waiting for the Render
+///
+/// An interactive render does not finish on its own; it keeps refining the
+/// image until it is stopped. [`wait()`](Self::wait()) therefore only
+/// returns once the render was stopped elsewhere. Hand a
+/// [`StopHandle`] to the thread that decides when the render is done, e.g.
+/// a UI, and wait on the session:
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let mut session = ctx.interactive_session();
+/// let stop = session.stop_handle();
+///
+/// std::thread::spawn(move || {
+///     std::thread::sleep(std::time::Duration::from_secs(10));
+///     stop.stop();
+/// });
+///
+/// // Returns once the thread has stopped the render.
+/// let status = session.wait();
+/// ```
+pub struct InteractiveSession<'c, 'a> {
+    ctx: &'c Context<'a>,
+    receiver: Receiver<RenderStatus>,
+    status: Option<RenderStatus>,
+    stopped: bool,
+}
+
+impl<'c, 'a> InteractiveSession<'c, 'a> {
+    pub(crate) fn new(ctx: &'c Context<'a>) -> Self {
+        let (sender, receiver) = channel();
+
+        let status_callback =
+            StatusCallback::new(move |_: &Context, status: RenderStatus| {
+                // The session may be gone already.
+                let _ = sender.send(status);
+            });
+
+        ctx.render_control(
+            Action::Start,
+            Some(&[
+                Arg::new("interactive", ArgData::from(Integer::new(1))),
+                Arg::new(
+                    "callback",
+                    ArgData::from(Callback::new(status_callback)),
+                ),
+            ]),
+        );
+
+        Self {
+            ctx,
+            receiver,
+            status: None,
+            stopped: false,
+        }
+    }
+
+    /// Runs `f` and then pushes the edits it made to the running render.
+    pub fn edit<F>(&self, f: F)
+    where
+        F: FnOnce(&Context<'a>),
+    {
+        f(self.ctx);
+        self.refresh();
+    }
+
+    /// Pushes all edits made on the context so far to the running render.
+    ///
+    /// This is [`Context::synchronize()`].
+    #[inline]
+    pub fn refresh(&self) {
+        self.ctx.synchronize();
+    }
+
+    /// Returns the last status the renderer reported, without blocking.
+    ///
+    /// This is [`None`] until the renderer reports anything.
+    pub fn status(&mut self) -> Option<RenderStatus> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(status) => self.status = Some(status),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => {
+                    return self.status
+                }
+            }
+        }
+    }
+
+    /// Blocks until the renderer reports that it has completed or aborted
+    /// the render.
+    ///
+    /// Returns the final status.
+    ///
+    /// Note that an interactive render keeps running after it has converged.
+    /// It only reports [`RenderStatus::Completed`] when it is stopped, i.e.
+    /// through a [`stop_handle()`](Self::stop_handle()) from another thread.
+    pub fn wait(&mut self) -> RenderStatus {
+        while !matches!(
+            self.status,
+            Some(RenderStatus::Completed) | Some(RenderStatus::Aborted)
+        ) {
+            match self.receiver.recv() {
+                Ok(status) => self.status = Some(status),
+                // The renderer is gone without telling us.
+                Err(_) => return RenderStatus::Aborted,
+            }
+        }
+
+        self.status.unwrap()
+    }
+
+    /// Returns a handle that stops the render from another thread.
+    ///
+    /// Use this to end a [`wait()`](Self::wait()).
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle<'a> {
+        StopHandle(self.ctx.clone())
+    }
+
+    /// Stops the render and blocks until it has finished.
+    ///
+    /// This is what happens when the session is dropped.
+    pub fn stop(mut self) {
+        self.stop_render();
+    }
+
+    fn stop_render(&mut self) {
+        if !self.stopped {
+            self.stopped = true;
+            self.ctx.render_control(Action::Stop, None);
+            self.ctx.render_control(Action::Wait, None);
+        }
+    }
+}
+
+impl Drop for InteractiveSession<'_, '_> {
+    fn drop(&mut self) {
+        self.stop_render();
+    }
+}
+
+/// Stops the render of an [`InteractiveSession`] from another thread.
+///
+/// Created with [`InteractiveSession::stop_handle()`].
+#[derive(Clone, Debug)]
+pub struct StopHandle<'a>(Context<'a>);
+
+impl StopHandle<'_> {
+    /// Stops the render.
+    ///
+    /// This does not block. A [`wait()`](InteractiveSession::wait()) on the
+    /// session returns once the render has stopped.
+    #[inline]
+    pub fn stop(&self) {
+        self.0.render_control(Action::Stop, None);
+    }
+}
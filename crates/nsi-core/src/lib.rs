@@ -37,8 +37,13 @@ extern crate lazy_static;
 
 #[cfg(not(feature = "manual_init"))]
 lazy_static! {
-    static ref NSI_API: api::ApiImpl =
-        api::ApiImpl::new().expect("Could not load lib3delight");
+    // Kept so Context::new_or_explain() can report why loading failed.
+    static ref NSI_API_LOAD: Result<api::ApiImpl, ContextError> =
+        api::ApiImpl::new();
+    static ref NSI_API: &'static api::ApiImpl = match NSI_API_LOAD.as_ref() {
+        Ok(api) => api,
+        Err(e) => panic!("{}", e),
+    };
 }
 
 // Default modules ----------------------------------------------------
@@ -51,6 +56,27 @@ pub use argument::*;
 pub mod context;
 pub use context::*;
 
+pub mod context_builder;
+pub use context_builder::*;
+
+pub mod batch;
+pub use batch::*;
+
+pub mod interactive;
+pub use interactive::*;
+
+pub mod node_builder;
+pub use node_builder::*;
+
+pub mod procedural;
+pub use procedural::*;
+
+pub mod visibility;
+pub use visibility::*;
+
+mod graph;
+pub use graph::{Severity, ValidationIssue};
+
 #[cfg(feature = "output")]
 pub mod output;
 
@@ -8,7 +8,7 @@ pub struct LinkedApi {}
 
 impl LinkedApi {
     #[inline]
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new() -> Result<Self, crate::ContextError> {
         let api = LinkedApi {};
 
         #[cfg(feature = "output")]
@@ -73,3 +73,366 @@ pub const OUTPUT_LAYER: &str = "outputlayer";
 /// Describes how the view from a camera node will be rasterized into an
 /// `outputlayer` node. [Documentation](https://nsi.readthedocs.io/en/latest/nodes.html#node-screen).
 pub const SCREEN: &str = "screen";
+
+/// The kind of data an attribute holds.
+///
+/// The variants are named after the resp. [`ArgData`](crate::ArgData)
+/// variants and argument macros, e.g. [`AttrKind::Integers`] is set with
+/// [`integers!`](crate::integers).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AttrKind {
+    Float,
+    Floats,
+    Double,
+    Doubles,
+    Integer,
+    Integers,
+    String,
+    Strings,
+    Color,
+    Colors,
+    Point,
+    Points,
+    Vector,
+    Vectors,
+    Normal,
+    Normals,
+    Matrix,
+    Matrices,
+    DoubleMatrix,
+    DoubleMatrices,
+    Reference,
+}
+
+/// The standard ɴsɪ node types.
+///
+/// This is the typed counterpart to the string constants in this module,
+/// e.g. [`NodeType::Mesh`] corresponds to [`MESH`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NodeType {
+    Root,
+    Global,
+    Set,
+    Shader,
+    Attributes,
+    Transform,
+    Instances,
+    Plane,
+    Mesh,
+    FaceSet,
+    Curves,
+    Particles,
+    Procedural,
+    Volume,
+    Environment,
+    OrthographicCamera,
+    PerspectiveCamera,
+    FisheyeCamera,
+    CylindricalCamera,
+    SphericalCamera,
+    OutputDriver,
+    OutputLayer,
+    Screen,
+}
+
+const CAMERA_ATTRIBUTES: [(&str, AttrKind); 4] = [
+    ("clippingrange", AttrKind::Doubles),
+    ("shutterrange", AttrKind::Doubles),
+    ("shutteropening", AttrKind::Doubles),
+    ("lensshader", AttrKind::String),
+];
+
+macro_rules! camera_attributes {
+    ($($name: literal => $kind: ident),* $(,)?) => {
+        &[
+            CAMERA_ATTRIBUTES[0],
+            CAMERA_ATTRIBUTES[1],
+            CAMERA_ATTRIBUTES[2],
+            CAMERA_ATTRIBUTES[3],
+            $(($name, AttrKind::$kind),)*
+        ]
+    };
+}
+
+impl NodeType {
+    /// All node types.
+    pub const VARIANTS: [NodeType; 23] = [
+        NodeType::Root,
+        NodeType::Global,
+        NodeType::Set,
+        NodeType::Shader,
+        NodeType::Attributes,
+        NodeType::Transform,
+        NodeType::Instances,
+        NodeType::Plane,
+        NodeType::Mesh,
+        NodeType::FaceSet,
+        NodeType::Curves,
+        NodeType::Particles,
+        NodeType::Procedural,
+        NodeType::Volume,
+        NodeType::Environment,
+        NodeType::OrthographicCamera,
+        NodeType::PerspectiveCamera,
+        NodeType::FisheyeCamera,
+        NodeType::CylindricalCamera,
+        NodeType::SphericalCamera,
+        NodeType::OutputDriver,
+        NodeType::OutputLayer,
+        NodeType::Screen,
+    ];
+
+    /// Returns the name of the node type as used by the ɴsɪ API.
+    ///
+    /// For [`NodeType::Root`] & [`NodeType::Global`] this is the handle of
+    /// the resp. node.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeType::Root => ROOT,
+            NodeType::Global => GLOBAL,
+            NodeType::Set => SET,
+            NodeType::Shader => SHADER,
+            NodeType::Attributes => ATTRIBUTES,
+            NodeType::Transform => TRANSFORM,
+            NodeType::Instances => INSTANCES,
+            NodeType::Plane => PLANE,
+            NodeType::Mesh => MESH,
+            NodeType::FaceSet => FACESET,
+            NodeType::Curves => CURVES,
+            NodeType::Particles => PARTICLES,
+            NodeType::Procedural => PROCEDURAL,
+            NodeType::Volume => VOLUME,
+            NodeType::Environment => ENVIRONMENT,
+            NodeType::OrthographicCamera => ORTHOGRAPHIC_CAMERA,
+            NodeType::PerspectiveCamera => PERSPECTIVE_CAMERA,
+            NodeType::FisheyeCamera => FISHEYE_CAMERA,
+            NodeType::CylindricalCamera => CYLINDRICAL_CAMERA,
+            NodeType::SphericalCamera => SPHERICAL_CAMERA,
+            NodeType::OutputDriver => OUTPUT_DRIVER,
+            NodeType::OutputLayer => OUTPUT_LAYER,
+            NodeType::Screen => SCREEN,
+        }
+    }
+
+    /// Returns the node type called `name`, if it is a standard one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::VARIANTS
+            .into_iter()
+            .find(|node_type| node_type.as_str() == name)
+    }
+
+    /// Returns the common attributes this node type accepts, together with
+    /// the kind of data each holds.
+    ///
+    /// This is a curated subset of what the
+    /// [specification](https://nsi.readthedocs.io/en/latest/nodes.html)
+    /// lists, meant for building editors or auto-completion. Shader
+    /// parameters depend on the shader and are not included. Neither are
+    /// renderer-specific extensions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// use nsi::{AttrKind, NodeType};
+    ///
+    /// assert!(NodeType::PerspectiveCamera
+    ///     .default_attributes()
+    ///     .contains(&("fov", AttrKind::Float)));
+    /// ```
+    pub fn default_attributes(&self) -> &'static [(&'static str, AttrKind)] {
+        match self {
+            NodeType::Root | NodeType::Set | NodeType::Plane => &[],
+            NodeType::Global => &[
+                ("numberofthreads", AttrKind::Integer),
+                ("renderatlowpriority", AttrKind::Integer),
+                ("texturememory", AttrKind::Integer),
+                ("networkcache.size", AttrKind::Integer),
+                ("networkcache.directory", AttrKind::String),
+                ("networkcache.write", AttrKind::Integer),
+                ("license.server", AttrKind::String),
+                ("license.wait", AttrKind::Integer),
+                ("license.hold", AttrKind::Integer),
+                ("bucketorder", AttrKind::String),
+                ("frame", AttrKind::Double),
+                ("maximumraydepth.diffuse", AttrKind::Integer),
+                ("maximumraydepth.hair", AttrKind::Integer),
+                ("maximumraydepth.reflection", AttrKind::Integer),
+                ("maximumraydepth.refraction", AttrKind::Integer),
+                ("maximumraydepth.volume", AttrKind::Integer),
+                ("maximumraylength.diffuse", AttrKind::Double),
+                ("maximumraylength.hair", AttrKind::Double),
+                ("maximumraylength.reflection", AttrKind::Double),
+                ("maximumraylength.refraction", AttrKind::Double),
+                ("maximumraylength.specular", AttrKind::Double),
+                ("maximumraylength.volume", AttrKind::Double),
+                ("quality.shadingsamples", AttrKind::Integer),
+                ("quality.volumesamples", AttrKind::Integer),
+                ("show.displacement", AttrKind::Integer),
+                ("show.osl.subsurface", AttrKind::Integer),
+                ("statistics.progress", AttrKind::Integer),
+                ("statistics.filename", AttrKind::String),
+                ("statistics.format", AttrKind::String),
+                ("progressive.maxpasses", AttrKind::Integer),
+                ("adaptivesampling.enable", AttrKind::Integer),
+                ("adaptivesampling.minsamples", AttrKind::Integer),
+                ("adaptivesampling.maxsamples", AttrKind::Integer),
+                ("adaptivesampling.threshold", AttrKind::Float),
+            ],
+            NodeType::Shader => &[
+                ("shaderfilename", AttrKind::String),
+                ("shadersource", AttrKind::String),
+            ],
+            NodeType::Attributes => &[
+                ("priority", AttrKind::Integer),
+                ("visibility.camera", AttrKind::Integer),
+                ("visibility.diffuse", AttrKind::Integer),
+                ("visibility.hair", AttrKind::Integer),
+                ("visibility.reflection", AttrKind::Integer),
+                ("visibility.refraction", AttrKind::Integer),
+                ("visibility.shadow", AttrKind::Integer),
+                ("visibility.specular", AttrKind::Integer),
+                ("visibility.volume", AttrKind::Integer),
+                ("visibility", AttrKind::Integer),
+                ("matte", AttrKind::Integer),
+                ("regularemission", AttrKind::Integer),
+                ("quantizedemission", AttrKind::Integer),
+                ("displacement.bound", AttrKind::Float),
+            ],
+            NodeType::Transform => {
+                &[("transformationmatrix", AttrKind::DoubleMatrix)]
+            }
+            NodeType::Instances => &[
+                ("transformationmatrices", AttrKind::DoubleMatrices),
+                ("modelindices", AttrKind::Integers),
+                ("disabledinstances", AttrKind::Integers),
+            ],
+            NodeType::Mesh => &[
+                ("P", AttrKind::Points),
+                ("P.indices", AttrKind::Integers),
+                ("nvertices", AttrKind::Integers),
+                ("nholes", AttrKind::Integers),
+                ("clockwisewinding", AttrKind::Integer),
+                ("N", AttrKind::Normals),
+                ("st", AttrKind::Floats),
+                ("subdivision.scheme", AttrKind::String),
+                ("subdivision.cornervertices", AttrKind::Integers),
+                ("subdivision.cornersharpness", AttrKind::Floats),
+                ("subdivision.smoothcreasecorners", AttrKind::Integer),
+                ("subdivision.creasevertices", AttrKind::Integers),
+                ("subdivision.creasesharpness", AttrKind::Floats),
+                ("subdivision.facevaryinginterpolation", AttrKind::Integer),
+            ],
+            NodeType::FaceSet => &[("faces", AttrKind::Integers)],
+            NodeType::Curves => &[
+                ("nvertices", AttrKind::Integers),
+                ("P", AttrKind::Points),
+                ("width", AttrKind::Floats),
+                ("N", AttrKind::Normals),
+                ("basis", AttrKind::String),
+                ("extrapolate", AttrKind::Integer),
+            ],
+            NodeType::Particles => &[
+                ("P", AttrKind::Points),
+                ("width", AttrKind::Floats),
+                ("N", AttrKind::Normals),
+                ("id", AttrKind::Integers),
+            ],
+            NodeType::Procedural => &[
+                ("type", AttrKind::String),
+                ("filename", AttrKind::String),
+                ("boundingbox", AttrKind::Points),
+            ],
+            NodeType::Volume => &[
+                ("vdbfilename", AttrKind::String),
+                ("densitygrid", AttrKind::String),
+                ("colorgrid", AttrKind::String),
+                ("temperaturegrid", AttrKind::String),
+                ("emissionintensitygrid", AttrKind::String),
+                ("emissiongrid", AttrKind::String),
+                ("velocitygrid", AttrKind::String),
+                ("velocityscale", AttrKind::Double),
+            ],
+            NodeType::Environment => &[("angle", AttrKind::Double)],
+            NodeType::OrthographicCamera | NodeType::SphericalCamera => {
+                &CAMERA_ATTRIBUTES
+            }
+            NodeType::PerspectiveCamera => camera_attributes![
+                "fov" => Float,
+                "depthoffield.enable" => Integer,
+                "depthoffield.fstop" => Double,
+                "depthoffield.focallength" => Double,
+                "depthoffield.focaldistance" => Double,
+                "depthoffield.aperture.enable" => Integer,
+                "depthoffield.aperture.sides" => Integer,
+                "depthoffield.aperture.angle" => Double,
+            ],
+            NodeType::FisheyeCamera => camera_attributes![
+                "fov" => Float,
+                "mapping" => String,
+            ],
+            NodeType::CylindricalCamera => camera_attributes![
+                "fov" => Float,
+                "horizontalfov" => Float,
+                "eyeoffset" => Float,
+            ],
+            NodeType::OutputDriver => &[
+                ("drivername", AttrKind::String),
+                ("imagefilename", AttrKind::String),
+                ("embedstatistics", AttrKind::Integer),
+                ("emptybuckets", AttrKind::Integer),
+            ],
+            NodeType::OutputLayer => &[
+                ("variablename", AttrKind::String),
+                ("variablesource", AttrKind::String),
+                ("layertype", AttrKind::String),
+                ("scalarformat", AttrKind::String),
+                ("layername", AttrKind::String),
+                ("colorprofile", AttrKind::String),
+                ("withalpha", AttrKind::Integer),
+                ("sortkey", AttrKind::Integer),
+                ("dithering", AttrKind::Integer),
+                ("filter", AttrKind::String),
+                ("filterwidth", AttrKind::Double),
+                ("backgroundvalue", AttrKind::Float),
+                ("lightexpression", AttrKind::String),
+            ],
+            NodeType::Screen => &[
+                ("resolution", AttrKind::Integers),
+                ("oversampling", AttrKind::Integer),
+                ("crop", AttrKind::Floats),
+                ("prioritywindow", AttrKind::Integers),
+                ("screenwindow", AttrKind::Doubles),
+                ("pixelaspectratio", AttrKind::Float),
+                ("staticsamplingpattern", AttrKind::Integer),
+            ],
+        }
+    }
+}
+
+impl NodeType {
+    /// Returns `true` if `name` is one of the
+    /// [`default_attributes()`](NodeType::default_attributes()) or belongs
+    /// to a family of attributes with arbitrary names, e.g. `"metadata.*"`
+    /// on the [`.global`](GLOBAL) node.
+    pub(crate) fn accepts_attribute(&self, name: &str) -> bool {
+        let prefix = match self {
+            NodeType::Global => Some("metadata."),
+            // The closures of the crate's own output driver.
+            NodeType::OutputDriver => Some("callback."),
+            _ => None,
+        };
+
+        prefix.is_some_and(|prefix| name.starts_with(prefix))
+            || self
+                .default_attributes()
+                .iter()
+                .any(|(known, _)| *known == name)
+    }
+}
+
+impl std::fmt::Display for NodeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
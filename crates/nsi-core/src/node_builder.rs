@@ -0,0 +1,123 @@
+//! Creating a node, setting its attributes and connecting it in one
+//! expression.
+use crate::{Arg, ArgVec, Context, NodeType};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Suffix of the next generated handle.
+static NEXT_HANDLE: AtomicUsize = AtomicUsize::new(0);
+
+/// Builder for a single node.
+///
+/// Created with [`Context::build()`]. Nothing is sent to the renderer until
+/// [`finish()`](NodeBuilder::finish()) is called. This then
+/// [creates](Context::create()) the node, [sets](Context::set_attribute())
+/// all attributes in one call and makes the
+/// [connections](Context::connect()), in that order.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let mesh = ctx
+///     .build(nsi::NodeType::Mesh)
+///     .handle("triangle")
+///     .attr(nsi::integers!("nvertices", &[3]))
+///     .attr(nsi::points!(
+///         "P",
+///         &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+///     ))
+///     .connect_to(nsi::ROOT, "objects")
+///     .finish();
+///
+/// assert_eq!("triangle", mesh);
+/// ```
+#[must_use = "Nothing is created until finish() is called."]
+pub struct NodeBuilder<'c, 'a, 'b> {
+    ctx: &'c Context<'a>,
+    node_type: NodeType,
+    handle: Option<String>,
+    args: ArgVec<'b, 'a>,
+    connections: Vec<(String, String)>,
+}
+
+impl<'c, 'a, 'b> NodeBuilder<'c, 'a, 'b> {
+    #[inline]
+    pub(crate) fn new(ctx: &'c Context<'a>, node_type: NodeType) -> Self {
+        Self {
+            ctx,
+            node_type,
+            handle: None,
+            args: Vec::new(),
+            connections: Vec::new(),
+        }
+    }
+
+    /// Sets the handle of the node.
+    ///
+    /// If this is not called, a handle is generated from the node type,
+    /// e.g. `mesh_3`. It is unique among the nodes of the context.
+    #[inline]
+    pub fn handle(mut self, handle: &str) -> Self {
+        self.handle = Some(handle.to_string());
+        self
+    }
+
+    /// Adds an attribute to set on the node.
+    #[inline]
+    pub fn attr(mut self, arg: Arg<'b, 'a>) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Adds several attributes to set on the node.
+    #[inline]
+    pub fn attrs(
+        mut self,
+        args: impl IntoIterator<Item = Arg<'b, 'a>>,
+    ) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    /// Connects the node to the attribute `to_attr` of the node `to`.
+    ///
+    /// This can be called several times, e.g. to connect a shader to more
+    /// than one [`attributes`](crate::ATTRIBUTES) node.
+    #[inline]
+    pub fn connect_to(mut self, to: &str, to_attr: &str) -> Self {
+        self.connections.push((to.to_string(), to_attr.to_string()));
+        self
+    }
+
+    /// Creates the node, sets its attributes and connects it.
+    ///
+    /// Returns the handle of the node.
+    pub fn finish(self) -> String {
+        let handle = self.handle.unwrap_or_else(|| {
+            let graph = self.ctx.graph();
+            loop {
+                let handle = format!(
+                    "{}_{}",
+                    self.node_type.as_str(),
+                    NEXT_HANDLE.fetch_add(1, Ordering::Relaxed)
+                );
+                if graph.node_type(&handle).is_none() {
+                    break handle;
+                }
+            }
+        });
+
+        self.ctx.create(&handle, self.node_type.as_str(), None);
+
+        if !self.args.is_empty() {
+            self.ctx.set_attribute(&handle, &self.args);
+        }
+
+        for (to, to_attr) in &self.connections {
+            self.ctx.connect(&handle, None, to, to_attr, None);
+        }
+
+        handle
+    }
+}
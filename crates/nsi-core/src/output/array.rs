@@ -0,0 +1,51 @@
+//! Pixel buffers as [`ndarray`] arrays.
+#![cfg_attr(feature = "nightly", doc(cfg(feature = "ndarray")))]
+use crate::output::PixelFormat;
+use ndarray::{Array3, ShapeError};
+
+/// Turns a buffer as delivered by the callbacks into a
+/// `height` × `width` × [`channels()`](PixelFormat::channels()) array.
+///
+/// The buffer is moved into the array, not copied. The channels of a pixel
+/// are in the order of the `format`'s layers. Use
+/// [`Layer::offset()`](crate::output::Layer::offset()) to slice out a
+/// single layer, or [`split_aovs()`](crate::output::split_aovs()) first.
+///
+/// The pixels are [`f32`], like all pixels the callbacks get.
+///
+/// # Errors
+///
+/// A [`ShapeError`] if `pixels` does not hold `width` × `height` pixels of
+/// the `format`.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let finish = nsi::output::FinishCallback::new(
+///     |_: String,
+///      width: usize,
+///      height: usize,
+///      pixel_format: nsi::output::PixelFormat,
+///      pixels: Vec<f32>| {
+///         match nsi::output::to_ndarray(&pixel_format, pixels, width, height)
+///         {
+///             Ok(image) => {
+///                 // The mean of the first channel.
+///                 let mean = image.index_axis(ndarray::Axis(2), 0).mean();
+///
+///                 nsi::output::Error::None
+///             }
+///             Err(_) => nsi::output::Error::BadParameters,
+///         }
+///     },
+/// );
+/// ```
+pub fn to_ndarray(
+    format: &PixelFormat,
+    pixels: Vec<f32>,
+    width: usize,
+    height: usize,
+) -> Result<Array3<f32>, ShapeError> {
+    Array3::from_shape_vec((height, width, format.channels()), pixels)
+}
@@ -0,0 +1,201 @@
+//! Pull-style access to the pixels an output driver receives.
+use crate::{
+    context::{Context, RenderStatus, StatusCallback},
+    output::{Error, FinishCallback, PixelFormat, WriteCallback},
+};
+use std::sync::mpsc::{channel, Receiver};
+
+/// A rectangle of pixels sent by the renderer, as yielded by a
+/// [`BucketStream`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket<T = f32> {
+    /// The `"imagefilename"` of the output driver.
+    pub name: String,
+    /// The width of the whole image.
+    pub width: usize,
+    /// The height of the whole image.
+    pub height: usize,
+    /// The left edge of the bucket.
+    pub x_min: usize,
+    /// One past the right edge of the bucket.
+    pub x_max_plus_one: usize,
+    /// The top edge of the bucket.
+    pub y_min: usize,
+    /// One past the bottom edge of the bucket.
+    pub y_max_plus_one: usize,
+    /// The number of channels of each pixel. See [`PixelFormat`].
+    pub channels: usize,
+    /// The pixels of the bucket, row by row, with `channels` values each.
+    pub pixels: Vec<T>,
+}
+
+enum Message<T> {
+    Bucket(Bucket<T>),
+    Finished,
+}
+
+/// An [`Iterator`] over the [`Bucket`]s an
+/// [`OutputDriver`](crate::OUTPUT_DRIVER) receives.
+///
+/// This is an alternative to handling buckets inside an
+/// [`FnWrite`](crate::output::FnWrite) closure. [`new()`](BucketStream::new())
+/// returns the stream together with a [`WriteCallback`], a
+/// [`FinishCallback`] and a [`StatusCallback`]. Once the first two are set
+/// on a [`FERRIS`](crate::output::FERRIS) output driver, the stream yields
+/// each bucket as it arrives. It ends when the renderer closes the driver.
+///
+/// Pass the [`StatusCallback`] to
+/// [`render_control()`](Context::render_control()) when starting the render.
+/// The stream then also ends when the render completes or is aborted
+/// without the driver ever being opened, e.g. because the scene has no
+/// camera or the render failed.
+///
+/// Iterating blocks while waiting for the next bucket. The render must
+/// therefore be started before iterating and must not be waited for
+/// before the stream has ended.
+///
+/// Connect the callbacks of a stream to a single output driver only. The
+/// stream ends as soon as the first driver is closed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+/// let (buckets, write, finish, status) =
+///     nsi::output::BucketStream::<f32>::new();
+///
+/// ctx.set_attribute(
+///     "driver",
+///     &[
+///         nsi::string!("drivername", nsi::output::FERRIS),
+///         nsi::string!("imagefilename", "render"),
+///         nsi::callback!("callback.write", write),
+///         nsi::callback!("callback.finish", finish),
+///     ],
+/// );
+///
+/// ctx.render_control(
+///     nsi::Action::Start,
+///     Some(&[nsi::callback!("callback", status)]),
+/// );
+///
+/// for bucket in buckets {
+///     println!(
+///         "Bucket {}..{} × {}..{}",
+///         bucket.x_min, bucket.x_max_plus_one, bucket.y_min, bucket.y_max_plus_one
+///     );
+/// }
+///
+/// ctx.render_control(nsi::Action::Wait, None);
+/// ```
+pub struct BucketStream<T = f32> {
+    receiver: Receiver<Message<T>>,
+    finished: bool,
+}
+
+impl<T: From<f32> + Send + 'static> BucketStream<T> {
+    /// Creates a stream and the callbacks that feed it.
+    ///
+    /// Pass the first two callbacks to an output driver as
+    /// `"callback.write"` and `"callback.finish"`. Pass the last one to
+    /// [`render_control()`](Context::render_control()) as `"callback"`.
+    pub fn new() -> (
+        Self,
+        WriteCallback<'static>,
+        FinishCallback<'static>,
+        StatusCallback<'static>,
+    ) {
+        let (sender, receiver) = channel();
+        let finish_sender = sender.clone();
+        let status_sender = sender.clone();
+
+        let write = WriteCallback::new(
+            move |name: &str,
+                  width: usize,
+                  height: usize,
+                  x_min: usize,
+                  x_max_plus_one: usize,
+                  y_min: usize,
+                  y_max_plus_one: usize,
+                  pixel_format: &PixelFormat,
+                  pixel_data: &[f32]| {
+                let channels = pixel_format.channels();
+
+                let pixels = (y_min..y_max_plus_one)
+                    .flat_map(|y| {
+                        pixel_data[(y * width + x_min) * channels
+                            ..(y * width + x_max_plus_one) * channels]
+                            .iter()
+                            .map(|&value| T::from(value))
+                    })
+                    .collect();
+
+                match sender.send(Message::Bucket(Bucket {
+                    name: name.to_string(),
+                    width,
+                    height,
+                    x_min,
+                    x_max_plus_one,
+                    y_min,
+                    y_max_plus_one,
+                    channels,
+                    pixels,
+                })) {
+                    Ok(_) => Error::None,
+                    // The stream was dropped; nobody is interested anymore.
+                    Err(_) => Error::Stop,
+                }
+            },
+        );
+
+        let finish = FinishCallback::new(
+            move |_: String,
+                  _: usize,
+                  _: usize,
+                  _: PixelFormat,
+                  _: Vec<f32>| {
+                // The stream may already be gone; that is fine.
+                let _ = finish_sender.send(Message::Finished);
+                Error::None
+            },
+        );
+
+        let status =
+            StatusCallback::new(move |_: &Context, status: RenderStatus| {
+                if let RenderStatus::Completed | RenderStatus::Aborted = status
+                {
+                    let _ = status_sender.send(Message::Finished);
+                }
+            });
+
+        (
+            Self {
+                receiver,
+                finished: false,
+            },
+            write,
+            finish,
+            status,
+        )
+    }
+}
+
+impl<T> Iterator for BucketStream<T> {
+    type Item = Bucket<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.receiver.recv() {
+            Ok(Message::Bucket(bucket)) => Some(bucket),
+            Ok(Message::Finished) | Err(_) => {
+                self.finished = true;
+                None
+            }
+        }
+    }
+}
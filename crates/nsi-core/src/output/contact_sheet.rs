@@ -0,0 +1,248 @@
+//! A single image showing all layers of a render side by side.
+use crate::output::{linear_to_srgb, split_aovs, LayerDepth, PixelFormat};
+
+// Height of the label bar above each cell, in pixels.
+const LABEL_HEIGHT: usize = 14;
+// Size of a glyph of the label font, in pixels, before scaling.
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const GLYPH_SCALE: usize = 2;
+const LABEL_BACKGROUND: [u8; 3] = [32, 32, 32];
+const LABEL_FOREGROUND: [u8; 3] = [224, 224, 224];
+
+// A 3×5 pixel font. Each row of a glyph is a 3 bit mask, left pixel in the
+// highest bit. Lowercase letters are drawn as uppercase, anything else not
+// in here as a space.
+const GLYPHS: [(char, [u8; 5]); 39] = [
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b110, 0b001, 0b010, 0b100, 0b111]),
+    ('3', [0b110, 0b001, 0b010, 0b001, 0b110]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b110, 0b001, 0b110]),
+    ('6', [0b011, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b110]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+];
+
+/// Tiles all layers of a render into one labeled image and returns it as
+/// an encoded 8bit RGB PNG.
+///
+/// This is meant for debugging renders with many AOVs, e.g. as an artifact
+/// of a CI run or to show in a notebook. It is enabled through the
+/// `contact_sheet` feature. The layers are split with
+/// [`split_aovs()`] and laid out in a grid of `width` × `height` cells, in
+/// the order of the `format`. Each cell has a bar with the layer's
+/// [`name()`](crate::output::Layer::name()) above it.
+///
+/// How a layer is mapped to the 8bit of the image depends on its
+/// [`depth()`](crate::output::Layer::depth()):
+///
+/// | Layer type        | Mapping
+/// |-------------------|-----------------------------------------------------
+/// | Color             | Linear to sRGB; values outside `0..1` are clamped.
+/// | Vector            | `v × ½ + ½`, without a curve; i.e. a unit normal map.
+/// | Four channels     | The first three channels, like a color.
+/// | Scalar            | Normalized to the smallest & largest finite value of the layer, as gray.
+///
+/// Alpha channels are not shown. Colors are premultiplied and hence appear
+/// composited over black.
+///
+/// # Panics
+///
+/// If `format` has no layers or if `pixels` does not hold `width` ×
+/// `height` pixels of the `format`.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let finish = nsi::output::FinishCallback::new(
+///     |_: String,
+///      width: usize,
+///      height: usize,
+///      pixel_format: nsi::output::PixelFormat,
+///      pixels: Vec<f32>| {
+///         let png = nsi::output::contact_sheet(
+///             &pixel_format,
+///             &pixels,
+///             width,
+///             height,
+///         );
+///         std::fs::write("aovs.png", png).unwrap();
+///
+///         nsi::output::Error::None
+///     },
+/// );
+/// ```
+pub fn contact_sheet(
+    format: &PixelFormat,
+    pixels: &[f32],
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    assert!(!format.is_empty(), "The pixel format has no layers.");
+
+    let aovs = split_aovs(format, pixels, width, height);
+
+    let columns = (format.len() as f64).sqrt().ceil() as usize;
+    let rows = format.len().div_ceil(columns);
+
+    let cell_height = height + LABEL_HEIGHT;
+    let sheet_width = columns * width;
+    let sheet_height = rows * cell_height;
+
+    let mut sheet = vec![0u8; sheet_width * sheet_height * 3];
+
+    for (index, (layer, data)) in aovs.iter().enumerate() {
+        let left = (index % columns) * width;
+        let top = (index / columns) * cell_height;
+
+        let mut put = |x: usize, y: usize, rgb: [u8; 3]| {
+            let offset = ((top + y) * sheet_width + left + x) * 3;
+            sheet[offset..offset + 3].copy_from_slice(&rgb);
+        };
+
+        for y in 0..LABEL_HEIGHT {
+            for x in 0..width {
+                put(x, y, LABEL_BACKGROUND);
+            }
+        }
+        draw_label(layer.name(), width, &mut put);
+
+        let cell = tonemap(layer.depth(), data);
+        for (i, rgb) in cell.into_iter().enumerate() {
+            put(i % width, LABEL_HEIGHT + i / width, rgb);
+        }
+    }
+
+    let mut png = Vec::new();
+    {
+        let mut encoder =
+            png::Encoder::new(&mut png, sheet_width as _, sheet_height as _);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&sheet))
+            .expect("Could not encode the contact sheet.");
+    }
+
+    png
+}
+
+/// Maps the tightly packed `data` of a layer to one 8bit RGB triplet per
+/// pixel.
+fn tonemap(depth: LayerDepth, data: &[f32]) -> Vec<[u8; 3]> {
+    let quantize = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    match depth {
+        LayerDepth::OneChannel | LayerDepth::OneChannelAndAlpha => {
+            let channels = depth.channels();
+            let values = data.iter().step_by(channels).copied();
+
+            let (min, max) =
+                values.clone().filter(|value| value.is_finite()).fold(
+                    (f32::INFINITY, f32::NEG_INFINITY),
+                    |(min, max), v| (min.min(v), max.max(v)),
+                );
+            let range = if max > min { max - min } else { 1.0 };
+
+            values
+                .map(|value| {
+                    let gray = if value.is_finite() {
+                        quantize((value - min) / range)
+                    } else {
+                        0
+                    };
+                    [gray; 3]
+                })
+                .collect()
+        }
+        LayerDepth::Vector | LayerDepth::VectorAndAlpha => data
+            .chunks_exact(depth.channels())
+            .map(|v| [0, 1, 2].map(|i| quantize(v[i] * 0.5 + 0.5)))
+            .collect(),
+        LayerDepth::Color
+        | LayerDepth::ColorAndAlpha
+        | LayerDepth::FourChannels
+        | LayerDepth::FourChannelsAndAlpha => data
+            .chunks_exact(depth.channels())
+            .map(|c| [0, 1, 2].map(|i| quantize(linear_to_srgb(c[i]))))
+            .collect(),
+    }
+}
+
+/// Draws `text` into the label bar of a cell `width` pixels wide.
+///
+/// Text that does not fit is cut off.
+fn draw_label(
+    text: &str,
+    width: usize,
+    put: &mut impl FnMut(usize, usize, [u8; 3]),
+) {
+    let advance = (GLYPH_WIDTH + 1) * GLYPH_SCALE;
+    let top = (LABEL_HEIGHT - GLYPH_HEIGHT * GLYPH_SCALE) / 2;
+
+    for (index, c) in text.chars().enumerate() {
+        let left = 2 + index * advance;
+        if width < left + GLYPH_WIDTH * GLYPH_SCALE {
+            break;
+        }
+
+        let Some((_, glyph)) = GLYPHS
+            .iter()
+            .find(|(glyph, _)| *glyph == c.to_ascii_uppercase())
+        else {
+            continue;
+        };
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if 0 == bits & (0b100 >> column) {
+                    continue;
+                }
+
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        put(
+                            left + column * GLYPH_SCALE + dx,
+                            top + row * GLYPH_SCALE + dy,
+                            LABEL_FOREGROUND,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -16,6 +16,13 @@
 //! * [`FnFinish`] is called once when the
 //!   [`OutputDriver`](crate::OUTPUT_DRIVER) is *closed* by the renderer.
 //!
+//! There is also an optional [`FnPass`] closure. It is called each time the
+//! renderer completed a full pass over the image. This is mostly useful for
+//! [progressive](crate::Context::set_progressive()) renders.
+//!
+//! The optional [`FnProgress`] closure receives the progress of the render.
+//! Without it, the driver does not report progress at all.
+//!
 //! As a user you can choose how to use this API.
 //!
 //! * To get a single buffer of pixel data when rendering is finished it is
@@ -24,9 +31,41 @@
 //! * To get the pixel buffer updated while the renderer is working implement an
 //!   [`FnWrite`] closure.
 //!
+//! * To iterate over the buckets as they arrive, instead of handling them in a
+//!   closure, use a [`BucketStream`].
+//!
 //! The format of the [`Vec<f32>`] buffer is described by the [`PixelFormat`]
 //! parameter which is passed to both of these closures.
 //!
+//! ## Empty Buckets
+//!
+//! By default the renderer also sends buckets that contain no geometry.
+//! Every pixel of the image is hence written at least once. Consumers that
+//! draw buckets into a live preview rely on this to clear regions left over
+//! from a previous render.
+//!
+//! Setting the integer attribute `"emptybuckets"` of the
+//! [`OutputDriver`](crate::OUTPUT_DRIVER) node to `0` asks the renderer to
+//! skip those. [`FnWrite`] is then only called for buckets with geometry in
+//! them. The pixels of the others stay `0.0` in the buffer passed to
+//! [`FnFinish`]. This saves work and is what writers of image files want.
+//!
+//! The setting is ignored when an [`FnPass`] closure is set. Pass boundaries
+//! are found by counting pixels, which only works if every bucket is sent.
+//!
+//! ```
+//! # use nsi_core as nsi;
+//! # let ctx = nsi::Context::new(None).unwrap();
+//! ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+//! ctx.set_attribute(
+//!     "driver",
+//!     &[
+//!         nsi::string!("drivername", nsi::output::FERRIS),
+//!         nsi::integer!("emptybuckets", 0),
+//!     ],
+//! );
+//! ```
+//!
 //! ## Example
 //! ```
 //! # use nsi_core as nsi;
@@ -117,6 +156,10 @@
 //! [profile/LUT](https://github.com/colour-science/OpenColorIO-Configs/tree/feature/aces-1.2-config/aces_1.2/luts)
 //! before quantizing (see below).
 //!
+//! The color spaces & displays of the OCIO config the user has set up via
+//! `$OCIO` can be listed with [`ocio_from_env()`], e.g. to offer them in a
+//! UI.
+//!
 //! Once OCIO has a [Rust wrapper](https://crates.io/crates/opencolorio) you can easily choose to
 //! do these color conversions yourself. In the meantime there is the
 //! [`colorspace`](https://crates.io/crates/colorspace) crate which has some useful profiles built
@@ -164,6 +207,28 @@ use std::{
 pub mod pixel_format;
 pub use pixel_format::*;
 
+pub mod srgb;
+pub use srgb::*;
+
+pub mod bucket_stream;
+pub use bucket_stream::*;
+
+pub mod setup;
+pub use setup::*;
+
+#[cfg(feature = "contact_sheet")]
+pub mod contact_sheet;
+#[cfg(feature = "contact_sheet")]
+pub use contact_sheet::*;
+
+pub mod ocio;
+pub use ocio::*;
+
+#[cfg(feature = "ndarray")]
+pub mod array;
+#[cfg(feature = "ndarray")]
+pub use array::*;
+
 /// This is the name of the crate’s built-in output driver that understands the
 /// "closure.*" attributes.
 pub static FERRIS: &str = "ferris";
@@ -450,6 +515,135 @@ pub trait FnFinish<'a> = dyn FnMut(
     + 'a;
 */
 
+/// A closure which is called each time the renderer finished a full pass
+/// over the image.
+///
+/// It is passed to ɴsɪ via the `"callback.pass"` attribute on an
+/// [`OutputDriver`](crate::OUTPUT_DRIVER) node.
+///
+/// In a [progressive](crate::Context::set_progressive()) render the image is
+/// refined in several passes and [`FnWrite`] is called for the same pixels
+/// once per pass. A live viewer can use this closure to know when it is worth
+/// refreshing its display.
+///
+/// # Pass Boundaries
+///
+/// The renderer does not tell an output driver when a pass is complete. The
+/// [`FERRIS`] driver counts the pixels it received instead. A pass is
+/// considered complete once as many pixels as the image has were written
+/// since the previous pass boundary.
+///
+/// This requires every bucket of the image to be sent. When this closure is
+/// set the driver hence always asks for
+/// [empty buckets](crate::output#empty-buckets), whatever the
+/// `"emptybuckets"` attribute is set to.
+///
+/// For a non-progressive render this closure is called once, after the last
+/// bucket was written.
+///
+/// # Arguments
+/// The `pass` parameter is the index of the pass that was just completed,
+/// starting from `0`. The `pixel_data` contains the image as of the end of
+/// this pass.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "output")]
+/// # {
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("display_driver", nsi::OUTPUT_DRIVER, None);
+/// let pass = nsi::output::PassCallback::new(
+///     |_name: &str,
+///      _width: usize,
+///      _height: usize,
+///      pass: usize,
+///      _pixel_format: &nsi::output::PixelFormat,
+///      _pixel_data: &[f32]| {
+///         println!("Pass {} done, refreshing display", pass);
+///         nsi::output::Error::None
+///     },
+/// );
+///
+/// ctx.set_attribute(
+///     "display_driver",
+///     &[
+///         nsi::string!("drivername", "ferris"),
+///         nsi::callback!("callback.pass", pass),
+///     ],
+/// );
+/// # }
+/// ```
+pub trait FnPass<'a>: FnMut(
+    // Filename.
+    &str,
+    // Width.
+    usize,
+    // Height.
+    usize,
+    // Index of the pass that was completed.
+    usize,
+    // Pixel format.
+    &PixelFormat,
+    // Pixel data.
+    &[f32],
+) -> Error
++ 'a {}
+
+#[doc(hidden)]
+impl<
+        'a,
+        T: FnMut(&str, usize, usize, usize, &PixelFormat, &[f32]) -> Error + 'a,
+    > FnPass<'a> for T
+{
+}
+
+/// A closure which is called with the progress of the render.
+///
+/// It is passed to ɴsɪ via the `"callback.progress"` attribute on an
+/// [`OutputDriver`](crate::OUTPUT_DRIVER) node.
+///
+/// The [`FERRIS`] driver is silent otherwise; use this closure to print or
+/// log the progress, or to drive a progress bar.
+///
+/// # Arguments
+/// The `progress` parameter is the fraction of the render that is done, from
+/// `0.0` to `1.0`.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "output")]
+/// # {
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("display_driver", nsi::OUTPUT_DRIVER, None);
+/// let progress = nsi::output::ProgressCallback::new(
+///     |_name: &str, progress: f32| {
+///         eprint!("\rProgress: {:.0}%", progress * 100.0);
+///         nsi::output::Error::None
+///     },
+/// );
+///
+/// ctx.set_attribute(
+///     "display_driver",
+///     &[
+///         nsi::string!("drivername", "ferris"),
+///         nsi::callback!("callback.progress", progress),
+///     ],
+/// );
+/// # }
+/// ```
+pub trait FnProgress<'a>: FnMut(
+    // Filename.
+    &str,
+    // Progress; 0..1.
+    f32,
+) -> Error
++ 'a {}
+
+#[doc(hidden)]
+impl<'a, T: FnMut(&str, f32) -> Error + 'a> FnProgress<'a> for T {}
+
 enum Query {}
 
 trait FnQuery<'a>: FnMut(Query) -> Error + 'a {}
@@ -468,6 +662,7 @@ pub struct OpenCallback<'a>(Box<Box<Box<dyn FnOpen<'a>>>>);
 // No idea and neither had anyone from the Rust community. But omitting a
 // single Box wrapper layer leads to an instant segfault.
 impl<'a> OpenCallback<'a> {
+    #[must_use]
     pub fn new<F>(fn_open: F) -> Self
     where
         F: FnOpen<'a>,
@@ -487,6 +682,7 @@ impl CallbackPtr for OpenCallback<'_> {
 pub struct WriteCallback<'a>(Box<Box<Box<dyn FnWrite<'a>>>>);
 
 impl<'a> WriteCallback<'a> {
+    #[must_use]
     pub fn new<F>(fn_write: F) -> Self
     where
         F: FnWrite<'a>,
@@ -507,6 +703,7 @@ impl CallbackPtr for WriteCallback<'_> {
 pub struct FinishCallback<'a>(Box<Box<Box<dyn FnFinish<'a>>>>);
 
 impl<'a> FinishCallback<'a> {
+    #[must_use]
     pub fn new<F>(fn_finish: F) -> Self
     where
         F: FnFinish<'a>,
@@ -522,6 +719,52 @@ impl CallbackPtr for FinishCallback<'_> {
     }
 }
 
+/// Wrapper to pass an [`FnPass`] closure to an
+/// [`OutputDriver`](crate::OUTPUT_DRIVER) node.
+// See OpenCallback on why this needs a triple Box.
+#[allow(clippy::redundant_allocation)]
+pub struct PassCallback<'a>(Box<Box<Box<dyn FnPass<'a>>>>);
+
+impl<'a> PassCallback<'a> {
+    #[must_use]
+    pub fn new<F>(fn_pass: F) -> Self
+    where
+        F: FnPass<'a>,
+    {
+        PassCallback(Box::new(Box::new(Box::new(fn_pass))))
+    }
+}
+
+impl CallbackPtr for PassCallback<'_> {
+    #[doc(hidden)]
+    fn to_ptr(self) -> *const core::ffi::c_void {
+        Box::into_raw(self.0) as *const _ as _
+    }
+}
+
+/// Wrapper to pass an [`FnProgress`] closure to an
+/// [`OutputDriver`](crate::OUTPUT_DRIVER) node.
+// See OpenCallback on why this needs a triple Box.
+#[allow(clippy::redundant_allocation)]
+pub struct ProgressCallback<'a>(pub(crate) Box<Box<Box<dyn FnProgress<'a>>>>);
+
+impl<'a> ProgressCallback<'a> {
+    #[must_use]
+    pub fn new<F>(fn_progress: F) -> Self
+    where
+        F: FnProgress<'a>,
+    {
+        ProgressCallback(Box::new(Box::new(Box::new(fn_progress))))
+    }
+}
+
+impl CallbackPtr for ProgressCallback<'_> {
+    #[doc(hidden)]
+    fn to_ptr(self) -> *const core::ffi::c_void {
+        Box::into_raw(self.0) as *const _ as _
+    }
+}
+
 struct DisplayData<'a> {
     name: String,
     width: usize,
@@ -530,6 +773,14 @@ struct DisplayData<'a> {
     pixel_data: Vec<f32>,
     fn_write: Option<Box<Box<Box<dyn FnWrite<'a>>>>>,
     fn_finish: Option<Box<Box<Box<dyn FnFinish<'a>>>>>,
+    #[allow(clippy::redundant_allocation)]
+    fn_pass: Option<Box<Box<Box<dyn FnPass<'a>>>>>,
+    #[allow(clippy::redundant_allocation)]
+    fn_progress: Option<Box<Box<Box<dyn FnProgress<'a>>>>>,
+    // The index of the pass currently being rendered.
+    pass: usize,
+    // The number of pixels received during the current pass.
+    pass_pixels: usize,
     // FIXME: unused atm.
     fn_query: Option<Box<Box<Box<dyn FnQuery<'a>>>>>,
 }
@@ -561,6 +812,21 @@ fn get_parameter_triple_box<T: ?Sized>(
     None
 }
 
+fn get_parameter_integer(
+    name: &str,
+    parameters: &[ndspy_sys::UserParameter],
+) -> Option<i32> {
+    parameters
+        .iter()
+        .find(|p| {
+            b'i' == p.valueType as _
+                && 1 == p.valueCount
+                && !p.value.is_null()
+                && name == unsafe { CStr::from_ptr(p.name) }.to_str().unwrap()
+        })
+        .map(|p| unsafe { *(p.value as *const i32) })
+}
+
 // Trampoline function for the FnOpen callback.
 #[no_mangle]
 pub(crate) extern "C" fn image_open(
@@ -611,6 +877,20 @@ pub(crate) extern "C" fn image_open(
             1,
             parameters,
         ),
+        fn_pass: get_parameter_triple_box::<dyn FnPass>(
+            "callback.pass",
+            b'p',
+            1,
+            parameters,
+        ),
+        fn_progress: get_parameter_triple_box::<dyn FnProgress>(
+            "callback.progress",
+            b'p',
+            1,
+            parameters,
+        ),
+        pass: 0,
+        pass_pixels: 0,
         fn_query: None, /* get_parameter_triple_box::<FnQuery>("callback.
                          * query", b'p', 1,
                          * parameters), */
@@ -646,10 +926,21 @@ pub(crate) extern "C" fn image_open(
         Error::None
     };
 
+    // Pass boundaries are found by counting pixels so FnPass needs all
+    // buckets.
+    let wants_empty_buckets = display_data.fn_pass.is_some()
+        || !matches!(
+            get_parameter_integer("emptybuckets", parameters),
+            Some(0)
+        );
+
     unsafe {
         *image_handle_ptr = Box::into_raw(display_data) as _;
-        // We want to be called for all buckets.
-        (*flag_stuff).flags = ndspy_sys::PkDspyFlagsWantsEmptyBuckets as _;
+        (*flag_stuff).flags = if wants_empty_buckets {
+            ndspy_sys::PkDspyFlagsWantsEmptyBuckets as _
+        } else {
+            0
+        };
     }
 
     error.into()
@@ -679,6 +970,20 @@ pub(crate) extern "C" fn image_query(
                 Error::None
             }
         }
+        // We can deal with buckets being sent more than once.
+        ndspy_sys::PtDspyQueryType::Progressive => {
+            if (data_len as usize)
+                < core::mem::size_of::<ndspy_sys::PtDspyProgressiveInfo>()
+            {
+                Error::BadParameters
+            } else {
+                unsafe {
+                    (*(data as *mut ndspy_sys::PtDspyProgressiveInfo))
+                        .acceptProgressive = 1;
+                }
+                Error::None
+            }
+        }
         _ => Error::Unsupported,
     }
     .into()
@@ -727,7 +1032,7 @@ pub(crate) extern "C" fn image_write(
     }
 
     // Call the closure.
-    if let Some(ref mut fn_write) = display_data.fn_write {
+    let error = if let Some(ref mut fn_write) = display_data.fn_write {
         fn_write(
             &display_data.name,
             display_data.width,
@@ -741,8 +1046,35 @@ pub(crate) extern "C" fn image_write(
         )
     } else {
         Error::None
+    };
+
+    if Error::None != error {
+        return error.into();
     }
-    .into()
+
+    // Did we complete a pass?
+    display_data.pass_pixels +=
+        ((x_max_plus_one - x_min) * (y_max_plus_one - y_min)) as usize;
+
+    if display_data.width * display_data.height <= display_data.pass_pixels {
+        display_data.pass_pixels = 0;
+        let pass = display_data.pass;
+        display_data.pass += 1;
+
+        if let Some(ref mut fn_pass) = display_data.fn_pass {
+            return fn_pass(
+                &display_data.name,
+                display_data.width,
+                display_data.height,
+                pass,
+                &display_data.pixel_format,
+                display_data.pixel_data.as_slice(),
+            )
+            .into();
+        }
+    }
+
+    Error::None.into()
 }
 
 // Trampoline function for the FnFinish callback.
@@ -776,15 +1108,54 @@ pub(crate) extern "C" fn image_close(
     if let Some(fn_finish) = display_data.fn_finish {
         Box::leak(fn_finish);
     }
+    if let Some(fn_pass) = display_data.fn_pass {
+        Box::leak(fn_pass);
+    }
+    if let Some(fn_progress) = display_data.fn_progress {
+        Box::leak(fn_progress);
+    }
 
     error.into()
 }
 
+// Trampoline function for the FnProgress callback.
 #[no_mangle]
-extern "C" fn image_progress(
-    _image_handle_ptr: ndspy_sys::PtDspyImageHandle,
+pub(crate) extern "C" fn image_progress(
+    image_handle_ptr: ndspy_sys::PtDspyImageHandle,
     progress: f32,
 ) -> ndspy_sys::PtDspyError {
-    println!("\rProgress: {}", progress * 100.0);
-    Error::None.into()
+    if image_handle_ptr.is_null() {
+        return Error::BadParameters.into();
+    }
+
+    let display_data = unsafe { &mut *(image_handle_ptr as *mut DisplayData) };
+
+    match display_data.fn_progress {
+        Some(ref mut fn_progress) => fn_progress(&display_data.name, progress),
+        // Stay silent.
+        None => Error::None,
+    }
+    .into()
+}
+
+/// Returns the handle of a driver without pixels that only has the given
+/// `fn_progress`. Free it with `image_close()`.
+#[cfg(test)]
+pub(crate) fn progress_only_image_handle(
+    fn_progress: Option<ProgressCallback>,
+) -> ndspy_sys::PtDspyImageHandle {
+    Box::into_raw(Box::new(DisplayData {
+        name: "progress".to_string(),
+        width: 0,
+        height: 0,
+        pixel_format: PixelFormat::default(),
+        pixel_data: Vec::new(),
+        fn_write: None,
+        fn_finish: None,
+        fn_pass: None,
+        fn_progress: fn_progress.map(|callback| callback.0),
+        pass: 0,
+        pass_pixels: 0,
+        fn_query: None,
+    })) as _
 }
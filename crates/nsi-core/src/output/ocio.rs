@@ -0,0 +1,154 @@
+//! Discovery of the color spaces & displays of an OCIO config.
+use std::path::Path;
+
+/// The color spaces & displays of an
+/// [OpenColorIO](https://opencolorio.org/) (OCIO) config.
+///
+/// This is not a full OCIO implementation. Only the names are read from the
+/// config, so a UI can offer them, e.g. as the `"colorprofile"` of an
+/// [`outputlayer`](crate::OUTPUT_LAYER). The renderer does the actual color
+/// conversion. If that renderer is 3Delight, it reads the same config
+/// through `$OCIO`.
+///
+/// Both OCIO v1 & v2 configs are understood. Color spaces include the
+/// `display_colorspaces` of v2 configs. Anything else in the config, e.g.
+/// roles, looks or file rules, is ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OcioConfig {
+    color_spaces: Vec<String>,
+    displays: Vec<OcioDisplay>,
+}
+
+/// A display of an [`OcioConfig`] with the views it offers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OcioDisplay {
+    name: String,
+    views: Vec<String>,
+}
+
+impl OcioDisplay {
+    /// The name of the display, e.g. `sRGB`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of the views of the display, e.g. `Film` or `Raw`.
+    #[inline]
+    pub fn views(&self) -> &[String] {
+        &self.views
+    }
+}
+
+impl OcioConfig {
+    /// Reads the config at `path`.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Reads the names from the YAML text of a config.
+    ///
+    /// Unknown or malformed parts are skipped.
+    pub fn parse(config: &str) -> Self {
+        let mut ocio = OcioConfig::default();
+        let mut section = "";
+        let mut in_color_space = false;
+
+        for line in config.lines() {
+            let content = line.split('#').next().unwrap_or_default();
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            // A top level key starts a new section.
+            if !content.starts_with([' ', '\t', '-']) {
+                section = content.split(':').next().unwrap_or_default().trim();
+                in_color_space = false;
+                continue;
+            }
+
+            let entry = content.trim_start().trim_start_matches("- ");
+
+            match section {
+                "colorspaces" | "display_colorspaces" => {
+                    if entry.starts_with("!<ColorSpace>") {
+                        in_color_space = true;
+                    }
+                    if in_color_space {
+                        if let Some(name) = value_of(entry, "name") {
+                            ocio.color_spaces.push(name);
+                            in_color_space = false;
+                        }
+                    }
+                }
+                "displays" => {
+                    if entry.starts_with("!<View>") {
+                        if let (Some(display), Some(view)) =
+                            (ocio.displays.last_mut(), value_of(entry, "name"))
+                        {
+                            display.views.push(view);
+                        }
+                    } else if let Some(name) = entry.trim().strip_suffix(':') {
+                        ocio.displays.push(OcioDisplay {
+                            name: unquote(name),
+                            views: Vec::new(),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        ocio
+    }
+
+    /// The names of all color spaces, in the order of the config.
+    #[inline]
+    pub fn color_spaces(&self) -> &[String] {
+        &self.color_spaces
+    }
+
+    /// All displays, in the order of the config.
+    #[inline]
+    pub fn displays(&self) -> &[OcioDisplay] {
+        &self.displays
+    }
+}
+
+/// Reads the OCIO config the `$OCIO` environment variable points to.
+///
+/// This is the config OCIO-aware applications, including 3Delight, use
+/// unless told otherwise.
+///
+/// Returns [`None`] if `$OCIO` is not set or the config can not be read.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// if let Some(config) = nsi::output::ocio_from_env() {
+///     for color_space in config.color_spaces() {
+///         println!("{}", color_space);
+///     }
+/// }
+/// ```
+pub fn ocio_from_env() -> Option<OcioConfig> {
+    OcioConfig::load(std::env::var_os("OCIO")?).ok()
+}
+
+/// Returns the value of `key` in `entry`.
+///
+/// This handles a `key: value` line as well as a `{key: value, …}` flow
+/// mapping.
+fn value_of(entry: &str, key: &str) -> Option<String> {
+    let body = entry.split_once('{').map(|(_, body)| body).unwrap_or(entry);
+
+    body.split(',').find_map(|pair| {
+        let (k, v) = pair.split_once(':')?;
+        (key == k.trim()).then(|| unquote(v.trim_end_matches('}')))
+    })
+}
+
+fn unquote(value: &str) -> String {
+    value.trim().trim_matches(['"', '\'']).to_string()
+}
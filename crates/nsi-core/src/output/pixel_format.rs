@@ -42,6 +42,25 @@ impl Layer {
     pub fn has_alpha(&self) -> bool {
         self.depth.has_alpha()
     }
+
+    /// Copies the channels of this layer out of `pixels`.
+    ///
+    /// `pixels` is a buffer as delivered by the callbacks, with
+    /// `pixel_channels` values per pixel; i.e. the
+    /// [`channels()`](PixelFormat::channels()) of the [`PixelFormat`] this
+    /// layer is part of.
+    ///
+    /// Returns a tightly packed buffer with [`channels()`](Layer::channels())
+    /// values per pixel.
+    pub fn extract(&self, pixels: &[f32], pixel_channels: usize) -> Vec<f32> {
+        pixels
+            .chunks_exact(pixel_channels)
+            .flat_map(|pixel| {
+                &pixel[self.offset..self.offset + self.channels()]
+            })
+            .copied()
+            .collect()
+    }
 }
 
 /// The depth (number and type of channels) a pixel in a [`Layer`] is
@@ -176,13 +195,29 @@ pub struct PixelFormat(Vec<Layer>);
 impl PixelFormat {
     #[inline]
     pub(crate) fn new(format: &[ndspy_sys::PtDspyDevFormat]) -> Self {
+        Self::from_channel_names(
+            &format
+                .iter()
+                .map(|format| {
+                    unsafe { CStr::from_ptr(format.name) }.to_str().unwrap()
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Builds the pixel format from the names of the channels the renderer
+    /// sends, e.g. `["r", "g", "b", "a", "N_world.001.x", …]`.
+    pub(crate) fn from_channel_names(format: &[&str]) -> Self {
+        if format.is_empty() {
+            return Self::default();
+        }
+
         let (mut previous_layer_name, mut previous_channel_id) =
-            Self::split_into_layer_name_and_channel_id(
-                unsafe { CStr::from_ptr(format[0].name) }.to_str().unwrap(),
-            );
+            Self::split_into_layer_name_and_channel_id(format[0]);
 
         let mut depth = LayerDepth::OneChannel;
         let mut offset = 0;
+        let mut is_first = true;
 
         PixelFormat(
             // This loops through each format (channel), r, g, b, a etc.
@@ -194,18 +229,18 @@ impl PixelFormat {
                 .filter_map(|format| {
                     // FIXME: add support for specifying AOV and detect type
                     // for indexing (.r vs .x)
-                    let name = unsafe { CStr::from_ptr(format.1.name) }
-                        .to_str()
-                        .unwrap();
-
                     let (layer_name, channel_id) =
-                        Self::split_into_layer_name_and_channel_id(name);
+                        Self::split_into_layer_name_and_channel_id(format.1);
 
                     // A boundary between two layers will be when the postfix
-                    // is a combination of those above.
-                    if ["b", "z", "s", "a"].contains(&previous_channel_id)
-                        && ["r", "x", "s"].contains(&channel_id)
-                    {
+                    // is a combination of those above. The first channel
+                    // only starts a layer.
+                    let is_boundary = !is_first
+                        && ["b", "z", "s", "a"].contains(&previous_channel_id)
+                        && ["r", "x", "s"].contains(&channel_id);
+                    is_first = false;
+
+                    if is_boundary {
                         let tmp_layer_name = if previous_layer_name.is_empty() {
                             "Ci"
                         } else {
@@ -313,6 +348,159 @@ impl PixelFormat {
     }
 }
 
+/// Splits a buffer as delivered by the callbacks into one buffer per
+/// [`Layer`].
+///
+/// Returns each layer, in the order of the `format`, with a tightly packed
+/// buffer of `width` × `height` pixels with the layer's
+/// [`channels()`](Layer::channels()) values each. See also
+/// [`Layer::extract()`].
+///
+/// Layers are not keyed by [`name()`](Layer::name()). Two layers can share
+/// a name, e.g. the same variable output twice with different filters.
+///
+/// # Panics
+///
+/// If `pixels` does not hold `width` × `height` pixels of the `format`.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let finish = nsi::output::FinishCallback::new(
+///     |_: String,
+///      width: usize,
+///      height: usize,
+///      pixel_format: nsi::output::PixelFormat,
+///      pixels: Vec<f32>| {
+///         let aovs =
+///             nsi::output::split_aovs(&pixel_format, &pixels, width, height);
+///
+///         if let Some((_, normals)) =
+///             aovs.iter().find(|(layer, _)| "N_world" == layer.name())
+///         {
+///             assert_eq!(width * height * 3, normals.len());
+///         }
+///
+///         nsi::output::Error::None
+///     },
+/// );
+/// ```
+pub fn split_aovs<'f>(
+    format: &'f PixelFormat,
+    pixels: &[f32],
+    width: usize,
+    height: usize,
+) -> Vec<(&'f Layer, Vec<f32>)> {
+    let pixel_channels = format.channels();
+
+    assert_eq!(
+        width * height * pixel_channels,
+        pixels.len(),
+        "The pixel buffer does not match a {}×{} image with {} channels.",
+        width,
+        height,
+        pixel_channels
+    );
+
+    format
+        .iter()
+        .map(|layer| (layer, layer.extract(pixels, pixel_channels)))
+        .collect()
+}
+
+/// Returns a display-ready copy of the vector layer `layer_name`, e.g. a
+/// normal, with each component mapped from `-1..1` to `0..1`.
+///
+/// `pixels` is a buffer as delivered by the callbacks. The result is tightly
+/// packed with the layer's [`channels()`](Layer::channels()) values per
+/// pixel; values outside `-1..1` are clamped.
+///
+/// For a [`VectorAndAlpha`](LayerDepth::VectorAndAlpha) layer the vector is
+/// divided by the alpha before it is mapped and the alpha is passed
+/// through. Pixels with zero alpha become all zero.
+///
+/// Returns [`None`] if the `format` has no layer named `layer_name` or if
+/// that layer is not a [`Vector`](LayerDepth::Vector) or
+/// [`VectorAndAlpha`](LayerDepth::VectorAndAlpha) layer.
+///
+/// # Panics
+///
+/// If the length of `pixels` is not a multiple of the
+/// [`channels()`](PixelFormat::channels()) of the `format`.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let finish = nsi::output::FinishCallback::new(
+///     |_: String,
+///      _: usize,
+///      _: usize,
+///      pixel_format: nsi::output::PixelFormat,
+///      pixels: Vec<f32>| {
+///         if let Some(normals) = nsi::output::normalize_vector_layer(
+///             &pixel_format,
+///             &pixels,
+///             "N_world",
+///         ) {
+///             // Ready to be quantized, e.g. to 8bit.
+///             assert!(normals.iter().all(|c| (0.0..=1.0).contains(c)));
+///         }
+///
+///         nsi::output::Error::None
+///     },
+/// );
+/// ```
+pub fn normalize_vector_layer(
+    format: &PixelFormat,
+    pixels: &[f32],
+    layer_name: &str,
+) -> Option<Vec<f32>> {
+    let pixel_channels = format.channels();
+
+    assert_eq!(
+        0,
+        pixels.len() % pixel_channels,
+        "The pixel buffer is not a multiple of {} channels.",
+        pixel_channels
+    );
+
+    let layer = format.iter().find(|layer| layer_name == layer.name())?;
+    let normalize = |x: f32| (0.5 + x * 0.5).clamp(0.0, 1.0);
+
+    match layer.depth() {
+        LayerDepth::Vector => Some(
+            layer
+                .extract(pixels, pixel_channels)
+                .into_iter()
+                .map(normalize)
+                .collect(),
+        ),
+        LayerDepth::VectorAndAlpha => Some(
+            layer
+                .extract(pixels, pixel_channels)
+                .chunks_exact(4)
+                .flat_map(|v| {
+                    let alpha = v[3];
+                    // We ignore pixels with zero alpha.
+                    if 0.0 != alpha {
+                        [
+                            normalize(v[0] / alpha),
+                            normalize(v[1] / alpha),
+                            normalize(v[2] / alpha),
+                            alpha,
+                        ]
+                    } else {
+                        [0.0; 4]
+                    }
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
 impl Deref for PixelFormat {
     type Target = Vec<Layer>;
 
@@ -0,0 +1,144 @@
+//! A minimal render pipeline in one call.
+use crate::{
+    output::{FinishCallback, OpenCallback, WriteCallback, FERRIS},
+    Arg, ArgData, Callback, Context, Integer, Integers, String, OUTPUT_DRIVER,
+    OUTPUT_LAYER, PERSPECTIVE_CAMERA, SCREEN, TRANSFORM,
+};
+
+/// The callbacks [`setup_render()`] puts on the [`FERRIS`] output driver.
+///
+/// Callbacks that are [`None`] are not set.
+#[derive(Default)]
+pub struct RenderCallbacks<'a> {
+    pub open: Option<OpenCallback<'a>>,
+    pub write: Option<WriteCallback<'a>>,
+    pub finish: Option<FinishCallback<'a>>,
+}
+
+/// The handles of the nodes created by [`setup_render()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderTargets {
+    /// The [`transform`](crate::TRANSFORM) the camera hangs off. It is
+    /// connected to the [`.root`](crate::ROOT) and has no transformation.
+    /// Set its `"transformationmatrix"` to place the camera.
+    pub transform: std::string::String,
+    /// The [`perspectivecamera`](crate::PERSPECTIVE_CAMERA), using the
+    /// handle passed to [`setup_render()`].
+    pub camera: std::string::String,
+    /// The [`screen`](crate::SCREEN), with the requested `"resolution"`.
+    pub screen: std::string::String,
+    /// The beauty [`outputlayer`](crate::OUTPUT_LAYER). This outputs `Ci`
+    /// with alpha, as `float`.
+    pub layer: std::string::String,
+    /// The [`outputdriver`](crate::OUTPUT_DRIVER), using the [`FERRIS`]
+    /// driver with the [`RenderCallbacks`].
+    pub driver: std::string::String,
+}
+
+/// Creates everything needed to render an image with the [`FERRIS`] output
+/// driver:
+///
+/// ```text
+/// .root ← transform ← camera ← screen ← layer ← driver
+/// ```
+///
+/// The handles of the `transform`, `screen`, `layer` and `driver` nodes are
+/// derived from `camera_handle`. The returned [`RenderTargets`] holds all of
+/// them so the nodes can be edited further.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let targets = nsi::output::setup_render(
+///     &ctx,
+///     "camera",
+///     [640, 480],
+///     nsi::output::RenderCallbacks {
+///         finish: Some(nsi::output::FinishCallback::new(
+///             |_: String,
+///              width: usize,
+///              height: usize,
+///              _: nsi::output::PixelFormat,
+///              pixels: Vec<f32>| {
+///                 println!("{}×{}: {} values", width, height, pixels.len());
+///                 nsi::output::Error::None
+///             },
+///         )),
+///         ..Default::default()
+///     },
+/// );
+///
+/// ctx.set_attribute(&targets.camera, &[nsi::float!("fov", 35.)]);
+/// ```
+pub fn setup_render<'a>(
+    ctx: &Context<'a>,
+    camera_handle: &str,
+    resolution: [i32; 2],
+    callbacks: RenderCallbacks<'a>,
+) -> RenderTargets {
+    let targets = RenderTargets {
+        transform: format!("{}_transform", camera_handle),
+        camera: camera_handle.to_string(),
+        screen: format!("{}_screen", camera_handle),
+        layer: format!("{}_beauty", camera_handle),
+        driver: format!("{}_driver", camera_handle),
+    };
+
+    ctx.create(&targets.transform, TRANSFORM, None);
+    ctx.attach_to_root(&targets.transform);
+
+    ctx.create(&targets.camera, PERSPECTIVE_CAMERA, None);
+    ctx.connect(&targets.camera, None, &targets.transform, "objects", None);
+
+    ctx.create(
+        &targets.screen,
+        SCREEN,
+        Some(&[Arg::new(
+            "resolution",
+            ArgData::from(Integers::new(&resolution)),
+        )
+        .array_len(2)]),
+    );
+    ctx.connect(&targets.screen, None, &targets.camera, "screens", None);
+
+    ctx.create(
+        &targets.layer,
+        OUTPUT_LAYER,
+        Some(&[
+            Arg::new("variablename", ArgData::from(String::new("Ci"))),
+            Arg::new("withalpha", ArgData::from(Integer::new(1))),
+            Arg::new("scalarformat", ArgData::from(String::new("float"))),
+        ]),
+    );
+    ctx.connect(&targets.layer, None, &targets.screen, "outputlayers", None);
+
+    let mut driver_args = vec![
+        Arg::new("drivername", ArgData::from(String::new(FERRIS))),
+        Arg::new("imagefilename", ArgData::from(String::new(camera_handle))),
+    ];
+    if let Some(open) = callbacks.open {
+        driver_args.push(Arg::new(
+            "callback.open",
+            ArgData::from(Callback::new(open)),
+        ));
+    }
+    if let Some(write) = callbacks.write {
+        driver_args.push(Arg::new(
+            "callback.write",
+            ArgData::from(Callback::new(write)),
+        ));
+    }
+    if let Some(finish) = callbacks.finish {
+        driver_args.push(Arg::new(
+            "callback.finish",
+            ArgData::from(Callback::new(finish)),
+        ));
+    }
+
+    ctx.create(&targets.driver, OUTPUT_DRIVER, Some(&driver_args));
+    ctx.connect(&targets.driver, None, &targets.layer, "outputdrivers", None);
+
+    targets
+}
@@ -0,0 +1,132 @@
+//! Conversion of rendered pixels to 8bit sRGB for display or e.g. PNGs.
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// Number of pixels each rayon task converts.
+#[cfg(feature = "rayon")]
+const PIXELS_PER_TASK: usize = 4096;
+
+/// Converts a linear value to (`0..1` clamped) sRGB.
+#[inline]
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0 {
+        0.0
+    } else if x >= 1.0 {
+        1.0
+    } else if x < 0.0031308 {
+        x * 12.92
+    } else {
+        x.powf(1.0 / 2.4) * 1.055 - 0.055
+    }
+}
+
+/// Converts linear, premultiplied `f32` pixels to 8bit sRGB.
+///
+/// Both `src` and `dst` hold pixels of `channels` interleaved values each.
+///
+/// If `alpha_index` is given, that channel is treated as alpha. The other
+/// channels are unpremultiplied by it before conversion and the alpha itself
+/// is quantized linearly. Pixels with zero alpha become all zero. Without
+/// `alpha_index` all channels are converted.
+///
+/// See [`srgb_encode_parallel()`] for a version that uses all CPU cores with
+/// the `rayon` feature.
+///
+/// # Panics
+///
+/// If `src` and `dst` differ in length or their length is not a multiple of
+/// `channels`.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// let src = [0.5, 0.25, 0.0, 0.5];
+/// let mut dst = [0u8; 4];
+///
+/// nsi::output::srgb_encode(&src, &mut dst, 4, Some(3));
+///
+/// assert_eq!([255, 188, 0, 128], dst);
+/// ```
+pub fn srgb_encode(
+    src: &[f32],
+    dst: &mut [u8],
+    channels: usize,
+    alpha_index: Option<usize>,
+) {
+    assert_eq!(src.len(), dst.len(), "src & dst differ in length");
+    assert_eq!(
+        0,
+        src.len() % channels,
+        "length is not a multiple of {} channels",
+        channels
+    );
+
+    src.chunks_exact(channels)
+        .zip(dst.chunks_exact_mut(channels))
+        .for_each(|(src, dst)| encode_pixel(src, dst, alpha_index));
+}
+
+/// Parallel version of [`srgb_encode()`].
+///
+/// The pixels are split into chunks that are converted in parallel, using
+/// [`rayon`](https://docs.rs/rayon/). For small buffers, e.g. a single bucket,
+/// the overhead of this outweighs the gain; use [`srgb_encode()`] there.
+///
+/// Without the `rayon` feature this is the same as [`srgb_encode()`].
+///
+/// # Panics
+///
+/// If `src` and `dst` differ in length or their length is not a multiple of
+/// `channels`.
+pub fn srgb_encode_parallel(
+    src: &[f32],
+    dst: &mut [u8],
+    channels: usize,
+    alpha_index: Option<usize>,
+) {
+    assert_eq!(src.len(), dst.len(), "src & dst differ in length");
+    assert_eq!(
+        0,
+        src.len() % channels,
+        "length is not a multiple of {} channels",
+        channels
+    );
+
+    #[cfg(feature = "rayon")]
+    src.par_chunks(channels * PIXELS_PER_TASK)
+        .zip(dst.par_chunks_mut(channels * PIXELS_PER_TASK))
+        .for_each(|(src, dst)| srgb_encode(src, dst, channels, alpha_index));
+
+    #[cfg(not(feature = "rayon"))]
+    srgb_encode(src, dst, channels, alpha_index);
+}
+
+#[inline]
+fn encode_pixel(src: &[f32], dst: &mut [u8], alpha_index: Option<usize>) {
+    let quantize = |x: f32| (x * 255.0 + 0.5) as u8;
+
+    match alpha_index {
+        Some(alpha_index) => {
+            let alpha = src[alpha_index];
+
+            if 0.0 == alpha {
+                dst.fill(0);
+            } else {
+                for (channel, (src, dst)) in
+                    src.iter().zip(dst.iter_mut()).enumerate()
+                {
+                    *dst = if alpha_index == channel {
+                        quantize(alpha.clamp(0.0, 1.0))
+                    } else {
+                        quantize(linear_to_srgb(src / alpha))
+                    };
+                }
+            }
+        }
+        None => src
+            .iter()
+            .zip(dst.iter_mut())
+            .for_each(|(src, dst)| *dst = quantize(linear_to_srgb(*src))),
+    }
+}
@@ -0,0 +1,196 @@
+//! Generating geometry on demand from Rust closures.
+use crate::{argument::FfiParam, Context};
+use std::os::raw::c_int;
+
+// Used by procedural!().
+#[doc(hidden)]
+pub use nsi_sys::{NSIContext, NSIProcedural, NSIReport};
+
+/// A closure which is called when the renderer needs the contents of a
+/// [`procedural`](crate::PROCEDURAL) node.
+///
+/// It is passed a [`Context`] to create the geometry in and the arguments
+/// set on the procedural node. Nodes are connected relative to the
+/// procedural, i.e. connecting to [`ROOT`](crate::ROOT) attaches them below
+/// the procedural node.
+///
+/// The closure may be called from several render threads at once. It hence
+/// needs to be [`Send`] & [`Sync`].
+///
+/// The [`Context`] is the renderer's and valid only for the duration of
+/// the call. Do not keep it, or a clone of it, around.
+///
+/// See [`procedural!`] for an example.
+pub trait FnProcedural<'a>: Fn(
+    // The context to create the geometry in.
+    &Context,
+    // The arguments of the procedural node.
+    &[FfiParam],
+)
++ Send
++ Sync
++ 'a {}
+
+#[doc(hidden)]
+impl<
+        'a,
+        T: Fn(&Context, &[FfiParam])
+            + Send
+            + Sync
+            + 'a
+            + for<'r, 's> Fn(&'r Context<'s>, &[FfiParam]),
+    > FnProcedural<'a> for T
+{
+}
+
+// The descriptor handed to the renderer. The renderer passes it back to the
+// trampolines, which find the closure behind it.
+#[repr(C)]
+struct ProceduralDescriptor {
+    procedural: nsi_sys::NSIProcedural,
+    fn_procedural: *mut Box<dyn FnProcedural<'static>>,
+}
+
+/// Wrapper to hand a [`FnProcedural`] closure to the renderer.
+///
+/// The renderer loads procedurals from dynamic libraries only. A closure
+/// can therefore not be set on a [`procedural`](crate::PROCEDURAL) node
+/// directly. Instead, a `cdylib` crate returns the callback from its
+/// `NSIProceduralLoad()` entry point. The [`procedural!`] macro generates
+/// this entry point.
+///
+/// The library is then used from a scene with a procedural node whose
+/// `"type"` is `"dynamiclibrary"` and whose `"filename"` is the path of the
+/// library.
+#[allow(clippy::redundant_allocation)]
+pub struct ProceduralCallback(Box<Box<Box<dyn FnProcedural<'static>>>>);
+
+impl ProceduralCallback {
+    #[must_use]
+    pub fn new<F>(fn_procedural: F) -> Self
+    where
+        F: FnProcedural<'static>,
+    {
+        ProceduralCallback(Box::new(Box::new(Box::new(fn_procedural))))
+    }
+
+    /// Returns the descriptor `NSIProceduralLoad()` hands to the renderer.
+    ///
+    /// The descriptor is freed when the renderer unloads the procedural.
+    #[doc(hidden)]
+    pub fn into_raw(self) -> *mut nsi_sys::NSIProcedural {
+        Box::into_raw(Box::new(ProceduralDescriptor {
+            procedural: nsi_sys::NSIProcedural {
+                nsi_version: nsi_sys::NSI_VERSION as _,
+                unload: Some(procedural_unload),
+                execute: Some(procedural_execute),
+            },
+            fn_procedural: Box::into_raw(*self.0),
+        })) as _
+    }
+}
+
+/// Generates the `NSIProceduralLoad()` entry point of a procedural library
+/// from a [`FnProcedural`] closure.
+///
+/// Use this once, in a crate with `crate-type = ["cdylib"]`.
+///
+/// # Examples
+///
+/// A procedural that emits a unit cube:
+///
+/// ```
+/// # use nsi_core as nsi;
+/// nsi::procedural!(|ctx: &nsi::Context, _: &[nsi::FfiParam]| {
+///     ctx.create("cube", nsi::MESH, None);
+///     ctx.connect("cube", None, nsi::ROOT, "objects", None);
+///
+///     ctx.set_attribute(
+///         "cube",
+///         &[
+///             nsi::integers!("nvertices", &[4; 6]),
+///             nsi::points!(
+///                 "P",
+///                 &[
+///                     -0.5, -0.5, -0.5, 0.5, -0.5, -0.5, 0.5, 0.5, -0.5, -0.5,
+///                     0.5, -0.5, -0.5, -0.5, 0.5, 0.5, -0.5, 0.5, 0.5, 0.5, 0.5,
+///                     -0.5, 0.5, 0.5,
+///                 ]
+///             ),
+///             nsi::integers!(
+///                 "P.indices",
+///                 &[
+///                     0, 3, 2, 1, 4, 5, 6, 7, 0, 1, 5, 4, 1, 2, 6, 5, 2, 3, 7,
+///                     6, 3, 0, 4, 7,
+///                 ]
+///             ),
+///         ],
+///     );
+/// });
+/// ```
+///
+/// The library is then used from a scene like so:
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// ctx.create(
+///     "cube_procedural",
+///     nsi::PROCEDURAL,
+///     Some(&[
+///         nsi::string!("type", "dynamiclibrary"),
+///         nsi::string!("filename", "libcube_procedural.so"),
+///     ]),
+/// );
+/// ctx.attach_to_root("cube_procedural");
+/// ```
+#[macro_export]
+macro_rules! procedural {
+    ($fn_procedural: expr) => {
+        #[no_mangle]
+        pub extern "C" fn NSIProceduralLoad(
+            _ctx: nsi::NSIContext,
+            _report: nsi::NSIReport,
+            _nsi_library_path: *const std::os::raw::c_char,
+            _renderer_version: *const std::os::raw::c_char,
+        ) -> *mut nsi::NSIProcedural {
+            nsi::ProceduralCallback::new($fn_procedural).into_raw()
+        }
+    };
+}
+
+// Trampoline function for the FnProcedural callback.
+pub(crate) unsafe extern "C" fn procedural_execute(
+    context: nsi_sys::NSIContext,
+    _report: nsi_sys::NSIReport,
+    procedural: *mut nsi_sys::NSIProcedural,
+    params_len: c_int,
+    params: *const nsi_sys::NSIParam,
+) {
+    if procedural.is_null() {
+        return;
+    }
+
+    let descriptor = &*(procedural as *const ProceduralDescriptor);
+    let params = if params.is_null() || params_len <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(params, params_len as _)
+    };
+
+    Context::with_borrowed(context, |ctx| {
+        (*descriptor.fn_procedural)(ctx, params)
+    });
+}
+
+// Frees the descriptor and the closure.
+pub(crate) unsafe extern "C" fn procedural_unload(
+    _context: nsi_sys::NSIContext,
+    _report: nsi_sys::NSIReport,
+    procedural: *mut nsi_sys::NSIProcedural,
+) {
+    if !procedural.is_null() {
+        let descriptor = Box::from_raw(procedural as *mut ProceduralDescriptor);
+        drop(Box::from_raw(descriptor.fn_procedural));
+    }
+}
@@ -1,5 +1,7 @@
 #[allow(unused_imports)]
 use crate as nsi;
+#[cfg(test)]
+use nsi::record_stream;
 
 #[cfg(test)]
 #[test]
@@ -219,3 +221,1968 @@ fn live_edit() {
 }
 
 // FIXME: port rest of live_edit example from Python
+
+#[cfg(test)]
+#[test]
+fn test_single_attributes() {
+    let ctx =
+        nsi::Context::new(Some(&[nsi::string!("streamfilename", "stdout")]))
+            .expect("Could not create NSI context.");
+
+    ctx.create("mesh_attribs", nsi::ATTRIBUTES, None);
+    ctx.set_integer("mesh_attribs", "visibility.camera", 0);
+
+    ctx.create("mesh_shader", nsi::SHADER, None);
+    ctx.set_string(
+        "mesh_shader",
+        "shaderfilename",
+        "${DELIGHT}/osl/dlPrincipled",
+    );
+    ctx.set_float("mesh_shader", "roughness", 0.2);
+    ctx.set_color("mesh_shader", "i_color", [1., 0.6, 0.3]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_colors() {
+    let colors = [1., 0.6, 0.3, 0.3, 0.6, 1.];
+
+    let arg = nsi::colors!("colors", &colors, 2);
+    assert_eq!(2, arg.array_length);
+}
+
+#[cfg(test)]
+#[test]
+#[should_panic]
+fn test_colors_wrong_channel_count() {
+    let _ = nsi::colors!("colors", &[1., 0.6, 0.3, 0.3]);
+}
+
+#[cfg(test)]
+#[test]
+#[should_panic]
+fn test_colors_wrong_array_len() {
+    let _ = nsi::colors!("colors", &[1., 0.6, 0.3, 0.3, 0.6, 1.], 4);
+}
+
+#[cfg(test)]
+#[test]
+#[should_panic(expected = "array length of zero")]
+fn test_zero_array_len() {
+    let _ = nsi::integers!("resolution", &[640, 480]).array_len(0);
+}
+
+#[cfg(test)]
+#[test]
+#[should_panic(expected = "array length of zero")]
+fn test_zero_owned_array_len() {
+    let _ =
+        nsi::OwnedArg::new("resolution", vec![640, 480].into()).array_len(0);
+}
+
+#[cfg(test)]
+#[test]
+fn test_action_from_str() {
+    assert_eq!(Ok(nsi::Action::Start), nsi::Action::try_from("start"));
+    assert_eq!(Ok(nsi::Action::Wait), nsi::Action::try_from("wait"));
+    assert_eq!(
+        Err(nsi::UnknownActionError("strat".to_string())),
+        nsi::Action::try_from("strat")
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_control_unknown_action() {
+    let errors = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = errors.clone();
+
+    let ctx = nsi::Context::builder()
+        .error_handler(move |level: log::Level, _: i32, message: &str| {
+            if log::Level::Error == level {
+                sink.lock().unwrap().push(message.to_string());
+            }
+        })
+        .build()
+        .unwrap();
+
+    ctx.render_control(
+        nsi::Action::Start,
+        Some(&[nsi::string!("action", "strat")]),
+    );
+
+    assert_eq!(
+        vec![nsi::UnknownActionError("strat".to_string()).to_string()],
+        *errors.lock().unwrap()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_validate() {
+    use crate::graph::Graph;
+
+    let mut graph = Graph::default();
+
+    // A complete scene.
+    graph.create("mesh", nsi::MESH);
+    graph.create("attributes", nsi::ATTRIBUTES);
+    graph.create("shader", nsi::SHADER);
+    graph.create("xform", nsi::TRANSFORM);
+    graph.create("camera", nsi::PERSPECTIVE_CAMERA);
+    graph.create("screen", nsi::SCREEN);
+    graph.create("layer", nsi::OUTPUT_LAYER);
+    graph.create("driver", nsi::OUTPUT_DRIVER);
+
+    graph.connect("mesh", "", "xform", "objects", 0);
+    graph.connect("xform", "", nsi::ROOT, "objects", 0);
+    graph.connect("shader", "", "attributes", "surfaceshader", 0);
+    // The material is inherited from the transform.
+    graph.connect("attributes", "", "xform", "geometryattributes", 0);
+    graph.connect("screen", "", "camera", "screens", 0);
+    graph.connect("layer", "", "screen", "outputlayers", 0);
+    graph.connect("driver", "", "layer", "outputdrivers", 0);
+
+    assert!(graph.validate().is_empty());
+
+    graph.disconnect("driver", "", "layer", "outputdrivers");
+    graph.disconnect(nsi::ALL, "", "camera", "screens");
+    graph.disconnect("attributes", "", "xform", "geometryattributes");
+    graph.connect("mesh", "", "missing", "objects", 0);
+
+    let issues = graph.validate();
+
+    assert_eq!(4, issues.len());
+    assert_eq!(
+        3,
+        issues
+            .iter()
+            .filter(|issue| nsi::Severity::Warning == issue.severity)
+            .count()
+    );
+    assert!(issues.iter().any(|issue| {
+        nsi::Severity::Error == issue.severity
+            && issue.message.contains("'missing'")
+    }));
+}
+
+#[cfg(test)]
+#[test]
+fn test_validate_recursive_delete() {
+    use crate::graph::Graph;
+
+    let mut graph = Graph::default();
+
+    graph.create("shader", nsi::SHADER);
+    graph.create("texture", nsi::SHADER);
+    graph.create("attributes", nsi::ATTRIBUTES);
+    graph.connect("texture", "outColor", "shader", "i_color", 0);
+    graph.connect("shader", "", "attributes", "surfaceshader", 0);
+
+    // Deleting the attributes removes the whole shader network.
+    graph.delete("attributes", true);
+
+    assert!(graph.nodes.is_empty());
+    assert!(graph.connections.is_empty());
+    assert!(graph.validate().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_graph_subtree() {
+    use crate::graph::{Attribute, Graph};
+
+    let mut graph = Graph::default();
+
+    graph.create("xform", nsi::TRANSFORM);
+    graph.create("sphere", nsi::PARTICLES);
+    graph.create("attributes", nsi::ATTRIBUTES);
+    graph.create("other", nsi::MESH);
+
+    graph.connect("sphere", "", "xform", "objects", 0);
+    graph.connect("attributes", "", "sphere", "geometryattributes", 0);
+    graph.connect("attributes", "", "other", "geometryattributes", 0);
+    graph.connect("xform", "", nsi::ROOT, "objects", 0);
+
+    let width = nsi::floats!("width", &[1.0]);
+    graph.set_attribute(
+        "sphere",
+        Attribute::from_arg(&width, None, false).unwrap(),
+    );
+    let width = nsi::floats!("width", &[2.0]);
+    graph.set_attribute(
+        "sphere",
+        Attribute::from_arg(&width, Some(0.0), false).unwrap(),
+    );
+    graph.set_attribute(
+        "sphere",
+        Attribute::from_arg(&width, Some(1.0), false).unwrap(),
+    );
+
+    // The time samples replaced the value without a time.
+    assert_eq!(2, graph.nodes["sphere"].attributes.len());
+
+    let (nodes, connections) = graph.subtree("xform");
+
+    let mut handles = nodes.iter().map(|(h, _)| h.as_str()).collect::<Vec<_>>();
+    handles.sort();
+    assert_eq!(vec!["attributes", "sphere", "xform"], handles);
+    // Neither the connection to `other` nor to `.root` are part of it.
+    assert_eq!(2, connections.len());
+
+    graph.delete_attribute("sphere", "width");
+    assert!(graph.nodes["sphere"].attributes.is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_graph_connections() {
+    use crate::graph::Graph;
+
+    let mut graph = Graph::default();
+
+    for i in 0..8 {
+        graph.connect(&format!("xform{}", i), "", nsi::ROOT, "objects", 0);
+    }
+    // Connecting again replaces the connection and moves it to the end.
+    graph.connect("xform0", "", nsi::ROOT, "objects", 1);
+    for i in 1..6 {
+        graph.disconnect(&format!("xform{}", i), "", nsi::ROOT, "objects");
+    }
+    graph.disconnect("missing", "", nsi::ROOT, "objects");
+
+    assert_eq!(
+        vec![("xform6", 0), ("xform7", 0), ("xform0", 1)],
+        graph
+            .connections
+            .iter()
+            .map(|c| (c.from.as_str(), c.strength))
+            .collect::<Vec<_>>()
+    );
+
+    graph.disconnect(nsi::ALL, "", nsi::ROOT, "objects");
+    assert!(graph.connections.is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_graph_mirror_data() {
+    use crate::graph::Attribute;
+
+    let matrix = nsi::double_matrix!("transformationmatrix", &[1.0; 16]);
+    let positions = nsi::points!("P", &[0.0; 18]);
+
+    // Up to a matrix worth of values is always copied.
+    assert!(Attribute::from_arg(&matrix, None, false)
+        .unwrap()
+        .as_param()
+        .is_some());
+    assert!(Attribute::from_arg(&positions, None, false)
+        .unwrap()
+        .as_param()
+        .is_none());
+    assert!(Attribute::from_arg(&positions, None, true)
+        .unwrap()
+        .as_param()
+        .is_some());
+}
+
+#[cfg(test)]
+#[test]
+fn test_graph_mirror_reference() {
+    use crate::graph::Attribute;
+    use std::pin::Pin;
+
+    let payload = 42u8;
+    let reference = nsi::reference!("payload", Pin::new(&payload));
+
+    // The pointer itself is the data of a reference, not what it points to.
+    assert_eq!(
+        &payload as *const u8 as *const std::ffi::c_void,
+        Attribute::from_arg(&reference, None, false)
+            .unwrap()
+            .as_param()
+            .unwrap()
+            .data
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_param_group() {
+    let args = nsi::ParamGroup::new("subdivision.")
+        .set(nsi::string!("scheme", "catmull-clark"))
+        .set(nsi::integers!("cornervertices", &[0, 1, 2, 3]).array_len(2))
+        .args();
+
+    assert_eq!(
+        vec!["subdivision.scheme", "subdivision.cornervertices"],
+        args.iter().map(|arg| arg.name()).collect::<Vec<_>>()
+    );
+    // The array length survives the renaming.
+    assert_eq!(2, args[1].array_length);
+
+    // An empty prefix keeps the names.
+    let args = nsi::ParamGroup::new("")
+        .set(nsi::float!("roughness", 0.3))
+        .args();
+    assert_eq!("roughness", args[0].name());
+}
+
+#[cfg(test)]
+#[test]
+fn test_node_type_default_attributes() {
+    use nsi::{AttrKind, NodeType};
+
+    for node_type in NodeType::VARIANTS {
+        assert_eq!(Some(node_type), NodeType::from_name(node_type.as_str()));
+
+        let attributes = node_type.default_attributes();
+        let mut names =
+            attributes.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(attributes.len(), names.len(), "{}", node_type);
+
+        match node_type {
+            NodeType::Root | NodeType::Set | NodeType::Plane => {
+                assert!(attributes.is_empty())
+            }
+            _ => assert!(!attributes.is_empty(), "{}", node_type),
+        }
+    }
+
+    for camera in [
+        NodeType::OrthographicCamera,
+        NodeType::PerspectiveCamera,
+        NodeType::FisheyeCamera,
+        NodeType::CylindricalCamera,
+        NodeType::SphericalCamera,
+    ] {
+        assert!(camera
+            .default_attributes()
+            .contains(&("clippingrange", AttrKind::Doubles)));
+    }
+
+    let screen = NodeType::Screen.default_attributes();
+    assert!(screen.contains(&("resolution", AttrKind::Integers)));
+    assert!(screen.contains(&("oversampling", AttrKind::Integer)));
+
+    assert_eq!(None, NodeType::from_name("fancyhair"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_double_points() {
+    let points = [1.0e9, 0.5, -2.0, 1.0e9 + 0.25, 0.5, -2.0];
+    let arg = nsi::double_points!("P", &points);
+
+    let (_, _, params) = nsi::argument::get_c_param_vec(Some(&[arg]));
+
+    assert_eq!(nsi_sys::NSIType::Double as i32, params[0].type_);
+    assert_eq!(3, params[0].arraylength);
+    assert_eq!(2, params[0].count);
+    assert_eq!(points, unsafe {
+        std::slice::from_raw_parts(params[0].data as *const f64, 6)
+    });
+}
+
+#[cfg(test)]
+#[test]
+fn test_color_forms() {
+    let orange = [1.0f32, 0.6, 0.3];
+
+    for arg in [
+        nsi::color!("i_color", &orange),
+        nsi::color!("i_color", orange),
+        nsi::color!("i_color", (1., 0.6, 0.3)),
+    ] {
+        // The color lives inside the argument.
+        let args = [arg];
+        let (_, _, params) = nsi::argument::get_c_param_vec(Some(&args));
+
+        assert_eq!(nsi_sys::NSIType::Color as i32, params[0].type_);
+        assert_eq!(orange, unsafe {
+            std::slice::from_raw_parts(params[0].data as *const f32, 3)
+        });
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_ffi_param() {
+    let name = std::ffi::CString::new("resolution").unwrap();
+    let resolution = [640i32, 480];
+
+    let hand_built = nsi::FfiParam {
+        name: name.as_ptr(),
+        data: resolution.as_ptr() as _,
+        type_: nsi_sys::NSIType::Integer as _,
+        arraylength: 2,
+        count: 1,
+        flags: nsi_sys::NSIParamFlags::IsArray.bits(),
+    };
+
+    let args = [nsi::integers!("resolution", &resolution).array_len(2)];
+    let (len, _, params) = nsi::argument::get_c_param_vec(Some(&args));
+
+    assert_eq!(1, len);
+    let from_macro = &params[0];
+
+    assert_eq!(
+        unsafe { std::ffi::CStr::from_ptr(hand_built.name) },
+        unsafe { std::ffi::CStr::from_ptr(from_macro.name) }
+    );
+    assert_eq!(hand_built.type_, from_macro.type_);
+    assert_eq!(hand_built.arraylength, from_macro.arraylength);
+    assert_eq!(hand_built.count, from_macro.count);
+    assert_eq!(hand_built.flags, from_macro.flags);
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(hand_built.data as *const i32, 2) },
+        unsafe { std::slice::from_raw_parts(from_macro.data as *const i32, 2) }
+    );
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_srgb_encode_parallel() {
+    // Enough pixels to be split across several tasks, with an odd remainder.
+    let src = (0..3 * 10_007)
+        .map(|i| (i % 1031) as f32 / 1030.0)
+        .collect::<Vec<_>>();
+
+    let mut scalar = vec![0u8; src.len()];
+    let mut parallel = vec![0u8; src.len()];
+
+    nsi::output::srgb_encode(&src, &mut scalar, 3, None);
+    nsi::output::srgb_encode_parallel(&src, &mut parallel, 3, None);
+    assert_eq!(scalar, parallel);
+
+    let src = src.iter().map(|x| 0.5 * x).collect::<Vec<_>>();
+
+    nsi::output::srgb_encode(&src, &mut scalar, 3, Some(2));
+    nsi::output::srgb_encode_parallel(&src, &mut parallel, 3, Some(2));
+    assert_eq!(scalar, parallel);
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_setup_render() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let targets = nsi::output::setup_render(
+        &ctx,
+        "camera",
+        [64, 32],
+        nsi::output::RenderCallbacks::default(),
+    );
+
+    let graph = ctx.graph();
+    assert_eq!(Some(nsi::TRANSFORM), graph.node_type(&targets.transform));
+    assert_eq!(Some(nsi::PERSPECTIVE_CAMERA), graph.node_type("camera"));
+    assert_eq!(Some(nsi::SCREEN), graph.node_type(&targets.screen));
+    assert_eq!(Some(nsi::OUTPUT_LAYER), graph.node_type(&targets.layer));
+    assert_eq!(Some(nsi::OUTPUT_DRIVER), graph.node_type(&targets.driver));
+    drop(graph);
+
+    assert!(ctx.validate().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_context_builder() {
+    let path = std::env::temp_dir().join("nsi_test_context_builder.nsi");
+
+    assert_eq!(
+        Some(nsi::ContextBuilderError::RenderOptionOnStream {
+            option: "threads"
+        }),
+        nsi::Context::builder()
+            .stream(
+                path.to_str().unwrap(),
+                nsi::StreamFormat::Ascii,
+                nsi::StreamCompression::None,
+            )
+            .threads(4)
+            .build()
+            .err()
+    );
+
+    {
+        let ctx = nsi::Context::builder()
+            .stream(
+                path.to_str().unwrap(),
+                nsi::StreamFormat::Ascii,
+                nsi::StreamCompression::None,
+            )
+            .build()
+            .expect("Could not create NSI stream.");
+
+        ctx.create("plane", nsi::PLANE, None);
+    }
+
+    let stream = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(stream.contains("Create \"plane\" \"plane\""));
+}
+
+#[cfg(test)]
+#[test]
+fn test_procedural_callback() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let procedural = nsi::ProceduralCallback::new(
+        |ctx: &nsi::Context, params: &[nsi::FfiParam]| {
+            assert!(params.is_empty());
+            ctx.create("cube", nsi::MESH, None);
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        },
+    )
+    .into_raw();
+
+    // Call the procedural the way the renderer does.
+    unsafe {
+        let descriptor = *procedural;
+        assert_eq!(nsi_sys::NSI_VERSION, descriptor.nsi_version);
+
+        descriptor.execute.unwrap()(
+            ctx.clone().into(),
+            None,
+            procedural,
+            0,
+            std::ptr::null(),
+        );
+        descriptor.unload.unwrap()(ctx.clone().into(), None, procedural);
+    }
+
+    assert_eq!(1, CALLS.load(Ordering::Relaxed));
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_probe_pixel_format() {
+    use nsi::output::LayerDepth;
+
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("screen", nsi::SCREEN, None);
+    assert!(ctx.probe_pixel_format("screen").is_empty());
+
+    for (handle, args) in [
+        (
+            "beauty",
+            vec![
+                nsi::string!("variablename", "Ci"),
+                nsi::integer!("withalpha", 1),
+            ],
+        ),
+        (
+            "normal",
+            vec![
+                nsi::string!("variablename", "N_world"),
+                nsi::string!("layertype", "vector"),
+            ],
+        ),
+        (
+            "depth",
+            vec![
+                nsi::string!("variablename", "z"),
+                nsi::string!("layertype", "scalar"),
+                // Sorts in front of the others.
+                nsi::integer!("sortkey", -1),
+            ],
+        ),
+    ] {
+        ctx.create(handle, nsi::OUTPUT_LAYER, Some(&args));
+        ctx.connect(handle, None, "screen", "outputlayers", None);
+    }
+
+    let pixel_format = ctx.probe_pixel_format("screen");
+
+    assert_eq!(
+        vec![
+            ("z", LayerDepth::OneChannel, 0),
+            ("Ci", LayerDepth::ColorAndAlpha, 1),
+            ("N_world", LayerDepth::Vector, 5),
+        ],
+        pixel_format
+            .iter()
+            .map(|layer| (layer.name(), layer.depth(), layer.offset()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(8, pixel_format.channels());
+}
+
+#[cfg(test)]
+#[test]
+fn test_attach_to_root() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("plane", nsi::PLANE, None);
+    ctx.create("attributes", nsi::ATTRIBUTES, None);
+
+    ctx.attach_to_root("plane");
+    ctx.attach_to_root_slot("attributes", "geometryattributes");
+
+    let graph = ctx.graph();
+    assert_eq!(
+        vec!["plane"],
+        graph.inputs(nsi::ROOT, "objects").collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["attributes"],
+        graph
+            .inputs(nsi::ROOT, "geometryattributes")
+            .collect::<Vec<_>>()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_visibility_flags() {
+    use nsi::VisibilityFlags;
+
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+    ctx.create("attributes", nsi::ATTRIBUTES, None);
+
+    assert_eq!(VisibilityFlags::all(), ctx.visibility_for("attributes"));
+
+    let mask = VisibilityFlags::SHADOW | VisibilityFlags::REFLECTION;
+    ctx.set_visibility("attributes", mask);
+    assert_eq!(mask, ctx.visibility_for("attributes"));
+
+    // Only the attributes of flags that were cleared got set.
+    assert_eq!(
+        0,
+        ctx.graph()
+            .attributes("attributes", "visibility.shadow")
+            .count()
+    );
+    assert_eq!(
+        Some(0),
+        ctx.graph()
+            .integer_attribute("attributes", "visibility.camera")
+    );
+
+    ctx.set_visibility("attributes", VisibilityFlags::default());
+    assert_eq!(VisibilityFlags::all(), ctx.visibility_for("attributes"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_visibility() {
+    use nsi::{RenderVisibility, VisibilityFlags};
+
+    assert!(RenderVisibility::default().args().is_empty());
+
+    let visibility = RenderVisibility {
+        camera: Some(false),
+        specular: Some(true),
+        ..Default::default()
+    };
+
+    // Unset fields are not emitted.
+    assert_eq!(
+        vec!["visibility.camera", "visibility.specular"],
+        visibility
+            .args()
+            .iter()
+            .map(|arg| arg.name())
+            .collect::<Vec<_>>()
+    );
+
+    let all = RenderVisibility::from(VisibilityFlags::SHADOW);
+    assert_eq!(8, all.args().len());
+    assert_eq!(Some(true), all.shadow);
+    assert_eq!(Some(false), all.camera);
+}
+
+#[cfg(test)]
+#[test]
+fn test_wait_timeout() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    // Without an output driver the render aborts right away.
+    ctx.render_control(nsi::Action::Start, None);
+    assert_eq!(
+        nsi::WaitResult::Completed,
+        ctx.wait_timeout(std::time::Duration::from_secs(60), true)
+    );
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_split_aovs() {
+    let format = nsi::output::PixelFormat::from_channel_names(&[
+        "Ci.001.r",
+        "Ci.001.g",
+        "Ci.001.b",
+        "N_world.002.x",
+        "N_world.002.y",
+        "N_world.002.z",
+        "z.000",
+    ]);
+
+    // Two pixels, each channel holding its offset plus 10 × the pixel
+    // index.
+    let pixels = (0..2)
+        .flat_map(|pixel| (0..7).map(move |c| (c + 10 * pixel) as f32))
+        .collect::<Vec<_>>();
+
+    let aovs = nsi::output::split_aovs(&format, &pixels, 2, 1);
+
+    assert_eq!(
+        vec![
+            ("Ci", vec![0., 1., 2., 10., 11., 12.]),
+            ("N_world", vec![3., 4., 5., 13., 14., 15.]),
+            ("z", vec![6., 16.]),
+        ],
+        aovs.iter()
+            .map(|(layer, data)| (layer.name(), data.clone()))
+            .collect::<Vec<_>>()
+    );
+
+    // Layers of the same name are kept apart.
+    let format =
+        nsi::output::PixelFormat::from_channel_names(&["z.000", "z.000"]);
+    let aovs = nsi::output::split_aovs(&format, &[1., 2.], 1, 1);
+    assert_eq!(
+        vec![vec![1.], vec![2.]],
+        aovs.into_iter().map(|(_, data)| data).collect::<Vec<_>>()
+    );
+}
+
+#[cfg(all(test, feature = "contact_sheet"))]
+#[test]
+fn test_contact_sheet() {
+    let format = nsi::output::PixelFormat::from_channel_names(&[
+        "Ci.001.r",
+        "Ci.001.g",
+        "Ci.001.b",
+        "N_world.002.x",
+        "N_world.002.y",
+        "N_world.002.z",
+        "z.000",
+    ]);
+
+    let (width, height) = (32, 8);
+    let pixels = (0..width * height)
+        .flat_map(|pixel| [1.0, 0.5, 0.0, -1.0, 0.0, 1.0, pixel as f32])
+        .collect::<Vec<_>>();
+
+    let png = nsi::output::contact_sheet(&format, &pixels, width, height);
+
+    let decoder = png::Decoder::new(png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut sheet = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut sheet).unwrap();
+
+    // Three layers in a 2×2 grid, each cell with a label bar.
+    assert_eq!(2 * width as u32, info.width);
+    assert_eq!(2 * (height as u32 + 14), info.height);
+
+    let pixel = |x: usize, y: usize| {
+        let offset = (y * info.width as usize + x) * 3;
+        [sheet[offset], sheet[offset + 1], sheet[offset + 2]]
+    };
+
+    // The label bar, with the top of the 'C' of "Ci".
+    assert_eq!([32; 3], pixel(0, 0));
+    assert_eq!([224; 3], pixel(4, 2));
+
+    // Color: sRGB encoded.
+    assert_eq!([255, 188, 0], pixel(0, 14));
+    // Vector: mapped to 0..1.
+    assert_eq!([0, 128, 255], pixel(width, 14));
+    // Scalar: normalized, first & last pixel.
+    assert_eq!([0; 3], pixel(0, height + 28));
+    assert_eq!([255; 3], pixel(width - 1, 2 * height + 27));
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_normalize_vector_layer() {
+    let format = nsi::output::PixelFormat::from_channel_names(&[
+        "Ci.001.r",
+        "Ci.001.g",
+        "Ci.001.b",
+        "N_world.002.x",
+        "N_world.002.y",
+        "N_world.002.z",
+    ]);
+
+    // Normals pointing along +y and -x.
+    let pixels = [
+        0.2, 0.2, 0.2, 0.0, 1.0, 0.0, //
+        0.2, 0.2, 0.2, -1.0, 0.0, 0.0,
+    ];
+
+    assert_eq!(
+        Some(vec![0.5, 1.0, 0.5, 0.0, 0.5, 0.5]),
+        nsi::output::normalize_vector_layer(&format, &pixels, "N_world")
+    );
+    // Not a vector layer.
+    assert_eq!(
+        None,
+        nsi::output::normalize_vector_layer(&format, &pixels, "Ci")
+    );
+    assert_eq!(
+        None,
+        nsi::output::normalize_vector_layer(&format, &pixels, "P_world")
+    );
+
+    let format = nsi::output::PixelFormat::from_channel_names(&[
+        "N_world.001.x",
+        "N_world.001.y",
+        "N_world.001.z",
+        "a",
+    ]);
+
+    // A premultiplied +z normal at half coverage and an empty pixel.
+    let pixels = [0.0, 0.0, 0.5, 0.5, 0.0, 0.0, 0.0, 0.0];
+
+    assert_eq!(
+        Some(vec![0.5, 0.5, 1.0, 0.5, 0.0, 0.0, 0.0, 0.0]),
+        nsi::output::normalize_vector_layer(&format, &pixels, "N_world")
+    );
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_bucket_stream_ends_with_render() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let (buckets, write, finish, status) =
+        nsi::output::BucketStream::<f32>::new();
+
+    // The driver is never opened as the scene has no camera.
+    ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+    ctx.set_attribute(
+        "driver",
+        &[
+            nsi::string!("drivername", nsi::output::FERRIS),
+            nsi::callback!("callback.write", write),
+            nsi::callback!("callback.finish", finish),
+        ],
+    );
+
+    ctx.render_control(
+        nsi::Action::Start,
+        Some(&[nsi::callback!("callback", status)]),
+    );
+
+    assert_eq!(0, buckets.count());
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_progress_silent_by_default() {
+    use nsi::output::{Error, ProgressCallback};
+
+    // Without a callback, progress is swallowed.
+    let handle = nsi::output::progress_only_image_handle(None);
+    assert!(matches!(
+        nsi::output::image_progress(handle, 0.5),
+        ndspy_sys::PtDspyError::None
+    ));
+    nsi::output::image_close(handle);
+
+    let progress = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = progress.clone();
+
+    let handle = nsi::output::progress_only_image_handle(Some(
+        ProgressCallback::new(move |name: &str, progress: f32| {
+            assert_eq!("progress", name);
+            sink.lock().unwrap().push(progress);
+            Error::None
+        }),
+    ));
+    nsi::output::image_progress(handle, 0.25);
+    nsi::output::image_progress(handle, 1.0);
+    nsi::output::image_close(handle);
+
+    assert_eq!(vec![0.25, 1.0], *progress.lock().unwrap());
+}
+
+#[cfg(test)]
+#[test]
+fn test_motion_segments() {
+    let stream = record_stream(|ctx| {
+        ctx.create("mesh", nsi::MESH, None);
+        for time in [0.0, 0.5, 1.0] {
+            ctx.set_attribute_at_time(
+                "mesh",
+                time,
+                &[nsi::points!("P", &[0., time as f32, 0.])],
+            );
+        }
+
+        assert_eq!(
+            vec![(ustr::Ustr::from("P"), 3)],
+            ctx.graph().time_samples("mesh")
+        );
+
+        ctx.set_motion_segments("mesh", 2);
+    });
+
+    assert_eq!(
+        "Create \"mesh\" \"mesh\"\n\
+         SetAttributeAtTime \"mesh\" 0.0 \"P\" \"point\" 1 [ 0.0 0.0 0.0 ]\n\
+         SetAttributeAtTime \"mesh\" 0.5 \"P\" \"point\" 1 [ 0.0 0.5 0.0 ]\n\
+         SetAttributeAtTime \"mesh\" 1.0 \"P\" \"point\" 1 [ 0.0 1.0 0.0 ]\n\
+         SetAttribute \"mesh\" \"motionblur.segments\" \"int\" 1 [ 2 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_metadata() {
+    let stream = record_stream(|ctx| {
+        ctx.create("xform", nsi::TRANSFORM, None);
+        ctx.set_metadata("xform", "ui.note", "hero_prop");
+        ctx.set_metadata("xform", "ui.color", "red");
+        ctx.set_metadata("xform", "ui.color", "blue");
+        ctx.set_metadata("missing", "ui.color", "red");
+
+        let metadata = ctx.metadata("xform");
+        assert_eq!(2, metadata.len());
+        assert_eq!("blue", metadata["ui.color"]);
+        assert!(ctx.metadata("missing").is_empty());
+
+        let copy = ctx.duplicate_subtree("xform", "copy_");
+        assert_eq!(metadata, ctx.metadata(&copy));
+
+        ctx.delete("xform", None);
+        assert!(ctx.metadata("xform").is_empty());
+    });
+
+    // Metadata never reaches the renderer.
+    assert_eq!(
+        "Create \"xform\" \"transform\"\n\
+         Create \"copy_xform\" \"transform\"\n\
+         Delete \"xform\"\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_create_reserved_handle() {
+    let errors = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = errors.clone();
+
+    let ctx = nsi::Context::builder()
+        .error_handler(move |level: log::Level, _: i32, message: &str| {
+            if log::Level::Error == level {
+                sink.lock().unwrap().push(message.to_string());
+            }
+        })
+        .build()
+        .unwrap();
+
+    ctx.create(nsi::ROOT, nsi::TRANSFORM, None);
+
+    assert!(ctx.graph().node_type(nsi::ROOT).is_none());
+    assert!(errors
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|message| message.contains(nsi::ROOT)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_trace() {
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    {
+        let ctx =
+            nsi::Context::new(None).expect("Could not create NSI context.");
+
+        let sink = calls.clone();
+        ctx.set_trace_sink(move |call: &str| {
+            sink.lock().unwrap().push(call.to_string())
+        });
+
+        // Not traced.
+        ctx.create("hidden", nsi::PLANE, None);
+
+        ctx.set_trace(true);
+        ctx.create("plane", nsi::PLANE, None);
+        ctx.set_attribute("plane", &[nsi::integer!("visibility", 1)]);
+        ctx.set_integer("plane", "visibility.camera", 0);
+        ctx.delete_attribute("plane", "visibility.camera");
+        ctx.connect("plane", None, nsi::ROOT, "objects", None);
+        ctx.batch(|batch| {
+            batch.create("batched", nsi::PLANE, None);
+            batch.connect("batched", None, nsi::ROOT, "objects", None);
+        });
+        ctx.delete("plane", None);
+        ctx.set_trace(false);
+
+        ctx.delete("hidden", None);
+    }
+
+    assert_eq!(
+        vec![
+            "Create \"plane\" \"plane\"",
+            "SetAttribute \"plane\" \"visibility\" \"int\" 1 [ 1 ]",
+            "SetAttribute \"plane\" \"visibility.camera\" \"int\" 1 [ 0 ]",
+            "DeleteAttribute \"plane\" \"visibility.camera\"",
+            "Connect \"plane\" \"\" \".root\" \"objects\"",
+            "Create \"batched\" \"plane\"",
+            "Connect \"batched\" \"\" \".root\" \"objects\"",
+            "Delete \"plane\"",
+        ],
+        *calls.lock().unwrap()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_write_set_attribute() {
+    let args = [
+        nsi::float!("fov", 35.0),
+        nsi::integers!("resolution", &[640, 480]).array_len(2),
+        nsi::strings!("names", &["a \"b\"", "c"]),
+        nsi::double_matrix!(
+            "transformationmatrix",
+            &[
+                1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 2.5, 0.,
+                1.,
+            ]
+        ),
+    ];
+    let (_, _, params) = crate::argument::get_c_param_vec(Some(&args));
+
+    let mut call = String::new();
+    crate::batch::write_set_attribute(
+        &mut call,
+        "SetAttributeAtTime",
+        "camera",
+        Some(1.0),
+        &params,
+    );
+
+    assert_eq!(
+        "SetAttributeAtTime \"camera\" 1.0 \"fov\" \"float\" 1 [ 35.0 ] \
+         \"resolution\" \"int[2]\" 1 [ 640 480 ] \
+         \"names\" \"string\" 2 [ \"a \\\"b\\\"\" \"c\" ] \
+         \"transformationmatrix\" \"doublematrix\" 1 [ 1.0 0.0 0.0 0.0 0.0 \
+         1.0 0.0 0.0 0.0 0.0 1.0 0.0 0.0 2.5 0.0 1.0 ]",
+        call
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_type_layout() {
+    use nsi::{ArgType, Type};
+
+    let pointer = std::mem::size_of::<*const ()>() / 4;
+
+    for (type_, components, size_in_f32) in [
+        (Type::Float, 1, 1),
+        (Type::Double, 1, 2),
+        (Type::Integer, 1, 1),
+        (Type::String, 1, pointer),
+        (Type::Color, 3, 3),
+        (Type::Point, 3, 3),
+        (Type::Vector, 3, 3),
+        (Type::Normal, 3, 3),
+        (Type::Matrix, 16, 16),
+        (Type::DoubleMatrix, 16, 32),
+        (Type::Reference, 1, pointer),
+    ] {
+        assert_eq!(Some(type_), Type::from_nsi_type(type_ as i32));
+        assert_eq!(components, type_.component_count(), "{:?}", type_);
+        assert_eq!(size_in_f32, type_.size_in_f32(), "{:?}", type_);
+    }
+
+    assert_eq!(11, Type::ALL.len());
+    assert_eq!(None, Type::from_nsi_type(-1));
+
+    fn type_of<T: ArgType + ?Sized>(_: &T) -> Type {
+        Type::from_rust::<T>()
+    }
+
+    let matrix = [0.0f32; 16];
+    let double_matrix = [0.0f64; 16];
+    let vector = [0.0f32; 3];
+
+    assert_eq!(Type::Float, type_of(&1.0f32));
+    assert_eq!(Type::Double, type_of(&1.0f64));
+    assert_eq!(Type::Integer, type_of(&1i32));
+    assert_eq!(Type::String, type_of("foo"));
+    assert_eq!(Type::String, type_of(&"foo".to_string()));
+    assert_eq!(Type::Matrix, type_of(&matrix));
+    assert_eq!(Type::DoubleMatrix, type_of(&double_matrix));
+    assert_eq!(Type::Reference, type_of(&(&vector as *const _)));
+
+    assert_eq!(Type::Float, type_of(&nsi::Float::new(1.0)));
+    assert_eq!(Type::Double, type_of(&nsi::Double::new(1.0)));
+    assert_eq!(Type::Integer, type_of(&nsi::Integer::new(1)));
+    assert_eq!(Type::String, type_of(&nsi::String::new("foo")));
+    assert_eq!(Type::Color, type_of(&nsi::Color::new(vector)));
+    assert_eq!(Type::Point, type_of(&nsi::Point::new(&vector)));
+    assert_eq!(Type::Vector, type_of(&nsi::Vector::new(&vector)));
+    assert_eq!(Type::Normal, type_of(&nsi::Normal::new(&vector)));
+    assert_eq!(Type::Matrix, type_of(&nsi::Matrix::new(&matrix)));
+    assert_eq!(
+        Type::DoubleMatrix,
+        type_of(&nsi::DoubleMatrix::new(&double_matrix))
+    );
+    assert_eq!(Type::Color, type_of(&nsi::Colors::new(&vector)));
+    assert_eq!(Type::Matrix, type_of(&nsi::Matrices::new(&matrix)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_node_builder() {
+    let mut handles = Vec::new();
+    let stream = record_stream(|ctx| {
+        let mesh = ctx
+            .build(nsi::NodeType::Mesh)
+            .handle("triangle")
+            .attr(nsi::integers!("nvertices", &[3]))
+            .attr(nsi::points!(
+                "P",
+                &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            ))
+            .connect_to(nsi::ROOT, "objects")
+            .finish();
+        assert_eq!("triangle", mesh);
+
+        handles.push(ctx.build(nsi::NodeType::Transform).finish());
+        handles.push(ctx.build(nsi::NodeType::Transform).finish());
+        assert_eq!(Some("transform"), ctx.graph().node_type(&handles[1]));
+    });
+
+    assert!(handles[0].starts_with("transform_"));
+    assert_ne!(handles[0], handles[1]);
+    assert_eq!(
+        format!(
+            "Create \"triangle\" \"mesh\"\n\
+             SetAttribute \"triangle\" \"nvertices\" \"int\" 1 [ 3 ] \
+             \"P\" \"point\" 3 [ 0.0 0.0 0.0 1.0 0.0 0.0 0.0 1.0 0.0 ]\n\
+             Connect \"triangle\" \"\" \".root\" \"objects\"\n\
+             Create \"{0}\" \"transform\"\n\
+             Create \"{1}\" \"transform\"\n",
+            handles[0], handles[1]
+        ),
+        stream
+    );
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+#[test]
+fn test_to_ndarray() {
+    let format = nsi::output::PixelFormat::from_channel_names(&[
+        "Ci.001.r", "Ci.001.g", "Ci.001.b", "z.000",
+    ]);
+
+    let (width, height) = (5, 3);
+    let pixels = (0..width * height)
+        .flat_map(|pixel| {
+            let (x, y) = ((pixel % width) as f32, (pixel / width) as f32);
+            [x, y, 0.5, 10.0 * y + x]
+        })
+        .collect::<Vec<_>>();
+
+    // One pixel short.
+    assert!(nsi::output::to_ndarray(
+        &format,
+        pixels[4..].to_vec(),
+        width,
+        height
+    )
+    .is_err());
+
+    let image =
+        nsi::output::to_ndarray(&format, pixels, width, height).unwrap();
+
+    assert_eq!(&[height, width, 4], image.shape());
+    assert_eq!(4.0, image[[2, 4, 0]]);
+    assert_eq!(2.0, image[[2, 4, 1]]);
+    assert_eq!(0.5, image[[2, 4, 2]]);
+    assert_eq!(13.0, image[[1, 3, 3]]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_strings_stream() {
+    let stream = record_stream(|ctx| {
+        ctx.create("volume", nsi::VOLUME, None);
+        ctx.set_attribute(
+            "volume",
+            &[nsi::strings!("grids", &["density", "temperature"])],
+        );
+
+        // Owned strings that are gone before the call is made.
+        let names = vec!["heat".to_string(), "smoke".to_string()];
+        let arg = nsi::strings!("fields", &names);
+        drop(names);
+        ctx.set_attribute("volume", &[arg]);
+
+        // Pushed through the graph mirror.
+        ctx.duplicate_subtree("volume", "copy_");
+    });
+
+    assert_eq!(
+        "Create \"volume\" \"volume\"\n\
+         SetAttribute \"volume\" \"grids\" \"string\" 2 \
+         [ \"density\" \"temperature\" ]\n\
+         SetAttribute \"volume\" \"fields\" \"string\" 2 [ \"heat\" \"smoke\" ]\n\
+         Create \"copy_volume\" \"volume\"\n\
+         SetAttribute \"copy_volume\" \"grids\" \"string\" 2 \
+         [ \"density\" \"temperature\" ]\n\
+         SetAttribute \"copy_volume\" \"fields\" \"string\" 2 \
+         [ \"heat\" \"smoke\" ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_new_or_explain() {
+    match nsi::Context::new_or_explain(None) {
+        Ok(_) => {}
+        Err(nsi::ContextError::LibraryNotFound { attempts }) => {
+            assert!(attempts
+                .iter()
+                .any(|(location, _)| location == "$DELIGHT"
+                    || location.ends_with("($DELIGHT)")));
+
+            let message =
+                nsi::ContextError::LibraryNotFound { attempts }.to_string();
+            assert!(message.starts_with("Could not load lib3delight."));
+            assert!(message.contains("Install 3Delight"));
+        }
+        Err(nsi::ContextError::BeginFailed) => {
+            panic!("The renderer could not create a context.")
+        }
+    }
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_empty_buckets() {
+    use std::ffi::CString;
+
+    let open = |empty_buckets: Option<i32>| {
+        let driver = CString::new("ferris").unwrap();
+        let file_name = CString::new("empty_buckets").unwrap();
+        let channel = CString::new("r").unwrap();
+        let name = CString::new("emptybuckets").unwrap();
+
+        let parameters = empty_buckets
+            .iter()
+            .map(|value| ndspy_sys::UserParameter {
+                name: name.as_ptr(),
+                valueType: b'i' as _,
+                valueCount: 1,
+                value: value as *const i32 as _,
+                nbytes: std::mem::size_of::<i32>() as _,
+            })
+            .collect::<Vec<_>>();
+        let mut format = [ndspy_sys::PtDspyDevFormat {
+            name: channel.as_ptr(),
+            type_: ndspy_sys::PkDspyFloat32,
+        }];
+        let mut flag_stuff = ndspy_sys::PtFlagStuff { flags: -1 };
+        let mut handle = std::ptr::null_mut();
+
+        assert!(matches!(
+            nsi::output::image_open(
+                &mut handle,
+                driver.as_ptr(),
+                file_name.as_ptr(),
+                2,
+                2,
+                parameters.len() as _,
+                parameters.as_ptr(),
+                format.len() as _,
+                format.as_mut_ptr(),
+                &mut flag_stuff,
+            ),
+            ndspy_sys::PtDspyError::None
+        ));
+        nsi::output::image_close(handle);
+
+        flag_stuff.flags as u32
+    };
+
+    // The default.
+    assert_eq!(ndspy_sys::PkDspyFlagsWantsEmptyBuckets, open(None));
+    assert_eq!(ndspy_sys::PkDspyFlagsWantsEmptyBuckets, open(Some(1)));
+    assert_eq!(0, open(Some(0)));
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_pass_callback_without_empty_buckets() {
+    use nsi::CallbackPtr;
+    use std::{cell::Cell, ffi::CString, rc::Rc};
+
+    let passes = Rc::new(Cell::new(0));
+    let fn_pass = nsi::output::PassCallback::new({
+        let passes = passes.clone();
+        move |_: &str,
+              _: usize,
+              _: usize,
+              pass: usize,
+              _: &nsi::output::PixelFormat,
+              _: &[f32]| {
+            assert_eq!(passes.get(), pass);
+            passes.set(pass + 1);
+            nsi::output::Error::None
+        }
+    })
+    .to_ptr();
+
+    let driver = CString::new("ferris").unwrap();
+    let file_name = CString::new("passes").unwrap();
+    let channels = ["r", "g", "b"].map(|name| CString::new(name).unwrap());
+    let empty_buckets_name = CString::new("emptybuckets").unwrap();
+    let pass_name = CString::new("callback.pass").unwrap();
+    let empty_buckets = 0i32;
+
+    let parameters = [
+        ndspy_sys::UserParameter {
+            name: empty_buckets_name.as_ptr(),
+            valueType: b'i' as _,
+            valueCount: 1,
+            value: &empty_buckets as *const i32 as _,
+            nbytes: std::mem::size_of::<i32>() as _,
+        },
+        ndspy_sys::UserParameter {
+            name: pass_name.as_ptr(),
+            valueType: b'p' as _,
+            valueCount: 1,
+            value: fn_pass as _,
+            nbytes: std::mem::size_of::<*const std::ffi::c_void>() as _,
+        },
+    ];
+    let mut format =
+        channels
+            .each_ref()
+            .map(|channel| ndspy_sys::PtDspyDevFormat {
+                name: channel.as_ptr(),
+                type_: ndspy_sys::PkDspyFloat32,
+            });
+    let mut flag_stuff = ndspy_sys::PtFlagStuff { flags: -1 };
+    let mut handle = std::ptr::null_mut();
+
+    nsi::output::image_open(
+        &mut handle,
+        driver.as_ptr(),
+        file_name.as_ptr(),
+        2,
+        2,
+        parameters.len() as _,
+        parameters.as_ptr(),
+        format.len() as _,
+        format.as_mut_ptr(),
+        &mut flag_stuff,
+    );
+
+    // "emptybuckets" 0 is overridden so all pixels are counted.
+    assert_eq!(
+        ndspy_sys::PkDspyFlagsWantsEmptyBuckets,
+        flag_stuff.flags as u32
+    );
+
+    // Two passes of two 2×1 buckets each.
+    let pixels = [0.5f32; 2 * 3];
+    for _ in 0..2 {
+        for y in 0..2 {
+            nsi::output::image_write(
+                handle,
+                0,
+                2,
+                y,
+                y + 1,
+                (3 * std::mem::size_of::<f32>()) as _,
+                pixels.as_ptr() as _,
+            );
+        }
+    }
+    nsi::output::image_close(handle);
+
+    assert_eq!(2, passes.get());
+}
+
+#[cfg(test)]
+#[test]
+fn test_owned_arg() {
+    let owned = [
+        nsi::OwnedArg::new("fov", 35.0f32.into()),
+        nsi::OwnedArg::new("resolution", vec![640, 480].into()).array_len(2),
+        nsi::OwnedArg::new("name", "camera".into()),
+        nsi::OwnedArg::new(
+            "P",
+            nsi::OwnedArgData::Points(vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
+        )
+        .per_vertex(),
+    ];
+
+    let args = owned.iter().map(nsi::OwnedArg::as_arg).collect::<Vec<_>>();
+    let names = args.iter().map(|arg| arg.name()).collect::<Vec<_>>();
+    assert_eq!(vec!["fov", "resolution", "name", "P"], names);
+
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+    ctx.batch(|batch| {
+        // The per-vertex "P" can not be streamed.
+        batch.set_attribute("camera", &args[..3]);
+
+        assert_eq!(
+            "SetAttribute \"camera\" \"fov\" \"float\" 1 [ 35.0 ] \
+             \"resolution\" \"int[2]\" 1 [ 640 480 ] \
+             \"name\" \"string\" 1 [ \"camera\" ]\n",
+            batch.stream()
+        );
+    });
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_connect_layers_to_driver() {
+    use nsi::output::LayerDepth;
+
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("driver", nsi::OUTPUT_DRIVER, None);
+    ctx.create(
+        "depth",
+        nsi::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "z"),
+            nsi::string!("layertype", "scalar"),
+        ]),
+    );
+    ctx.create(
+        "beauty",
+        nsi::OUTPUT_LAYER,
+        Some(&[nsi::integer!("withalpha", 1)]),
+    );
+    ctx.create(
+        "normal",
+        nsi::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "N_world"),
+            nsi::string!("layertype", "vector"),
+        ]),
+    );
+
+    // Connected in a different order before; this must not matter.
+    ctx.connect("driver", None, "normal", "outputdrivers", None);
+
+    let pixel_format =
+        ctx.connect_layers_to_driver("driver", &["beauty", "normal", "depth"]);
+
+    assert_eq!(
+        vec![
+            ("Ci", LayerDepth::ColorAndAlpha, 0),
+            ("N_world", LayerDepth::Vector, 4),
+            ("z", LayerDepth::OneChannel, 7),
+        ],
+        pixel_format
+            .iter()
+            .map(|layer| (layer.name(), layer.depth(), layer.offset()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(8, pixel_format.channels());
+}
+
+#[cfg(test)]
+#[test]
+fn test_missing_array_len() {
+    use crate::argument::missing_array_len;
+
+    // Flagged.
+    assert_eq!(
+        Some(2),
+        missing_array_len(&nsi::integers!("resolution", &[640, 480]))
+    );
+    assert_eq!(
+        Some(2),
+        missing_array_len(&nsi::doubles!(
+            "screenwindow",
+            &[-1.0, -1.0, 1.0, 1.0]
+        ))
+    );
+
+    // Not flagged.
+    assert_eq!(
+        None,
+        missing_array_len(
+            &nsi::integers!("resolution", &[640, 480]).array_len(2)
+        )
+    );
+    assert_eq!(
+        None,
+        missing_array_len(&nsi::integers!("nvertices", &[3, 4]))
+    );
+    assert_eq!(
+        None,
+        missing_array_len(&nsi::integers!("resolution", &[640]))
+    );
+    assert_eq!(
+        None,
+        missing_array_len(&nsi::floats!("crop", &[0.0, 0.5, 1.0]))
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_enabled() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("xform", nsi::TRANSFORM, None);
+    ctx.create("teapot", nsi::MESH, None);
+    ctx.connect("teapot", None, nsi::ROOT, "objects", None);
+    ctx.connect(
+        "teapot",
+        None,
+        "xform",
+        "objects",
+        Some(&[nsi::integer!("strength", 1)]),
+    );
+
+    let parents = |ctx: &nsi::Context| {
+        let mut parents = ctx
+            .graph()
+            .connections
+            .iter()
+            .filter(|c| "teapot" == c.from)
+            .map(|c| (c.to.clone(), c.strength))
+            .collect::<Vec<_>>();
+        parents.sort();
+        parents
+    };
+    let connected = parents(&ctx);
+    assert_eq!(2, connected.len());
+
+    for _ in 0..2 {
+        ctx.set_enabled("teapot", false);
+        assert!(parents(&ctx).is_empty());
+        // Disabling twice must not lose the connections.
+        ctx.set_enabled("teapot", false);
+
+        ctx.set_enabled("teapot", true);
+        assert_eq!(connected, parents(&ctx));
+    }
+
+    ctx.set_enabled("teapot", true);
+    assert_eq!(connected, parents(&ctx));
+}
+
+#[cfg(all(test, feature = "output"))]
+#[test]
+fn test_ocio_config() {
+    use nsi::output::OcioConfig;
+
+    let config = OcioConfig::parse(
+        r#"ocio_profile_version: 1
+
+roles:
+  scene_linear: ACES - ACEScg
+
+displays:
+  ACES:
+    - !<View> {name: sRGB, colorspace: Output - sRGB}
+    - !<View> {name: Raw, colorspace: Utility - Raw}
+  "Rec.709":
+    - !<View> {name: Film, colorspace: Output - Rec.709}
+
+active_displays: [ACES]
+
+colorspaces:
+  - !<ColorSpace>
+    name: ACES - ACEScg
+    family: ACES
+    description: |
+      The ACEScg color space
+  # A comment.
+  - !<ColorSpace>
+    name: "Utility - Raw"
+    isdata: true
+- !<ColorSpace> {name: Output - sRGB}
+"#,
+    );
+
+    assert_eq!(
+        ["ACES - ACEScg", "Utility - Raw", "Output - sRGB"],
+        config.color_spaces()
+    );
+    assert_eq!(
+        vec![("ACES", vec!["sRGB", "Raw"]), ("Rec.709", vec!["Film"])],
+        config
+            .displays()
+            .iter()
+            .map(|display| (
+                display.name(),
+                display.views().iter().map(String::as_str).collect()
+            ))
+            .collect::<Vec<(_, Vec<_>)>>()
+    );
+
+    let path = std::env::temp_dir().join("nsi_test_ocio_config.ocio");
+    std::fs::write(&path, "colorspaces:\n  - !<ColorSpace>\n    name: raw\n")
+        .unwrap();
+    let loaded = OcioConfig::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(["raw"], loaded.color_spaces());
+    assert!(loaded.displays().is_empty());
+    assert!(OcioConfig::load(&path).is_err());
+}
+
+#[cfg(test)]
+#[test]
+fn test_focus_on() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let translation = |x: f64, y: f64, z: f64| {
+        [
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, x, y,
+            z, 1.0,
+        ]
+    };
+
+    ctx.create("camera_xform", nsi::TRANSFORM, None);
+    ctx.set_attribute(
+        "camera_xform",
+        &[nsi::double_matrix!(
+            "transformationmatrix",
+            &translation(0.0, 3.0, 0.0)
+        )],
+    );
+    ctx.connect("camera_xform", None, nsi::ROOT, "objects", None);
+    ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+    ctx.connect("camera", None, "camera_xform", "objects", None);
+
+    ctx.create("teapot_xform", nsi::TRANSFORM, None);
+    ctx.set_attribute(
+        "teapot_xform",
+        &[nsi::double_matrix!(
+            "transformationmatrix",
+            &translation(4.0, 3.0, 0.0)
+        )],
+    );
+    ctx.create("teapot", nsi::MESH, None);
+    ctx.connect("teapot", None, "teapot_xform", "objects", None);
+
+    // Not connected to the root yet.
+    assert_eq!(None, ctx.focus_on("camera", "teapot"));
+
+    ctx.connect("teapot_xform", None, nsi::ROOT, "objects", None);
+    assert_eq!(Some(4.0), ctx.focus_on("camera", "teapot"));
+    assert!(ctx
+        .graph()
+        .attributes("camera", "depthoffield.focaldistance")
+        .next()
+        .is_some());
+}
+
+#[cfg(test)]
+#[test]
+fn test_world_position() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    let translation = |x: f64, y: f64, z: f64| {
+        [
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, x, y,
+            z, 1.0,
+        ]
+    };
+    // A quarter turn around Y, mapping +X to -Z.
+    let rotation = [
+        0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 1.0,
+    ];
+
+    ctx.create("outer", nsi::TRANSFORM, None);
+    ctx.set_attribute(
+        "outer",
+        &[nsi::double_matrix!(
+            "transformationmatrix",
+            &translation(0.0, 1.0, 0.0)
+        )],
+    );
+    ctx.create("middle", nsi::TRANSFORM, None);
+    ctx.set_attribute(
+        "middle",
+        &[nsi::double_matrix!("transformationmatrix", &rotation)],
+    );
+    ctx.create("inner", nsi::TRANSFORM, None);
+    ctx.set_attribute(
+        "inner",
+        &[nsi::double_matrix!(
+            "transformationmatrix",
+            &translation(2.0, 0.0, 0.0)
+        )],
+    );
+    ctx.create("teapot", nsi::MESH, None);
+
+    ctx.connect("teapot", None, "inner", "objects", None);
+    ctx.connect("inner", None, "middle", "objects", None);
+    ctx.connect("middle", None, "outer", "objects", None);
+
+    // Not connected to the root yet.
+    assert_eq!(None, ctx.world_position("teapot"));
+
+    ctx.connect("outer", None, nsi::ROOT, "objects", None);
+
+    assert_eq!(Some([0.0, 0.0, 0.0]), ctx.world_position(nsi::ROOT));
+    assert_eq!(Some([0.0, 1.0, 0.0]), ctx.world_position("middle"));
+    assert_eq!(Some([0.0, 1.0, -2.0]), ctx.world_position("inner"));
+    assert_eq!(Some([0.0, 1.0, -2.0]), ctx.world_position("teapot"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_get_attribute() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+    assert_eq!(None, ctx.get_attribute("camera", "fov"));
+
+    ctx.set_attribute("camera", &[nsi::float!("fov", 35.0)]);
+    ctx.set_attribute("camera", &[nsi::float!("fov", 50.0)]);
+    assert_eq!(
+        Some(nsi::OwnedArg::new("fov", 50.0f32.into())),
+        ctx.get_attribute("camera", "fov")
+    );
+
+    ctx.create("screen", nsi::SCREEN, None);
+    ctx.set_attribute(
+        "screen",
+        &[
+            nsi::integers!("resolution", &[640, 480]).array_len(2),
+            nsi::string!("name", "beauty"),
+            nsi::strings!("tags", &["a", "b"]),
+            nsi::color!("tint", &[1.0, 0.5, 0.25]),
+        ],
+    );
+    assert_eq!(
+        Some(
+            nsi::OwnedArg::new("resolution", vec![640, 480].into())
+                .array_len(2)
+        ),
+        ctx.get_attribute("screen", "resolution")
+    );
+    assert_eq!(
+        Some(nsi::OwnedArg::new("name", "beauty".into())),
+        ctx.get_attribute("screen", "name")
+    );
+    assert_eq!(
+        &nsi::OwnedArgData::Strings(vec!["a".into(), "b".into()]),
+        ctx.get_attribute("screen", "tags").unwrap().data()
+    );
+    assert_eq!(
+        &nsi::OwnedArgData::Color([1.0, 0.5, 0.25]),
+        ctx.get_attribute("screen", "tint").unwrap().data()
+    );
+
+    ctx.set_attribute_at_time(
+        "camera",
+        0.0,
+        &[nsi::double!("shutterangle", 90.0)],
+    );
+    ctx.set_attribute_at_time(
+        "camera",
+        1.0,
+        &[nsi::double!("shutterangle", 180.0)],
+    );
+    assert_eq!(
+        &nsi::OwnedArgData::Double(180.0),
+        ctx.get_attribute("camera", "shutterangle").unwrap().data()
+    );
+
+    ctx.delete_attribute("camera", "fov");
+    assert_eq!(None, ctx.get_attribute("camera", "fov"));
+}
+
+#[cfg(test)]
+#[test]
+fn test_compressed_binary_stream() {
+    let path = std::env::temp_dir().join("nsi_test_stream.nsi.gz");
+
+    {
+        let ctx = nsi::Context::new_stream(
+            path.to_str().unwrap(),
+            nsi::StreamFormat::Binary,
+            nsi::StreamCompression::Gzip,
+        )
+        .expect("Could not create NSI stream.");
+
+        ctx.create("plane", nsi::PLANE, None);
+        ctx.connect("plane", None, nsi::ROOT, "objects", None);
+    }
+
+    let stream = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // The gzip magic number.
+    assert!(stream.starts_with(&[0x1f, 0x8b]));
+}
+
+#[cfg(test)]
+#[test]
+fn test_batch() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.batch(|batch| {
+        batch.create("mesh", nsi::MESH, None);
+        batch.set_attribute(
+            "mesh",
+            &[
+                nsi::integers!("nvertices", &[3]),
+                nsi::string!("subdivision.scheme", "catmull-clark"),
+                nsi::integers!("resolution", &[640, 480]).array_len(2),
+            ],
+        );
+        batch.connect("mesh", None, nsi::ROOT, "objects", None);
+
+        assert_eq!(
+            "Create \"mesh\" \"mesh\"\n\
+             SetAttribute \"mesh\" \"nvertices\" \"int\" 1 [ 3 ] \
+             \"subdivision.scheme\" \"string\" 1 [ \"catmull-clark\" ] \
+             \"resolution\" \"int[2]\" 1 [ 640 480 ]\n\
+             Connect \"mesh\" \"\" \".root\" \"objects\"\n",
+            batch.stream()
+        );
+
+        // Flagged arguments flush the stream and are set directly.
+        batch.set_attribute(
+            "mesh",
+            &[nsi::points!(
+                "P",
+                &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            )
+            .per_vertex()],
+        );
+        assert!(batch.stream().is_empty());
+
+        // So are values the text format can not express.
+        batch.set_attribute(
+            "mesh",
+            &[
+                nsi::float!("width", f32::NAN),
+                nsi::double!("time", f64::INFINITY),
+            ],
+        );
+        assert!(batch.stream().is_empty());
+    });
+
+    assert_eq!(1, ctx.graph().attributes("mesh", "P").count());
+
+    assert!(ctx
+        .validate()
+        .iter()
+        .all(|issue| !issue.message.contains("unknown")));
+}
+
+#[cfg(test)]
+#[test]
+fn test_reset_to_default() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+
+    ctx.create("xform", nsi::TRANSFORM, None);
+
+    let translation = [
+        1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 1., 2., 3., 1.,
+    ];
+    ctx.set_attribute_at_time(
+        "xform",
+        0.0,
+        &[nsi::double_matrix!("transformationmatrix", &translation)],
+    );
+    ctx.set_attribute_at_time(
+        "xform",
+        1.0,
+        &[nsi::double_matrix!("transformationmatrix", &translation)],
+    );
+    assert_eq!(
+        2,
+        ctx.graph()
+            .attributes("xform", "transformationmatrix")
+            .count()
+    );
+
+    // Both time samples are gone; the transform is an identity again.
+    ctx.reset_to_default("xform", "transformationmatrix");
+    assert_eq!(
+        0,
+        ctx.graph()
+            .attributes("xform", "transformationmatrix")
+            .count()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_attribute_check() {
+    let ctx = nsi::Context::new(None).expect("Could not create NSI context.");
+    ctx.create("screen", nsi::SCREEN, None);
+    ctx.create("shader", nsi::SHADER, None);
+
+    ctx.set_attribute_check(nsi::AttributeCheck::Deny);
+
+    ctx.set_attribute(
+        "screen",
+        &[
+            nsi::integers!("resolutoin", &[640, 480]).array_len(2),
+            nsi::integer!("oversampling", 16),
+        ],
+    );
+    assert_eq!(0, ctx.graph().attributes("screen", "resolutoin").count());
+    assert_eq!(1, ctx.graph().attributes("screen", "oversampling").count());
+
+    // Shader parameters are passed through.
+    ctx.set_attribute("shader", &[nsi::float!("roughness", 0.3)]);
+    assert_eq!(1, ctx.graph().attributes("shader", "roughness").count());
+
+    // The single attribute setters are checked too.
+    ctx.set_integer("screen", "oversampilng", 4);
+    ctx.set_integer("screen", "oversampling", 4);
+    assert_eq!(0, ctx.graph().attributes("screen", "oversampilng").count());
+    assert_eq!(
+        Some(4),
+        ctx.graph().integer_attribute("screen", "oversampling")
+    );
+
+    // .global is checked as such, including attribute families. It is not
+    // part of the mirror; the trace shows what is sent.
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = calls.clone();
+    ctx.set_trace_sink(move |call: &str| {
+        sink.lock().unwrap().push(call.to_string())
+    });
+    ctx.set_trace(true);
+    ctx.set_progressive(true, Some(8));
+    ctx.set_string(nsi::GLOBAL, "metadata.shot", "sh010");
+    ctx.set_integer(nsi::GLOBAL, "numberofthreadz", 4);
+    ctx.set_attribute(nsi::ROOT, &[nsi::integer!("anything", 1)]);
+    ctx.set_trace(false);
+    assert_eq!(
+        vec![
+            "SetAttribute \".global\" \"progressive.maxpasses\" \"int\" 1 [ 8 ]",
+            "SetAttribute \".global\" \"metadata.shot\" \"string\" 1 [ \"sh010\" ]",
+            "SetAttribute \".root\" \"anything\" \"int\" 1 [ 1 ]",
+        ],
+        *calls.lock().unwrap()
+    );
+
+    // Warnings do not block.
+    ctx.set_attribute_check(nsi::AttributeCheck::Warn);
+    ctx.set_attribute("screen", &[nsi::integer!("oversampilng", 4)]);
+    assert_eq!(1, ctx.graph().attributes("screen", "oversampilng").count());
+}
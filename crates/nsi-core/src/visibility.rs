@@ -0,0 +1,166 @@
+//! Ray visibility of geometry, as a bitmask or as named fields.
+use crate::{Arg, ArgData, ArgVec, Context, Integer};
+use bitflags::bitflags;
+
+bitflags! {
+    /// The kinds of rays an object is visible to.
+    ///
+    /// Each flag corresponds to a `"visibility.*"` attribute of an
+    /// [`attributes`](crate::ATTRIBUTES) node. Objects are visible to all
+    /// rays by default.
+    ///
+    /// See [`Context::set_visibility()`](crate::Context::set_visibility())
+    /// and [`Context::visibility_for()`](crate::Context::visibility_for()).
+    /// For the same with named fields, see [`RenderVisibility`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use nsi_core as nsi;
+    /// // A shadow catcher is not seen by the camera but receives
+    /// // shadows and shows up in reflections.
+    /// let shadow_catcher =
+    ///     nsi::VisibilityFlags::all() - nsi::VisibilityFlags::CAMERA;
+    ///
+    /// assert!(shadow_catcher.contains(nsi::VisibilityFlags::SHADOW));
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VisibilityFlags: u8 {
+        /// `"visibility.camera"`
+        const CAMERA = 1 << 0;
+        /// `"visibility.diffuse"`
+        const DIFFUSE = 1 << 1;
+        /// `"visibility.hair"`
+        const HAIR = 1 << 2;
+        /// `"visibility.reflection"`
+        const REFLECTION = 1 << 3;
+        /// `"visibility.refraction"`
+        const REFRACTION = 1 << 4;
+        /// `"visibility.shadow"`
+        const SHADOW = 1 << 5;
+        /// `"visibility.specular"`
+        const SPECULAR = 1 << 6;
+        /// `"visibility.volume"`
+        const VOLUME = 1 << 7;
+    }
+}
+
+impl Default for VisibilityFlags {
+    /// Visible to all rays.
+    #[inline]
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl VisibilityFlags {
+    /// Returns the name of the attribute of a single flag.
+    pub(crate) fn attribute_name(self) -> &'static str {
+        match self {
+            Self::CAMERA => "visibility.camera",
+            Self::DIFFUSE => "visibility.diffuse",
+            Self::HAIR => "visibility.hair",
+            Self::REFLECTION => "visibility.reflection",
+            Self::REFRACTION => "visibility.refraction",
+            Self::SHADOW => "visibility.shadow",
+            Self::SPECULAR => "visibility.specular",
+            Self::VOLUME => "visibility.volume",
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The kinds of rays an object is visible to, as named fields.
+///
+/// This is the same as [`VisibilityFlags`] for those who prefer named
+/// fields. Each field corresponds to a `"visibility.*"` attribute of an
+/// [`attributes`](crate::ATTRIBUTES) node. Only fields that are [`Some`] are
+/// sent to the renderer; all others keep their current value.
+///
+/// # Examples
+///
+/// ```
+/// # use nsi_core as nsi;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("attributes", nsi::ATTRIBUTES, None);
+/// // Hide from the camera and from shadow rays; leave everything else.
+/// nsi::RenderVisibility {
+///     camera: Some(false),
+///     shadow: Some(false),
+///     ..Default::default()
+/// }
+/// .apply(&ctx, "attributes");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RenderVisibility {
+    /// `"visibility.camera"`
+    pub camera: Option<bool>,
+    /// `"visibility.diffuse"`
+    pub diffuse: Option<bool>,
+    /// `"visibility.hair"`
+    pub hair: Option<bool>,
+    /// `"visibility.reflection"`
+    pub reflection: Option<bool>,
+    /// `"visibility.refraction"`
+    pub refraction: Option<bool>,
+    /// `"visibility.shadow"`
+    pub shadow: Option<bool>,
+    /// `"visibility.specular"`
+    pub specular: Option<bool>,
+    /// `"visibility.volume"`
+    pub volume: Option<bool>,
+}
+
+impl RenderVisibility {
+    /// Returns the arguments for all fields that are set.
+    pub fn args(&self) -> ArgVec<'static, 'static> {
+        [
+            (VisibilityFlags::CAMERA, self.camera),
+            (VisibilityFlags::DIFFUSE, self.diffuse),
+            (VisibilityFlags::HAIR, self.hair),
+            (VisibilityFlags::REFLECTION, self.reflection),
+            (VisibilityFlags::REFRACTION, self.refraction),
+            (VisibilityFlags::SHADOW, self.shadow),
+            (VisibilityFlags::SPECULAR, self.specular),
+            (VisibilityFlags::VOLUME, self.volume),
+        ]
+        .into_iter()
+        .filter_map(|(flag, visible)| {
+            visible.map(|visible| {
+                Arg::new(
+                    flag.attribute_name(),
+                    ArgData::from(Integer::new(visible as _)),
+                )
+            })
+        })
+        .collect()
+    }
+
+    /// Sets all fields that are set on the
+    /// [`attributes`](crate::ATTRIBUTES) node `handle`.
+    pub fn apply(&self, ctx: &Context, handle: &str) {
+        let args = self.args();
+
+        if !args.is_empty() {
+            ctx.set_attribute(handle, &args);
+        }
+    }
+}
+
+impl From<VisibilityFlags> for RenderVisibility {
+    /// Sets all fields; those of the flags that are set to `true`.
+    fn from(flags: VisibilityFlags) -> Self {
+        let visible = |flag| Some(flags.contains(flag));
+
+        Self {
+            camera: visible(VisibilityFlags::CAMERA),
+            diffuse: visible(VisibilityFlags::DIFFUSE),
+            hair: visible(VisibilityFlags::HAIR),
+            reflection: visible(VisibilityFlags::REFLECTION),
+            refraction: visible(VisibilityFlags::REFRACTION),
+            shadow: visible(VisibilityFlags::SHADOW),
+            specular: visible(VisibilityFlags::SPECULAR),
+            volume: visible(VisibilityFlags::VOLUME),
+        }
+    }
+}
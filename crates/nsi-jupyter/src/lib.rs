@@ -23,11 +23,36 @@ trait _Jupyter<'a> {
     fn output_layer_as_jupyter(output_layer: &str, args: &ArgSlice<'_, 'a>);
 }
 
+/// The format of the images [`as_jupyter_with()`] puts into a notebook.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Lossless, 16bit PNG.
+    #[default]
+    Png,
+    /// Lossy, 8bit JPEG. Alpha is dropped; pixels are composited over
+    /// black.
+    ///
+    /// The `quality` ranges from `1` (smallest) to `100` (best).
+    ///
+    /// This is enabled through the `image` feature.
+    #[cfg(feature = "image")]
+    Jpeg { quality: u8 },
+    /// Lossy, 8bit WebP.
+    ///
+    /// The `quality` ranges from `0` (smallest) to `100` (best).
+    ///
+    /// This is enabled through the `image` feature.
+    #[cfg(feature = "image")]
+    WebP { quality: u8 },
+}
+
 /// Render a [`Screen`](nsi::SCREEN) inside a Jupyter Notebook.
 ///
 /// Essentially this dumps a 16bit PNG as a BASE64 encoded binary
 /// blob to `stdout`.
 ///
+/// This is [`as_jupyter_with()`] using [`ImageFormat::Png`].
+///
 /// The [`Context`](nsi::Context) is unchanged after this returns.
 ///
 /// # Examples
@@ -54,7 +79,33 @@ trait _Jupyter<'a> {
 /// ```
 /// # Arguments
 /// * `screen` – A [`Screen`](nsi::SCREEN).
+#[inline]
 pub fn as_jupyter(ctx: &nsi::Context, screen: &str) {
+    as_jupyter_with(ctx, screen, ImageFormat::Png);
+}
+
+/// Render a [`Screen`](nsi::SCREEN) inside a Jupyter Notebook, as an image
+/// of the given `format`.
+///
+/// Notebooks store images as BASE64 encoded text. A lossy
+/// [`ImageFormat`] makes the blob a fraction of the size of the default
+/// 16bit PNG. This matters for notebooks with many render cells. Use it for
+/// previews and keep the PNG where accuracy matters.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use nsi_core as nsi;
+/// # use nsi_jupyter::{as_jupyter_with, ImageFormat};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # #[cfg(feature = "image")]
+/// as_jupyter_with(&ctx, "screen", ImageFormat::Jpeg { quality: 85 });
+/// ```
+/// # Arguments
+/// * `screen` – A [`Screen`](nsi::SCREEN).
+///
+/// * `format` – The format of the image.
+pub fn as_jupyter_with(ctx: &nsi::Context, screen: &str, format: ImageFormat) {
     // RGB layer.
     ctx.create("jupyter_beauty", nsi::OUTPUT_LAYER, None);
     ctx.set_attribute(
@@ -81,6 +132,7 @@ pub fn as_jupyter(ctx: &nsi::Context, screen: &str) {
                     layer,
                     pixel_format.channels(),
                     &pixel_data,
+                    format,
                 )
             });
 
@@ -123,14 +175,16 @@ fn pixel_data_to_jupyter(
     layer: &Layer,
     channels: usize,
     pixel_data: &[f32],
+    format: ImageFormat,
 ) {
     let one = std::u16::MAX as f32;
     let offset = layer.offset();
 
-    png_to_jupyter(
+    image_to_jupyter(
         width,
         height,
         layer,
+        format,
         bytemuck::cast_slice(
             &(match layer.depth() {
                 LayerDepth::OneChannel => {
@@ -288,13 +342,28 @@ fn normalize(x: f32) -> f32 {
     0.5 + x * 0.5
 }
 
-fn png_to_jupyter(width: usize, height: usize, layer: &Layer, data: &[u8]) {
+// Encodes 16bit, big-endian pixels as an image of the given format.
+fn image_to_jupyter(
+    width: usize,
+    height: usize,
+    layer: &Layer,
+    format: ImageFormat,
+    data: &[u8],
+) {
     if LayerDepth::FourChannels == layer.depth()
         || LayerDepth::FourChannelsAndAlpha == layer.depth()
     {
         return;
     }
 
+    match format {
+        ImageFormat::Png => png_to_jupyter(width, height, layer, data),
+        #[cfg(feature = "image")]
+        _ => lossy_to_jupyter(width, height, layer, format, data),
+    }
+}
+
+fn png_to_jupyter(width: usize, height: usize, layer: &Layer, data: &[u8]) {
     let mut buffer = Vec::new();
     let mut png_encoder =
         png::Encoder::new(&mut buffer, width as _, height as _);
@@ -320,3 +389,98 @@ fn png_to_jupyter(width: usize, height: usize, layer: &Layer, data: &[u8]) {
     evcxr_runtime::mime_type("image/png")
         .text(general_purpose::STANDARD.encode(&buffer));
 }
+
+#[cfg(feature = "image")]
+fn lossy_to_jupyter(
+    width: usize,
+    height: usize,
+    layer: &Layer,
+    format: ImageFormat,
+    data: &[u8],
+) {
+    use image::{
+        codecs::{
+            jpeg::JpegEncoder,
+            webp::{WebPEncoder, WebPQuality},
+        },
+        ColorType,
+    };
+
+    let (channels, has_alpha) = match layer.depth() {
+        LayerDepth::OneChannel => (1, false),
+        LayerDepth::OneChannelAndAlpha => (2, true),
+        LayerDepth::Color | LayerDepth::Vector => (3, false),
+        LayerDepth::ColorAndAlpha | LayerDepth::VectorAndAlpha => (4, true),
+        _ => unreachable!(),
+    };
+    let color_channels = channels - has_alpha as usize;
+
+    // The high bytes of the big-endian 16bit values are the 8bit values.
+    let data = data.iter().step_by(2).copied().collect::<Vec<u8>>();
+
+    let mut buffer = Vec::new();
+
+    let mime_type = match format {
+        ImageFormat::Jpeg { quality } => {
+            let rgb = data
+                .par_chunks_exact(channels)
+                .flat_map_iter(|pixel| {
+                    let alpha =
+                        if has_alpha { pixel[channels - 1] } else { 255 }
+                            as u16;
+
+                    (0..3).map(move |i| {
+                        ((pixel[i.min(color_channels - 1)] as u16 * alpha
+                            + 127)
+                            / 255) as u8
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+                .encode(&rgb, width as _, height as _, ColorType::Rgb8)
+                .unwrap();
+
+            "image/jpeg"
+        }
+        ImageFormat::WebP { quality } => {
+            let rgba = data
+                .par_chunks_exact(channels)
+                .flat_map_iter(|pixel| {
+                    (0..3 + has_alpha as usize).map(move |i| {
+                        if 3 == i {
+                            pixel[channels - 1]
+                        } else {
+                            pixel[i.min(color_channels - 1)]
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Lossy WebP encoding is deprecated upstream but it is the point
+            // of this format here.
+            #[allow(deprecated)]
+            WebPEncoder::new_with_quality(
+                &mut buffer,
+                WebPQuality::lossy(quality.min(100)),
+            )
+            .encode(
+                &rgba,
+                width as _,
+                height as _,
+                if has_alpha {
+                    ColorType::Rgba8
+                } else {
+                    ColorType::Rgb8
+                },
+            )
+            .unwrap();
+
+            "image/webp"
+        }
+        ImageFormat::Png => unreachable!(),
+    };
+
+    evcxr_runtime::mime_type(mime_type)
+        .text(general_purpose::STANDARD.encode(&buffer));
+}
@@ -0,0 +1,177 @@
+//! A builder for the attributes of the `.global` node.
+use nsi_core as nsi;
+
+/// The smallest texture cache, in megabytes, that
+/// [`GlobalSettings::texture_memory()`] and
+/// [`set_texture_memory()`](crate::set_texture_memory()) accept.
+///
+/// Smaller caches make the renderer re-read textures so often that it
+/// spends most of its time on it.
+pub const MIN_TEXTURE_MEMORY: u32 = 64;
+
+/// Builder for the attributes of the [`.global`](nsi::GLOBAL) node.
+///
+/// Only settings that were explicitly set are sent to the renderer. All
+/// others keep their defaults. Attributes not covered here can still be set
+/// on the [`.global`](nsi::GLOBAL) node directly.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::GlobalSettings;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// GlobalSettings::new()
+///     .max_diffuse_depth(2)
+///     .max_reflection_depth(4)
+///     .apply(&ctx);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlobalSettings {
+    max_diffuse_depth: Option<i32>,
+    max_reflection_depth: Option<i32>,
+    max_refraction_depth: Option<i32>,
+    max_hair_depth: Option<i32>,
+    max_volume_depth: Option<i32>,
+    shading_samples: Option<i32>,
+    volume_samples: Option<i32>,
+    texture_memory: Option<i32>,
+}
+
+impl GlobalSettings {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum depth of all ray types to `depth`.
+    ///
+    /// This is a shortcut for calling all the `max_*_depth()` methods with
+    /// the same value.
+    #[inline]
+    #[must_use]
+    pub fn max_ray_depth(self, depth: i32) -> Self {
+        self.max_diffuse_depth(depth)
+            .max_reflection_depth(depth)
+            .max_refraction_depth(depth)
+            .max_hair_depth(depth)
+            .max_volume_depth(depth)
+    }
+
+    /// Sets the maximum diffuse ray depth
+    /// (`"maximumraydepth.diffuse"`). The default is `1`.
+    #[inline]
+    #[must_use]
+    pub fn max_diffuse_depth(mut self, depth: i32) -> Self {
+        self.max_diffuse_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum reflection ray depth
+    /// (`"maximumraydepth.reflection"`). The default is `1`.
+    #[inline]
+    #[must_use]
+    pub fn max_reflection_depth(mut self, depth: i32) -> Self {
+        self.max_reflection_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum refraction ray depth
+    /// (`"maximumraydepth.refraction"`). The default is `4`.
+    #[inline]
+    #[must_use]
+    pub fn max_refraction_depth(mut self, depth: i32) -> Self {
+        self.max_refraction_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum hair ray depth (`"maximumraydepth.hair"`).
+    /// The default is `4`.
+    #[inline]
+    #[must_use]
+    pub fn max_hair_depth(mut self, depth: i32) -> Self {
+        self.max_hair_depth = Some(depth);
+        self
+    }
+
+    /// Sets the maximum volume ray depth (`"maximumraydepth.volume"`).
+    /// The default is `0`.
+    #[inline]
+    #[must_use]
+    pub fn max_volume_depth(mut self, depth: i32) -> Self {
+        self.max_volume_depth = Some(depth);
+        self
+    }
+
+    /// Sets the number of samples taken per shading point
+    /// (`"quality.shadingsamples"`). The default is `1`.
+    ///
+    /// This is the main knob for the noise of surfaces and lights. Each
+    /// doubling removes roughly 30% of the noise and doubles the time spent
+    /// shading. `1`–`4` is fine for look development, `16`–`64` is a typical
+    /// range for final frames. Scenes with many glossy surfaces or small,
+    /// bright lights may need more.
+    #[inline]
+    #[must_use]
+    pub fn shading_samples(mut self, samples: i32) -> Self {
+        self.shading_samples = Some(samples);
+        self
+    }
+
+    /// Sets the number of samples taken per volume shading point
+    /// (`"quality.volumesamples"`). The default is `1`.
+    ///
+    /// This controls the noise of volumes – smoke, fog, fire – independently
+    /// of [`shading_samples()`](GlobalSettings::shading_samples()). Volumes
+    /// are expensive to sample, so raise this only while they stay noisy
+    /// after the surfaces have cleaned up; `4`–`16` covers most scenes.
+    /// Scenes without volumes are not affected.
+    #[inline]
+    #[must_use]
+    pub fn volume_samples(mut self, samples: i32) -> Self {
+        self.volume_samples = Some(samples);
+        self
+    }
+
+    /// Sets the size of the texture cache in megabytes
+    /// (`"texturememory"`).
+    ///
+    /// Values below [`MIN_TEXTURE_MEMORY`] are raised to it. See
+    /// [`set_texture_memory()`](crate::set_texture_memory()) for the
+    /// tradeoff.
+    #[inline]
+    #[must_use]
+    pub fn texture_memory(mut self, megabytes: u32) -> Self {
+        self.texture_memory =
+            Some(megabytes.clamp(MIN_TEXTURE_MEMORY, i32::MAX as _) as _);
+        self
+    }
+
+    /// Returns the arguments for all settings that were set.
+    pub fn args(&self) -> nsi::ArgVec<'static, 'static> {
+        [
+            ("maximumraydepth.diffuse", self.max_diffuse_depth),
+            ("maximumraydepth.reflection", self.max_reflection_depth),
+            ("maximumraydepth.refraction", self.max_refraction_depth),
+            ("maximumraydepth.hair", self.max_hair_depth),
+            ("maximumraydepth.volume", self.max_volume_depth),
+            ("quality.shadingsamples", self.shading_samples),
+            ("quality.volumesamples", self.volume_samples),
+            ("texturememory", self.texture_memory),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value.map(|value| nsi::integer!(name, value))
+        })
+        .collect()
+    }
+
+    /// Sets all settings that were set on the
+    /// [`.global`](nsi::GLOBAL) node of `ctx`.
+    pub fn apply(&self, ctx: &nsi::Context) {
+        let args = self.args();
+
+        if !args.is_empty() {
+            ctx.set_attribute(nsi::GLOBAL, &args);
+        }
+    }
+}
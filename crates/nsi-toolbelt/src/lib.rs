@@ -7,9 +7,57 @@
 //! Where ergonomically advised, creation methods names carry postfixes
 //! that specify the type of node being created, such as `shader`.
 use nsi_core as nsi;
+use rand::{rngs::SmallRng, SeedableRng};
+use std::cell::RefCell;
 use ultraviolet as uv;
+
+mod global_settings;
+pub use global_settings::*;
+
+mod matrix;
+pub use matrix::*;
+
+mod principled_shader;
+pub use principled_shader::*;
+
+mod shader_graph;
+pub use shader_graph::*;
+
+mod subdivision;
+pub use subdivision::*;
+
+mod to_nsi;
+pub use to_nsi::*;
+
+mod volume_shader;
+pub use volume_shader::*;
+
+#[cfg(feature = "polyhedron")]
+mod polyhedron;
+#[cfg(feature = "polyhedron")]
+pub use polyhedron::*;
 //use uv::{DVec3, DMat4};
 
+thread_local! {
+    // Source of generated handles, if seeded via set_handle_seed().
+    static HANDLE_RNG: RefCell<Option<SmallRng>> = const { RefCell::new(None) };
+}
+
+/// Makes the handles generated by the helpers in this crate reproducible.
+///
+/// Helpers that are passed [`None`] as a handle generate a random one. After
+/// calling this with `Some(seed)`, the same sequence of calls generates the
+/// same handles. E.g. an ɴsɪ stream written from the same code is then
+/// identical from run to run. Passing [`None`] goes back to random handles.
+///
+/// The seed applies to the calling thread only. Scenes built on other
+/// threads, e.g. by tests running in parallel, do not affect the sequence.
+///
+/// For reproducible images, also see [`set_frame()`].
+pub fn set_handle_seed(seed: Option<u64>) {
+    HANDLE_RNG.set(seed.map(SmallRng::seed_from_u64));
+}
+
 /// Generates a random handle if `handle` is `None` or falls through,
 /// otherwise.
 #[doc(hidden)]
@@ -21,10 +69,15 @@ pub fn generate_or_use_handle(
     match handle {
         Some(handle) => handle.to_string(),
         None => {
+            let name = HANDLE_RNG.with_borrow_mut(|rng| match rng {
+                Some(rng) => petname::Petnames::default().generate(rng, 3, "_"),
+                None => petname::petname(3, "_"),
+            });
+
             if let Some(prefix) = prefix {
-                String::from(prefix) + "_" + &petname::petname(3, "_")
+                String::from(prefix) + "_" + &name
             } else {
-                petname::petname(3, "_")
+                name
             }
         }
     }
@@ -39,16 +92,23 @@ pub fn generate_or_use_handle(
     match handle {
         Some(handle) => handle.to_string(),
         None => {
-            use rand::{
-                distributions::Alphanumeric, rngs::SmallRng, Rng, SeedableRng,
-            };
+            use rand::{distributions::Alphanumeric, Rng};
             use std::iter;
-            let mut rng = SmallRng::from_entropy();
+            HANDLE_RNG.with_borrow_mut(|seeded_rng| {
+                let mut entropy_rng;
+                let rng = match seeded_rng.as_mut() {
+                    Some(rng) => rng,
+                    None => {
+                        entropy_rng = SmallRng::from_entropy();
+                        &mut entropy_rng
+                    }
+                };
 
-            iter::repeat(())
-                .map(|()| rng.sample(Alphanumeric) as char)
-                .take(20)
-                .collect()
+                iter::repeat(())
+                    .map(|()| rng.sample(Alphanumeric) as char)
+                    .take(20)
+                    .collect()
+            })
         }
     }
 }
@@ -79,7 +139,7 @@ pub fn generate_or_use_handle(
 ///     // to the scale node.
 ///     append(
 ///         &ctx,
-///         &scaling(&ctx, None, &[10., 10., 10.]),
+///         &scaling(&ctx, None, [10., 10., 10.]),
 ///         // Use "objects" slot.
 ///         None,
 ///         "tetrahedron",
@@ -176,33 +236,76 @@ pub fn node<'a>(
     handle
 }
 
-/// Create a scaling transform node.
+/// Create a transform node from a [`Matrix4`].
+///
+/// `matrix` can be anything that converts into a [`Matrix4`]. E.g. a
+/// `[f64; 16]` or, with the `glam`/`nalgebra` features enabled, a matrix type
+/// from these crates.
 ///
 /// If `handle` is [`None`] a random handle is generated.
 ///
 /// Returns `handle` for convenience.
 #[inline]
-pub fn scaling(
+pub fn transform(
     ctx: &nsi::Context,
     handle: Option<&str>,
-    scale: &[f64; 3],
+    matrix: impl Into<Matrix4>,
 ) -> String {
-    let handle = generate_or_use_handle(handle, Some("scaling"));
+    let handle = generate_or_use_handle(handle, Some("transform"));
     ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
 
+    set_transformation_matrix(ctx, handle.as_str(), matrix);
+
+    handle
+}
+
+// Sets the "transformationmatrix" of a transform node.
+#[inline]
+fn set_transformation_matrix(
+    ctx: &nsi::Context,
+    handle: &str,
+    matrix: impl Into<Matrix4>,
+) {
     ctx.set_attribute(
-        handle.as_str(),
+        handle,
         &[nsi::double_matrix!(
             "transformationmatrix",
-            uv::DMat4::from_nonuniform_scale(uv::DVec3::from(scale)).as_array()
+            matrix.into().as_array()
         )],
     );
+}
+
+/// Create a scaling transform node.
+///
+/// `scale` can be anything that converts into a [`Vector3`]. E.g. a
+/// `[f64; 3]` or, with the `glam`/`nalgebra` features enabled, a vector type
+/// from these crates.
+///
+/// If `handle` is [`None`] a random handle is generated.
+///
+/// Returns `handle` for convenience.
+#[inline]
+pub fn scaling(
+    ctx: &nsi::Context,
+    handle: Option<&str>,
+    scale: impl Into<Vector3>,
+) -> String {
+    let handle = generate_or_use_handle(handle, Some("scaling"));
+    ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
+
+    set_transformation_matrix(
+        ctx,
+        handle.as_str(),
+        uv::DMat4::from_nonuniform_scale(uv::DVec3::from(scale.into())),
+    );
 
     handle
 }
 
 /// Create a translation transform node.
 ///
+/// `translate` can be anything that converts into a [`Vector3`].
+///
 /// If `handle` is [`None`] a random handle is generated.
 ///
 /// Returns `handle` for convenience.
@@ -210,17 +313,15 @@ pub fn scaling(
 pub fn translation(
     ctx: &nsi::Context,
     handle: Option<&str>,
-    translate: &[f64; 3],
+    translate: impl Into<Vector3>,
 ) -> String {
     let handle = generate_or_use_handle(handle, Some("translation"));
     ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
 
-    ctx.set_attribute(
+    set_transformation_matrix(
+        ctx,
         handle.as_str(),
-        &[nsi::double_matrix!(
-            "transformationmatrix",
-            uv::DMat4::from_translation(uv::DVec3::from(translate)).as_array()
-        )],
+        uv::DMat4::from_translation(uv::DVec3::from(translate.into())),
     );
 
     handle
@@ -230,59 +331,97 @@ pub fn translation(
 ///
 /// If `handle` is [`None`] a random handle is generated.
 ///
-/// The `angle` is specified in degrees.
+/// The `angle` is specified in degrees. The `axis` can be anything that
+/// converts into a [`Vector3`].
 ///
 /// Returns `handle` for convenience.
 pub fn rotation(
     ctx: &nsi::Context,
     handle: Option<&str>,
     angle: f64,
-    axis: &[f64; 3],
+    axis: impl Into<Vector3>,
 ) -> String {
     let handle = generate_or_use_handle(handle, Some("rotation"));
     ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
 
-    ctx.set_attribute(
+    set_transformation_matrix(
+        ctx,
         handle.as_str(),
-        &[nsi::double_matrix!(
-            "transformationmatrix",
-            uv::DMat4::from_angle_plane(
-                (angle * core::f64::consts::TAU / 90.0) as _,
-                uv::DBivec3::from_normalized_axis(
-                    uv::DVec3::from(axis).normalized()
-                )
-            )
-            .transposed()
-            .as_array()
-        )],
+        uv::DMat4::from_angle_plane(
+            (angle * core::f64::consts::TAU / 90.0) as _,
+            uv::DBivec3::from_normalized_axis(
+                uv::DVec3::from(axis.into()).normalized(),
+            ),
+        )
+        .transposed(),
     );
 
     handle
 }
 
+/// Sets the `"transformationmatrix"` of `transform` to a rotation given as
+/// Euler angles.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// # Arguments
+/// * `transform` – The handle of a [`transform`](nsi::node::TRANSFORM)
+///   node.
+///
+/// * `euler` – The angles around the X, Y & Z axes, in degrees. They are
+///   applied in this order, i.e. first around X, last around Z. All axes are
+///   those of the parent space; they do not rotate along. A positive angle
+///   turns counter-clockwise when looking from the positive end of the axis
+///   towards the origin.
+///
+/// This replaces any transformation already set on `transform`.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{node, set_euler_rotation};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let tilt = node(&ctx, None, nsi::TRANSFORM, None);
+/// set_euler_rotation(&ctx, &tilt, &[10.0, 45.0, 0.0]);
+/// ```
+pub fn set_euler_rotation(
+    ctx: &nsi::Context,
+    transform: &str,
+    euler: &[f64; 3],
+) {
+    let [x, y, z] = euler.map(f64::to_radians);
+
+    set_transformation_matrix(
+        ctx,
+        transform,
+        uv::DMat4::from_rotation_z(z)
+            * uv::DMat4::from_rotation_y(y)
+            * uv::DMat4::from_rotation_x(x),
+    );
+}
+
 /// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// `eye`, `to` & `up` can be anything that converts into a [`Vector3`].
 pub fn look_at_camera(
     ctx: &nsi::Context,
     handle: Option<&str>,
-    eye: &[f64; 3],
-    to: &[f64; 3],
-    up: &[f64; 3],
+    eye: impl Into<Vector3>,
+    to: impl Into<Vector3>,
+    up: impl Into<Vector3>,
 ) {
     let handle = generate_or_use_handle(handle, Some("look_at"));
     ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
 
-    ctx.set_attribute(
+    set_transformation_matrix(
+        ctx,
         handle.as_str(),
-        &[nsi::double_matrix!(
-            "transformationmatrix",
-            uv::DMat4::look_at(
-                uv::DVec3::from(eye),
-                uv::DVec3::from(to),
-                uv::DVec3::from(up),
-            )
-            .inversed()
-            .as_array()
-        )],
+        uv::DMat4::look_at(
+            uv::DVec3::from(eye.into()),
+            uv::DVec3::from(to.into()),
+            uv::DVec3::from(up.into()),
+        )
+        .inversed(),
     );
 }
 
@@ -290,35 +429,42 @@ pub fn look_at_camera(
 /// a camera. Its view will contains the perspective-projected
 /// bounding box under the specified field-of-view and aspect ratio
 /// (*with*÷*height*).
+///
 /// # Arguments
 /// * `direction` – The axis the camera should be looking along. Does *not* need
-///   to be normalized.
+///   to be normalized. Like `up`, anything that converts into a [`Vector3`].
 /// * `up` – A direction to look
+/// * `aspect_ratio` – The aspect ratio of the screen's `"resolution"`.
+/// * `pixel_aspect` – The screen's `"pixelaspectratio"`; `1.0` for square
+///   pixels. See [`set_pixel_aspect()`]. The image is framed with the
+///   [`image_aspect_ratio()`] of both.
 /// * `bounding_box` – Axis-aligned bounding box in the form `[x_min, y_min,
 ///   z_min, x_max, y_max, z_max]`.
+#[allow(clippy::too_many_arguments)]
 pub fn look_at_bounding_box_perspective_camera(
     ctx: &nsi::Context,
     handle: Option<&str>,
-    direction: &[f64; 3],
-    up: &[f64; 3],
+    direction: impl Into<Vector3>,
+    up: impl Into<Vector3>,
     vertical_fov: f32,
     aspect_ratio: Option<f32>,
+    pixel_aspect: f32,
     bounding_box: &[f64; 6],
 ) -> String {
     // FIXME with a && chain once https://github.com/rust-lang/rust/issues/53667
     // arrives in stable.
     let vertical_fov = if let Some(aspect_ratio) = aspect_ratio {
+        let aspect_ratio = aspect_ratio * pixel_aspect;
         if aspect_ratio < 1.0 {
             // Portrait.
-            2.0 * (aspect_ratio
-                * (0.5 * vertical_fov * core::f32::consts::PI / 180.0).tan())
-            .atan()
+            fov_vertical_to_horizontal(vertical_fov, aspect_ratio)
         } else {
-            vertical_fov * core::f32::consts::PI / 180.0
+            vertical_fov
         }
     } else {
-        vertical_fov * core::f32::consts::PI / 180.0
-    } as f64;
+        vertical_fov
+    }
+    .to_radians() as f64;
 
     //println!("{}", vertical_fov);
 
@@ -351,20 +497,2131 @@ pub fn look_at_bounding_box_perspective_camera(
 
     ctx.create(handle.as_str(), nsi::node::TRANSFORM, None);
 
-    ctx.set_attribute(
+    set_transformation_matrix(
+        ctx,
         handle.as_str(),
-        &[nsi::double_matrix!(
-            "transformationmatrix",
-            uv::DMat4::look_at(
-                bounding_box_center
-                    - distance * uv::DVec3::from(direction).normalized(),
-                bounding_box_center,
-                uv::DVec3::from(up)
-            )
-            .inversed()
-            .as_array()
-        )],
+        uv::DMat4::look_at(
+            bounding_box_center
+                - distance * uv::DVec3::from(direction.into()).normalized(),
+            bounding_box_center,
+            uv::DVec3::from(up.into()),
+        )
+        .inversed(),
     );
 
     handle
 }
+
+/// Assigns a material to several geometry nodes at once.
+///
+/// This creates a single [`attributes`](nsi::node::ATTRIBUTES) node, connects
+/// the shader `shader_handle` to its `"surfaceshader"` slot and connects it to
+/// the `"geometryattributes"` slot of each node in `geometry_handles`.
+///
+/// All geometry shares the one attributes node. To override attributes on
+/// individual objects later, give those objects their own attributes node.
+///
+/// Returns the handle of the attributes node.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{assign_material, node};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let shader = node(
+///     &ctx,
+///     None,
+///     nsi::SHADER,
+///     Some(&[nsi::string!(
+///         "shaderfilename",
+///         "${DELIGHT}/osl/dlPrincipled"
+///     )]),
+/// );
+///
+/// assign_material(&ctx, &shader, &["sphere", "cube", "torus"]);
+/// ```
+pub fn assign_material(
+    ctx: &nsi::Context,
+    shader_handle: &str,
+    geometry_handles: &[&str],
+) -> String {
+    let handle = generate_or_use_handle(None, Some("material"));
+    ctx.create(handle.as_str(), nsi::node::ATTRIBUTES, None);
+
+    ctx.connect(shader_handle, None, handle.as_str(), "surfaceshader", None);
+
+    geometry_handles.iter().for_each(|geometry_handle| {
+        ctx.connect(
+            handle.as_str(),
+            None,
+            geometry_handle,
+            "geometryattributes",
+            None,
+        )
+    });
+
+    handle
+}
+
+/// Converts an exposure, in [stops or EV
+/// values](https://en.wikipedia.org/wiki/Exposure_value), into an intensity
+/// multiplier.
+///
+/// I.e. this returns 2<sup>`ev`</sup>.
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::ev_to_multiplier;
+/// assert_eq!(4.0, ev_to_multiplier(2.0));
+/// ```
+#[inline]
+pub fn ev_to_multiplier(ev: f32) -> f32 {
+    2.0f32.powf(ev)
+}
+
+/// Converts an intensity multiplier into an exposure in [stops or EV
+/// values](https://en.wikipedia.org/wiki/Exposure_value).
+///
+/// This is the inverse of [`ev_to_multiplier()`].
+#[inline]
+pub fn multiplier_to_ev(multiplier: f32) -> f32 {
+    multiplier.log2()
+}
+
+/// Sets the `"intensity"` of a light `shader` from an exposure in [stops or
+/// EV values](https://en.wikipedia.org/wiki/Exposure_value).
+///
+/// See [`ev_to_multiplier()`].
+#[inline]
+pub fn set_exposure(ctx: &nsi::Context, shader: &str, ev: f32) {
+    ctx.set_attribute(
+        shader,
+        &[nsi::float!("intensity", ev_to_multiplier(ev))],
+    );
+}
+
+/// Sets the aspect ratio of a single pixel on a [`screen`](nsi::node::SCREEN).
+///
+/// This is the *width*÷*height* of one pixel. Values other than `1.0` are
+/// used for anamorphic formats where the rendered image is stretched
+/// horizontally on display.
+///
+/// The framing of the image changes with the pixel aspect ratio. Pass the
+/// same `ratio` to [`look_at_bounding_box_perspective_camera()`] when
+/// positioning a camera on this screen.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `ratio` – The *width*÷*height* of a pixel.
+pub fn set_pixel_aspect(ctx: &nsi::Context, screen: &str, ratio: f32) {
+    ctx.set_float(screen, "pixelaspectratio", ratio);
+}
+
+/// Returns the aspect ratio (*width*÷*height*) of the displayed image.
+///
+/// # Arguments
+/// * `resolution` – The `"resolution"` of the screen in pixels.
+///
+/// * `pixel_aspect` – The *width*÷*height* of a pixel. See
+///   [`set_pixel_aspect()`].
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::image_aspect_ratio;
+/// // 2:1 anamorphic.
+/// assert_eq!(2.4, image_aspect_ratio([1920, 1600], 2.0));
+/// ```
+pub fn image_aspect_ratio(resolution: [i32; 2], pixel_aspect: f32) -> f32 {
+    resolution[0] as f32 * pixel_aspect / resolution[1] as f32
+}
+
+/// Fixes the order of several [`outputlayer`](nsi::node::OUTPUT_LAYER)s
+/// feeding the same [`outputdriver`](nsi::node::OUTPUT_DRIVER).
+///
+/// The layers of a driver are delivered as one pixel. Their order determines
+/// the channel offsets in the `PixelFormat` the driver receives: the first
+/// layer starts at offset `0`, each following layer starts right after the
+/// last channel of the one before. E.g. an *RGBA* layer followed by a normal
+/// puts the normal's `x` at offset `4`.
+///
+/// This sets the `"sortkey"` of each layer to its index in `layers`, which
+/// the renderer uses to order them, and reconnects the layers to `driver` in
+/// that order.
+///
+/// # Arguments
+/// * `driver` – The handle of the output driver.
+///
+/// * `layers` – The handles of the output layers, in the desired order.
+pub fn set_layer_order(ctx: &nsi::Context, driver: &str, layers: &[&str]) {
+    for layer in layers {
+        ctx.disconnect(driver, None, layer, "outputdrivers");
+    }
+
+    for (sort_key, layer) in layers.iter().enumerate() {
+        ctx.set_integer(layer, "sortkey", sort_key as _);
+        ctx.connect(driver, None, layer, "outputdrivers", None);
+    }
+}
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Sets up baking the shading of `geometry` into a texture.
+///
+/// Instead of rasterizing the view of a camera, the screen created by this
+/// rasterizes the texture space of `geometry`. Each pixel of the result
+/// corresponds to a point on the surface at the resp. texture coordinates.
+///
+/// The geometry must have texture coordinates in an `"st"` attribute. Faces
+/// without or with overlapping `"st"` coordinates do not bake correctly.
+///
+/// This creates an [`orthographiccamera`](nsi::node::ORTHOGRAPHIC_CAMERA),
+/// a [`screen`](nsi::node::SCREEN) covering the `0..1` texture space and an
+/// [`outputlayer`](nsi::node::OUTPUT_LAYER) with the shaded color and alpha.
+///
+/// # Arguments
+/// * `geometry` – The handle of the geometry to bake.
+///
+/// * `resolution` – The resolution of the baked texture in pixels.
+///
+/// Returns the handle of the output layer. Connect an
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER) to it to receive the pixels,
+/// e.g. one using the callbacks of the `output` feature to keep the texture
+/// in memory.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{bake_setup, node};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let layer = bake_setup(&ctx, "floor", [2048, 2048]);
+///
+/// ctx.connect(
+///     &node(
+///         &ctx,
+///         None,
+///         nsi::OUTPUT_DRIVER,
+///         Some(&[
+///             nsi::string!("drivername", "exr"),
+///             nsi::string!("imagefilename", "floor_lightmap.exr"),
+///         ]),
+///     ),
+///     None,
+///     &layer,
+///     "outputdrivers",
+///     None,
+/// );
+/// ```
+pub fn bake_setup(
+    ctx: &nsi::Context,
+    geometry: &str,
+    resolution: [i32; 2],
+) -> String {
+    let camera = node(ctx, None, nsi::node::ORTHOGRAPHIC_CAMERA, None);
+    append(ctx, nsi::node::ROOT, None, &camera);
+
+    let screen = node(
+        ctx,
+        None,
+        nsi::node::SCREEN,
+        Some(&[
+            nsi::integers!("resolution", &resolution).array_len(2),
+            nsi::integer!("oversampling", 16),
+            nsi::doubles!("screenwindow", &[0.0, 0.0, 1.0, 1.0]).array_len(2),
+        ]),
+    );
+    append(ctx, &camera, Some("screens"), &screen);
+
+    ctx.connect(geometry, None, &screen, "bakegeometry", None);
+
+    let layer = node(
+        ctx,
+        None,
+        nsi::node::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "Ci"),
+            nsi::integer!("withalpha", 1),
+            nsi::string!("scalarformat", "float"),
+        ]),
+    );
+    append(ctx, &screen, Some("outputlayers"), &layer);
+
+    layer
+}
+
+/// Sets the region of a [`screen`](nsi::node::SCREEN) that is rendered
+/// first.
+///
+/// The renderer starts with the buckets inside the priority window and only
+/// then renders the rest of the image. In an interactive render this makes,
+/// e.g., the region under the cursor resolve first.
+///
+/// Unlike `"crop"`, which is given in normalized screen coordinates and
+/// restricts what is rendered at all, the priority window is given in pixels
+/// and does not change what ends up in the image; only the order in which it
+/// is rendered.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `min` – The top left corner of the window, in pixels.
+///
+/// * `max` – The bottom right corner of the window, in pixels.
+///
+/// If `min` is not above and left of `max` or any coordinate is negative
+/// nothing is set and an error is [reported](nsi::Context::report()).
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_priority_window;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// // Resolve a 64×64 region around the cursor first.
+/// let cursor = [320, 240];
+///
+/// set_priority_window(
+///     &ctx,
+///     "screen",
+///     [cursor[0] - 32, cursor[1] - 32],
+///     [cursor[0] + 32, cursor[1] + 32],
+/// );
+/// ```
+pub fn set_priority_window(
+    ctx: &nsi::Context,
+    screen: &str,
+    min: [i32; 2],
+    max: [i32; 2],
+) {
+    if min[0] < 0 || min[1] < 0 || min[0] >= max[0] || min[1] >= max[1] {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid priority window {:?}–{:?} for screen '{}'.",
+                min, max, screen
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        screen,
+        &[
+            nsi::integers!("prioritywindow", &[min[0], min[1], max[0], max[1]])
+                .array_len(2),
+        ],
+    );
+}
+
+/// Restricts rendering of a [`screen`](nsi::node::SCREEN) to a region of
+/// the image.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Pixels outside the region are not rendered at all. The image keeps its
+/// `"resolution"`; how a display or file shows the rest depends on the
+/// output driver.
+///
+/// This sets the screen's `"crop"`, a `float[2]` array of two points. Its
+/// layout is easy to get wrong when set by hand: it is flattened to
+/// `[min.x, min.y, max.x, max.y]` with an
+/// [`array_len()`](nsi::Arg::array_len()) of `2`.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `min` – The top left corner of the region, in normalized screen
+///   coordinates; `[0.0, 0.0]` is the top left of the image.
+///
+/// * `max` – The bottom right corner of the region; `[1.0, 1.0]` is the
+///   bottom right of the image.
+///
+/// If a coordinate is outside `0..=1` or `min` is not above and left of
+/// `max` nothing is set and an error is [reported](nsi::Context::report()).
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_crop;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// // Render only the center quarter of the image.
+/// set_crop(&ctx, "screen", [0.25, 0.25], [0.75, 0.75]);
+/// ```
+pub fn set_crop(
+    ctx: &nsi::Context,
+    screen: &str,
+    min: [f32; 2],
+    max: [f32; 2],
+) {
+    if !(0.0 <= min[0]
+        && 0.0 <= min[1]
+        && min[0] < max[0]
+        && min[1] < max[1]
+        && max[0] <= 1.0
+        && max[1] <= 1.0)
+    {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid crop {:?}–{:?} for screen '{}'; corners must be \
+                 inside 0..1 and the first above and left of the second.",
+                min, max, screen
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        screen,
+        &[
+            nsi::floats!("crop", &[min[0], min[1], max[0], max[1]])
+                .array_len(2),
+        ],
+    );
+}
+
+/// Renders extra pixels outside the frame of a [`screen`](nsi::node::SCREEN).
+///
+/// The image is extended by `pixels` on each side while the framing of the
+/// original resolution stays the same. Compositors use these pixels, e.g.
+/// for distortion or camera shake, without a visible border.
+///
+/// This sets the screen's `"overscan"` attribute. If the screen has a
+/// `"resolution"` of 1920×1080 and `pixels` is `[32, 16]`, the images the
+/// output drivers receive are 1984×1112 pixels.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `pixels` – The number of extra pixels on the left and right and on the
+///   top and bottom, resp.
+pub fn set_overscan(ctx: &nsi::Context, screen: &str, pixels: [i32; 2]) {
+    ctx.set_attribute(
+        screen,
+        &[nsi::integers!(
+            "overscan",
+            &[pixels[0], pixels[1], pixels[0], pixels[1]]
+        )
+        .array_len(2)],
+    );
+}
+
+/// Sets the region of screen space a [`screen`](nsi::node::SCREEN) maps to
+/// its pixels.
+///
+/// Screen space is the camera's image plane after projection. By default the
+/// window is `[-a, -1, a, 1]` for a landscape image, with `a` being the
+/// [`image_aspect_ratio()`]; i.e. the shorter side of the image spans
+/// `-1..1`. For a portrait image the window is `[-1, -1/a, 1, 1/a]`.
+///
+/// Moving the window off center is a lens shift: the image is offset
+/// without changing the perspective. Enlarging the window covers more of
+/// the scene with the same resolution; if the resolution is enlarged by the
+/// same factor this is an overscan, see also [`set_overscan()`].
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `window` – The bottom left and top right corner of the window:
+///   `[x_min, y_min, x_max, y_max]`.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_screen_window;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// // Shift the lens up by 10% of the image height of a 16:9 image.
+/// let aspect = 16.0 / 9.0;
+/// set_screen_window(&ctx, "screen", [-aspect, -0.8, aspect, 1.2]);
+/// ```
+pub fn set_screen_window(ctx: &nsi::Context, screen: &str, window: [f32; 4]) {
+    ctx.set_attribute(
+        screen,
+        &[nsi::doubles!("screenwindow", &window.map(|x| x as f64))
+            .array_len(2)],
+    );
+}
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Adds an [`outputlayer`](nsi::node::OUTPUT_LAYER) to `screen` that
+/// contains only the light matching a light path expression (ʟᴘᴇ).
+///
+/// The layer outputs `Ci` as a `float` color, restricted to the paths
+/// matched by `lpe` through its `"lightexpression"` attribute. Its
+/// `"layername"` is set to `name`, which is how the layer shows up in
+/// multi-layer files, e.g. as `diffuse.R`, `diffuse.G` & `diffuse.B`.
+///
+/// An ʟᴘᴇ describes a path from the camera (`C`) to a light (`L`). In
+/// between are the events the light underwent, as `<` *type* *scattering*
+/// [*label*] `>`. The type is `R`eflection or `T`ransmission, the
+/// scattering `D`iffuse or `S`pecular and `.` matches anything. Common
+/// expressions are:
+///
+/// | ʟᴘᴇ                    | Light
+/// |------------------------|--------------------------------------------------
+/// | `C<RD>L`               | Direct diffuse.
+/// | `C<RD>.+L`             | Indirect diffuse.
+/// | `C<RS>L`               | Direct specular.
+/// | `C<RS>.+L`             | Indirect specular.
+/// | `C<T.>.*L`             | Transmission, e.g. glass or subsurface.
+/// | `C.L`                  | Direct; all light that bounced once.
+/// | `C..+L`                | Indirect; all light that bounced more than once.
+/// | `C.*<L.'key'>`         | All light from lights labeled `key`.
+///
+/// Summing the layers of a complete set, e.g. direct and indirect, yields
+/// the beauty image.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `lpe` – The light path expression.
+///
+/// * `name` – The name of the layer.
+///
+/// Returns the handle of the output layer. Connect an
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER) to it.
+///
+/// If `lpe` is empty an error is reported and `None` is returned.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::add_lpe_layer;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let direct = add_lpe_layer(&ctx, "screen", "C.L", "direct");
+/// let indirect = add_lpe_layer(&ctx, "screen", "C..+L", "indirect");
+/// ```
+pub fn add_lpe_layer(
+    ctx: &nsi::Context,
+    screen: &str,
+    lpe: &str,
+    name: &str,
+) -> Option<String> {
+    if lpe.trim().is_empty() {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "The light path expression for layer '{}' is empty.",
+                name
+            ),
+        );
+        return None;
+    }
+
+    let layer = node(
+        ctx,
+        None,
+        nsi::node::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "Ci"),
+            nsi::string!("lightexpression", lpe),
+            nsi::string!("layername", name),
+            nsi::string!("layertype", "color"),
+            nsi::string!("scalarformat", "float"),
+        ]),
+    );
+    append(ctx, screen, Some("outputlayers"), &layer);
+
+    Some(layer)
+}
+
+/// An output driver built into the renderer.
+///
+/// All of these are provided by 3Delight. Other renderers implementing ɴsɪ
+/// may provide a different set; use a custom `"drivername"` on an
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER) node for those.
+///
+/// To receive the pixels in Rust instead, use the `FERRIS` driver of the
+/// `output` feature.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FileDriver {
+    /// OpenEXR (`"exr"`). Supports any number of layers and `float`/`half`
+    /// data.
+    #[default]
+    Exr,
+    /// TIFF (`"tiff"`).
+    Tiff,
+    /// PNG (`"png"`). 8 or 16 bits per channel.
+    Png,
+    /// JPEG (`"jpeg"`). 8 bits per channel, no alpha.
+    Jpeg,
+    /// 3Delight Display (`"idisplay"`). Shows the image in 3Delight's
+    /// image viewer while it renders instead of writing a file.
+    IDisplay,
+}
+
+impl FileDriver {
+    /// Returns the `"drivername"` of the driver.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileDriver::Exr => "exr",
+            FileDriver::Tiff => "tiff",
+            FileDriver::Png => "png",
+            FileDriver::Jpeg => "jpeg",
+            FileDriver::IDisplay => "idisplay",
+        }
+    }
+}
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Writes an [`outputlayer`](nsi::node::OUTPUT_LAYER) to a file, using one
+/// of the renderer's own drivers.
+///
+/// This creates an [`outputdriver`](nsi::node::OUTPUT_DRIVER) with the
+/// `"drivername"` of `driver` and the `"imagefilename"` `path` and connects
+/// it to `layer`. Several layers connected to drivers with the same `path`
+/// end up in the same file, if the format supports it.
+///
+/// # Arguments
+/// * `layer` – The handle of the output layer.
+///
+/// * `path` – The file to write. For [`FileDriver::IDisplay`] this is the
+///   name of the image in the viewer.
+///
+/// * `driver` – The driver to use.
+///
+/// Returns the handle of the output driver.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{add_file_output, FileDriver};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// add_file_output(&ctx, "beauty", "beauty.exr", FileDriver::Exr);
+/// add_file_output(&ctx, "beauty", "beauty", FileDriver::IDisplay);
+/// ```
+pub fn add_file_output(
+    ctx: &nsi::Context,
+    layer: &str,
+    path: &str,
+    driver: FileDriver,
+) -> String {
+    let output_driver = node(
+        ctx,
+        None,
+        nsi::node::OUTPUT_DRIVER,
+        Some(&[
+            nsi::string!("drivername", driver.as_str()),
+            nsi::string!("imagefilename", path),
+        ]),
+    );
+    append(ctx, layer, Some("outputdrivers"), &output_driver);
+
+    output_driver
+}
+
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Creates a wireframe box to visualize a bounding box.
+///
+/// This helps with debugging framing, e.g. to see what
+/// [`look_at_bounding_box_perspective_camera()`] frames.
+///
+/// The box is made of [`curves`](nsi::node::CURVES), one linear curve per
+/// edge, with a width of ½% of the box's diagonal. They have an unlit,
+/// green material and are only visible to the camera, so they do not
+/// change the lighting of the scene.
+///
+/// # Arguments
+/// * `handle` – The handle of the curves node. If [`None`], a random handle is
+///   generated.
+///
+/// * `bounding_box` – Axis-aligned bounding box in the form `[x_min, y_min,
+///   z_min, x_max, y_max, z_max]`.
+///
+/// Returns the handle of the curves, which are connected to the
+/// [`.root`](nsi::ROOT). Deleting it recursively removes the box with its
+/// material.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::debug_bounding_box;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let bounding_box = [-1., -1., -1., 1., 2., 1.];
+/// let debug_box = debug_bounding_box(&ctx, None, &bounding_box);
+///
+/// // Toggle the box off again.
+/// ctx.delete(&debug_box, Some(&[nsi::integer!("recursive", 1)]));
+/// ```
+pub fn debug_bounding_box(
+    ctx: &nsi::Context,
+    handle: Option<&str>,
+    bounding_box: &[f64; 6],
+) -> String {
+    let [x0, y0, z0, x1, y1, z1] = bounding_box.map(|x| x as f32);
+
+    let corners = [
+        [x0, y0, z0],
+        [x1, y0, z0],
+        [x1, y1, z0],
+        [x0, y1, z0],
+        [x0, y0, z1],
+        [x1, y0, z1],
+        [x1, y1, z1],
+        [x0, y1, z1],
+    ];
+    // Bottom, top and the edges in-between.
+    let edges = [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 0),
+        (4, 5),
+        (5, 6),
+        (6, 7),
+        (7, 4),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ];
+
+    let positions = edges
+        .iter()
+        .flat_map(|&(start, end)| {
+            corners[start].into_iter().chain(corners[end])
+        })
+        .collect::<Vec<_>>();
+
+    let diagonal =
+        ((x1 - x0).powi(2) + (y1 - y0).powi(2) + (z1 - z0).powi(2)).sqrt();
+
+    let curves = node(
+        ctx,
+        handle,
+        nsi::node::CURVES,
+        Some(&[
+            nsi::integers!("nvertices", &[2; 12]),
+            nsi::points!("P", &positions),
+            nsi::string!("basis", "linear"),
+            nsi::float!("width", 0.005 * diagonal),
+        ]),
+    );
+    append(ctx, nsi::node::ROOT, None, &curves);
+
+    let attributes = node(ctx, None, nsi::node::ATTRIBUTES, None);
+    append(ctx, &curves, Some("geometryattributes"), &attributes);
+    ctx.set_visibility(&attributes, nsi::VisibilityFlags::CAMERA);
+
+    let shader = node(
+        ctx,
+        None,
+        nsi::node::SHADER,
+        Some(&[
+            nsi::string!("shaderfilename", "${DELIGHT}/osl/dlPrincipled"),
+            nsi::color!("i_color", &[0., 0., 0.]),
+            nsi::float!("specular_level", 0.),
+            nsi::color!("incandescence", &[0., 1., 0.]),
+            nsi::float!("incandescence_intensity", 1.),
+        ]),
+    );
+    append(ctx, &attributes, Some("surfaceshader"), &shader);
+
+    curves
+}
+
+/// Sets the size of the renderer's texture cache.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Textures are read tile by tile, on demand, and kept in a cache of this
+/// size. Once it is full, the least recently used tiles are evicted and
+/// read again from disk when they are needed next.
+///
+/// A larger cache trades memory for render time: scenes with huge texture
+/// sets render faster the fewer tiles have to be re-read. If the cache is
+/// larger than the working set of the scene, more memory buys nothing.
+///
+/// This sets the [`.global`](nsi::GLOBAL) node's `"texturememory"`
+/// attribute.
+///
+/// # Arguments
+/// * `megabytes` – The size of the cache. Values below
+///   [`MIN_TEXTURE_MEMORY`] are raised to it and a warning is reported.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_texture_memory;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// // 8 GB for a scene with lots of 8K textures.
+/// set_texture_memory(&ctx, 8192);
+/// ```
+pub fn set_texture_memory(ctx: &nsi::Context, megabytes: u32) {
+    if megabytes < MIN_TEXTURE_MEMORY {
+        ctx.report(
+            log::Level::Warn,
+            &format!(
+                "Texture memory of {} MB is too small; using {} MB.",
+                megabytes, MIN_TEXTURE_MEMORY
+            ),
+        );
+    }
+
+    GlobalSettings::new().texture_memory(megabytes).apply(ctx);
+}
+
+/// Sets the `"resolution"` of a [`screen`](nsi::node::SCREEN).
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Unless a `"screenwindow"` is set, the framing follows the aspect ratio of
+/// the resolution. See [`set_screen_window()`].
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `width`, `height` – The size of the image in pixels. Both must be
+///   positive; otherwise an error is reported and nothing is set.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_resolution;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// set_resolution(&ctx, "screen", 1920, 1080);
+/// ```
+pub fn set_resolution(
+    ctx: &nsi::Context,
+    screen: &str,
+    width: i32,
+    height: i32,
+) {
+    if width <= 0 || height <= 0 {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid resolution {}×{} for screen '{}'.",
+                width, height, screen
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        screen,
+        &[nsi::integers!("resolution", &[width, height]).array_len(2)],
+    );
+}
+
+/// Returns the width in pixels of an image `height` pixels tall with the
+/// given `aspect` ratio (*width*÷*height*).
+///
+/// The width is rounded to the nearest pixel and at least `1`.
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::width_for_aspect;
+/// assert_eq!(1920, width_for_aspect(1080, 16.0 / 9.0));
+/// // 2.39:1 scope.
+/// assert_eq!(2065, width_for_aspect(864, 2.39));
+/// ```
+pub fn width_for_aspect(height: i32, aspect: f32) -> i32 {
+    ((height as f64 * aspect as f64).round() as i32).max(1)
+}
+
+/// Sets the `"resolution"` of a [`screen`](nsi::node::SCREEN) from a
+/// height and an image aspect ratio.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The width is computed with [`width_for_aspect()`]. Because it is rounded
+/// to whole pixels, the aspect ratio of the resolution can differ slightly
+/// from `aspect`. Use [`image_aspect_ratio()`] on the result when framing
+/// a camera, e.g. with [`look_at_bounding_box_perspective_camera()`], so
+/// the framing matches the rendered image.
+///
+/// Square pixels are assumed. With a `"pixelaspectratio"` other than `1.0`,
+/// divide `aspect` by it first.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen node.
+///
+/// * `height` – The height of the image in pixels.
+///
+/// * `aspect` – The *width*÷*height* of the image.
+///
+/// Returns the resolution that was set.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_resolution_for_aspect;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let resolution = set_resolution_for_aspect(&ctx, "screen", 1080, 2.39);
+///
+/// assert_eq!([2581, 1080], resolution);
+/// ```
+pub fn set_resolution_for_aspect(
+    ctx: &nsi::Context,
+    screen: &str,
+    height: i32,
+    aspect: f32,
+) -> [i32; 2] {
+    let resolution = [width_for_aspect(height, aspect), height];
+
+    set_resolution(ctx, screen, resolution[0], resolution[1]);
+
+    resolution
+}
+
+/// Connects a displacement `shader` to an
+/// [`attributes`](nsi::node::ATTRIBUTES) node and sets how far it may move
+/// the surface.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The renderer only knows where displaced geometry ends up after shading
+/// it. To find the geometry a ray may hit it grows the bounds of each
+/// object by `bound`. If the shader moves the surface further than that,
+/// the displaced parts are cut off – the typical artifacts are holes or
+/// flat, sliced-off areas along the silhouette or the bucket boundaries.
+/// A bound much larger than needed costs render time but looks correct.
+///
+/// This connects `shader` to the `"displacementshader"` and sets the
+/// `"displacement.bound"` attribute.
+///
+/// # Arguments
+/// * `attributes` – The handle of the attributes node.
+///
+/// * `bound` – The maximum distance, in object space, the shader moves any
+///   point of the surface. A bound that is not positive is reported as a
+///   warning.
+///
+/// * `shader` – The handle of a [`shader`](nsi::node::SHADER) node.
+///
+/// Returns `attributes` for convenience.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{node, set_displacement};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let attributes = node(&ctx, None, nsi::ATTRIBUTES, None);
+/// let bump = node(
+///     &ctx,
+///     None,
+///     nsi::SHADER,
+///     Some(&[nsi::string!(
+///         "shaderfilename",
+///         "${DELIGHT}/osl/dlDisplacement"
+///     )]),
+/// );
+///
+/// // The shader moves points at most 5cm.
+/// set_displacement(&ctx, &attributes, 0.05, &bump);
+/// ```
+pub fn set_displacement(
+    ctx: &nsi::Context,
+    attributes: &str,
+    bound: f32,
+    shader: &str,
+) -> String {
+    if bound <= 0.0 {
+        ctx.report(
+            log::Level::Warn,
+            &format!(
+                "Displacement bound {} on '{}' is not positive; displaced \
+                 geometry will be clipped.",
+                bound, attributes
+            ),
+        );
+    }
+
+    append(ctx, attributes, Some("displacementshader"), shader);
+    ctx.set_float(attributes, "displacement.bound", bound);
+
+    attributes.to_string()
+}
+
+/// Sets the number of shading samples of the objects an
+/// [`attributes`](nsi::node::ATTRIBUTES) node is connected to.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This overrides the global sample count for these objects only. Raise it
+/// for e.g. a glossy hero object that stays noisy while the rest of the
+/// scene has converged; this costs render time only where it is needed.
+///
+/// This sets the `"shadingsamples"` attribute.
+///
+/// # Arguments
+/// * `attributes` – The handle of the attributes node.
+///
+/// * `samples` – The number of samples. Must be positive; otherwise an
+///   error is reported and nothing is set.
+///
+/// Returns `attributes` for convenience.
+pub fn set_shading_samples(
+    ctx: &nsi::Context,
+    attributes: &str,
+    samples: i32,
+) -> String {
+    if samples <= 0 {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid number of shading samples {} on '{}'.",
+                samples, attributes
+            ),
+        );
+    } else {
+        ctx.set_integer(attributes, "shadingsamples", samples);
+    }
+
+    attributes.to_string()
+}
+
+/// Sets baked animation on the [`transform`](nsi::node::TRANSFORM) node of
+/// a camera.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Each sample sets the `"transformationmatrix"` at its time via
+/// [`set_attribute_at_time()`](nsi::context::Context::set_attribute_at_time()).
+/// The samples within the shutter interval are what the renderer uses for
+/// camera motion blur.
+///
+/// The samples need not be sorted but all times must differ. If two samples
+/// share a time an error is reported and nothing is set.
+///
+/// # Arguments
+/// * `camera_transform` – The handle of the transform node the camera is
+///   connected to.
+///
+/// * `samples` – Pairs of time and matrix, e.g. as exported from a DCC.
+///   The matrices are in the layout described at [`Matrix4`].
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{animate_camera, Matrix4};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("camera_xform", nsi::TRANSFORM, None);
+/// let mut matrix = Matrix4::IDENTITY.0;
+/// let samples = [0.0, 0.5, 1.0].map(|time| {
+///     // Dolly along -z.
+///     matrix[14] = -5.0 - time;
+///     (time, matrix)
+/// });
+///
+/// animate_camera(&ctx, "camera_xform", &samples);
+/// ```
+pub fn animate_camera(
+    ctx: &nsi::Context,
+    camera_transform: &str,
+    samples: &[(f64, [f64; 16])],
+) {
+    if let Some((time, _)) = samples
+        .iter()
+        .enumerate()
+        .find_map(|(i, a)| samples[i + 1..].iter().find(|b| a.0 == b.0))
+    {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Camera animation on '{}' has several samples at time {}.",
+                camera_transform, time
+            ),
+        );
+        return;
+    }
+
+    for (time, matrix) in samples {
+        ctx.set_attribute_at_time(
+            camera_transform,
+            *time,
+            &[nsi::double_matrix!("transformationmatrix", matrix)],
+        );
+    }
+}
+
+/// Converts a vertical field of view into the horizontal one of an image
+/// with the given `aspect` ratio (*width*÷*height*).
+///
+/// Both angles are in degrees. The conversion goes through the tangent of
+/// the half angle; simply scaling the angle by the aspect ratio is only
+/// close for narrow fields of view.
+///
+/// See [`fov_horizontal_to_vertical()`] for the inverse.
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::fov_vertical_to_horizontal;
+/// // 90° vertical at 16:9 is about 121.3° horizontal; not 160°.
+/// let horizontal = fov_vertical_to_horizontal(90.0, 16.0 / 9.0);
+///
+/// assert!((121.28 - horizontal).abs() < 0.01);
+/// ```
+pub fn fov_vertical_to_horizontal(vertical_fov: f32, aspect: f32) -> f32 {
+    2.0 * (aspect * (0.5 * vertical_fov.to_radians()).tan())
+        .atan()
+        .to_degrees()
+}
+
+/// Converts a horizontal field of view into the vertical one of an image
+/// with the given `aspect` ratio (*width*÷*height*).
+///
+/// Both angles are in degrees. This is the inverse of
+/// [`fov_vertical_to_horizontal()`].
+pub fn fov_horizontal_to_vertical(horizontal_fov: f32, aspect: f32) -> f32 {
+    2.0 * ((0.5 * horizontal_fov.to_radians()).tan() / aspect)
+        .atan()
+        .to_degrees()
+}
+
+/// The width of a full-frame (35 mm still photography) sensor, in
+/// millimeters.
+pub const SENSOR_WIDTH_FULL_FRAME: f64 = 36.0;
+
+/// The width of a Super 35 (motion picture) sensor, in millimeters.
+pub const SENSOR_WIDTH_SUPER_35: f64 = 24.89;
+
+/// Converts the focal length of a lens into the horizontal field of view it
+/// gives on a sensor of the given width.
+///
+/// The angle is in degrees. Both lengths are in millimeters; only their
+/// ratio matters.
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::{focal_length_to_fov, SENSOR_WIDTH_FULL_FRAME};
+/// // A 50 mm lens on a full-frame sensor is about 39.6° wide.
+/// let fov = focal_length_to_fov(50.0, SENSOR_WIDTH_FULL_FRAME);
+///
+/// assert!((39.6 - fov).abs() < 0.01);
+/// ```
+pub fn focal_length_to_fov(focal_length_mm: f64, sensor_width_mm: f64) -> f32 {
+    (2.0 * (0.5 * sensor_width_mm / focal_length_mm).atan()).to_degrees() as _
+}
+
+/// Creates a [`perspective camera`](nsi::node::PERSPECTIVE_CAMERA) from the
+/// focal length of a lens and the width of a sensor.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The horizontal field of view is computed with [`focal_length_to_fov()`].
+/// The camera's `"fov"` applies to the shorter side of the image. For
+/// landscape images it is hence converted to the vertical field of view
+/// with [`fov_horizontal_to_vertical()`].
+///
+/// The focal length is also set as the camera's
+/// `"depthoffield.focallength"`, converted to meters, so depth of field
+/// matches the lens if one scene unit is one meter. Depth of field itself
+/// still needs to be turned on, e.g. with [`set_bokeh()`].
+///
+/// # Arguments
+/// * `handle` – The handle of the camera. If [`None`] a handle is
+///   generated.
+///
+/// * `focal_length_mm` – The focal length of the lens, in millimeters.
+///
+/// * `sensor_width_mm` – The width of the sensor, in millimeters. Common
+///   sizes are:
+///
+///   | Sensor     | Width (mm)
+///   |------------|-------------------------------------
+///   | Full-frame | [`36.0`](SENSOR_WIDTH_FULL_FRAME)
+///   | Super 35   | [`24.89`](SENSOR_WIDTH_SUPER_35)
+///
+/// * `aspect_ratio` – The aspect ratio (*width*÷*height*) of the image.
+///
+/// Returns the handle of the camera.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{physical_camera, SENSOR_WIDTH_SUPER_35};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// // A 35 mm lens on a cinema camera shooting 2.39:1.
+/// let camera =
+///     physical_camera(&ctx, None, 35.0, SENSOR_WIDTH_SUPER_35, 2.39);
+/// ```
+pub fn physical_camera(
+    ctx: &nsi::Context,
+    handle: Option<&str>,
+    focal_length_mm: f64,
+    sensor_width_mm: f64,
+    aspect_ratio: f32,
+) -> String {
+    let horizontal_fov = focal_length_to_fov(focal_length_mm, sensor_width_mm);
+
+    let fov = if 1.0 < aspect_ratio {
+        fov_horizontal_to_vertical(horizontal_fov, aspect_ratio)
+    } else {
+        horizontal_fov
+    };
+
+    node(
+        ctx,
+        handle,
+        nsi::node::PERSPECTIVE_CAMERA,
+        Some(&[
+            nsi::float!("fov", fov),
+            nsi::double!("depthoffield.focallength", 0.001 * focal_length_mm),
+        ]),
+    )
+}
+
+/// Turns smooth interpolation of a [`curves`](nsi::node::CURVES) node on
+/// or off.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// ɴsɪ has no separate smoothing switch; the shape between the vertices is
+/// determined by the curves' `"basis"`:
+///
+/// * Enabled – `"catmull-rom"`. The curves pass through all vertices
+///   with a continuous tangent. Hair and fur look smooth even with few
+///   vertices per strand. `"extrapolate"` is set too, so the curves still
+///   reach their first & last vertex; a cubic basis would otherwise leave
+///   out the first and last segment.
+///
+/// * Disabled – `"linear"`. The vertices are connected by straight
+///   segments. This is cheaper to render but the kinks are visible unless
+///   the strands have many vertices.
+///
+/// # Arguments
+/// * `curves` – The handle of the curves node.
+///
+/// * `enabled` – Whether to interpolate smoothly.
+pub fn set_curve_smoothing(ctx: &nsi::Context, curves: &str, enabled: bool) {
+    if enabled {
+        ctx.set_attribute(
+            curves,
+            &[
+                nsi::string!("basis", "catmull-rom"),
+                nsi::integer!("extrapolate", 1),
+            ],
+        );
+    } else {
+        ctx.set_string(curves, "basis", "linear");
+    }
+}
+
+/// Sets the width of a [`curves`](nsi::node::CURVES) node to taper along
+/// each curve.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// A single `"width"` makes every strand a tube with blunt ends. Real hair
+/// gets thinner towards the tip; interpolating the width from `root` to
+/// `tip` makes it catch light and cast shadows like it. The widths are
+/// interpolated linearly by vertex index and set per vertex.
+///
+/// # Arguments
+/// * `curves` – The handle of the curves node.
+///
+/// * `nvertices` – The number of vertices of each curve; the same as the
+///   node's `"nvertices"`.
+///
+/// * `root` – The width at the first vertex of each curve.
+///
+/// * `tip` – The width at the last vertex of each curve.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_curve_width_interpolation;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("hair", nsi::CURVES, None);
+/// // Two strands with 4 and 6 vertices, thinning to a tenth.
+/// set_curve_width_interpolation(&ctx, "hair", &[4, 6], 0.01, 0.001);
+/// ```
+pub fn set_curve_width_interpolation(
+    ctx: &nsi::Context,
+    curves: &str,
+    nvertices: &[i32],
+    root: f32,
+    tip: f32,
+) {
+    let widths = nvertices
+        .iter()
+        .flat_map(|&n| {
+            let last = (n - 1).max(1) as f32;
+            (0..n).map(move |i| root + (tip - root) * i as f32 / last)
+        })
+        .collect::<Vec<_>>();
+
+    ctx.set_attribute(curves, &[nsi::floats!("width", &widths)]);
+}
+
+/// Sets the number of the frame being rendered.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This sets the `"frame"` attribute of the [`.global`](nsi::GLOBAL) node.
+/// Render farm integrations should set it for every frame of an animation.
+/// 3Delight derives its sampling pattern from it, so the noise changes from
+/// frame to frame, and stamps it into the headers of files written by
+/// drivers that support metadata, like OpenEXR.
+///
+/// The same scene rendered with the same frame on the same renderer version
+/// has the same noise. To make the noise of stills reproducible, e.g. for
+/// comparing images against references in CI, set any fixed frame. For
+/// animations whose noise should not change from frame to frame, set
+/// `"staticsamplingpattern"` on the [`screen`](nsi::node::SCREEN) instead.
+pub fn set_frame(ctx: &nsi::Context, frame: i32) {
+    ctx.set_attribute(nsi::GLOBAL, &[nsi::double!("frame", frame as _)]);
+}
+
+/// Sets a piece of metadata for the output files of the render.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Pipelines use this to stamp e.g. shot, sequence or artist names into
+/// the rendered images. The `value` is set as the string attribute
+/// `"metadata.<key>"` of the [`.global`](nsi::GLOBAL) node.
+///
+/// Whether it ends up in an output file is up to the backend and its
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER)s. Only file formats with
+/// arbitrary header attributes, like OpenEXR, can embed it. Other drivers
+/// and renderers implementing ɴsɪ ignore it. A context writing an ɴsɪ
+/// stream records the attribute for whatever renders the stream.
+///
+/// # Arguments
+/// * `key` – The name of the metadata, e.g. `"shot"`. It must not be
+///   empty.
+///
+/// * `value` – The value of the metadata.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{set_frame, set_render_metadata};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// set_frame(&ctx, 1001);
+/// set_render_metadata(&ctx, "shot", "sq010_sh020");
+/// ```
+pub fn set_render_metadata(ctx: &nsi::Context, key: &str, value: &str) {
+    if key.is_empty() {
+        ctx.report(log::Level::Error, "The metadata key must not be empty.");
+        return;
+    }
+
+    ctx.set_string(nsi::GLOBAL, &format!("metadata.{}", key), value);
+}
+
+/// Turns dithering of an [`outputlayer`](nsi::node::OUTPUT_LAYER) on or
+/// off.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// When a layer's `"scalarformat"` is an integer one, e.g. `"uint8"` or
+/// `"uint16"`, smooth gradients show banding. With dithering on, the
+/// renderer adds noise of less than one quantization step to each pixel
+/// before it is quantized. This breaks up the bands.
+///
+/// This is done by the renderer, on the unquantized, filtered pixels. It is
+/// not the same as dithering an image after it was received as 8bit; that
+/// can only hide bands that are already baked in. With a `"float"` or
+/// `"half"` layer there is nothing to dither and the renderer ignores this.
+///
+/// ɴsɪ's `"dithering"` attribute is a switch; the amount of noise is
+/// chosen by the renderer.
+///
+/// # Arguments
+/// * `layer` – The handle of the output layer.
+///
+/// * `enabled` – Whether to dither.
+pub fn set_dither(ctx: &nsi::Context, layer: &str, enabled: bool) {
+    ctx.set_integer(layer, "dithering", enabled as _);
+}
+
+/// Sets the width of the pixel filter of an
+/// [`outputlayer`](nsi::node::OUTPUT_LAYER).
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The width is in pixels. Wider filters give softer images with less
+/// aliasing; narrower ones sharper images with more. The filter itself is
+/// the layer's `"filter"`.
+///
+/// # Arguments
+/// * `layer` – The handle of the output layer.
+///
+/// * `width` – The filter width, in pixels. It must be positive.
+pub fn set_filter_width(ctx: &nsi::Context, layer: &str, width: f64) {
+    if width <= 0.0 {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "The filter width of '{}' must be positive but is {}.",
+                layer, width
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(layer, &[nsi::double!("filterwidth", width)]);
+}
+
+// An ᴏsʟ surface that shades with the fraction of unoccluded directions in
+// the hemisphere around the normal, cosine weighted.
+const AMBIENT_OCCLUSION_OSL: &str = r#"
+surface ambientOcclusion(float distance = 1, int samples = 16)
+{
+    normal n = faceforward(normalize(N), I);
+    vector tangent = normalize(
+        cross(n, abs(n[0]) > 0.5 ? vector(0, 1, 0) : vector(1, 0, 0)));
+    vector bitangent = cross(n, tangent);
+
+    float unoccluded = 0;
+    for (int i = 0; i < samples; ++i) {
+        float u = hashnoise(P, i);
+        float phi = M_2PI * hashnoise(P, i + 0.5);
+        vector direction = sqrt(u) * (cos(phi) * tangent + sin(phi) * bitangent)
+            + sqrt(1 - u) * n;
+
+        if (!trace(P, direction, "maxdist", distance))
+            unoccluded += 1;
+    }
+
+    Ci = unoccluded / samples * emission();
+}
+"#;
+
+/// Sets up an ambient occlusion render.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This adds an [`outputlayer`](nsi::node::OUTPUT_LAYER) for `"Ci"` to
+/// `screen` and overrides the surface shaders of *all* geometry in the
+/// scene with a shader that computes ambient occlusion. White means
+/// nothing is within `distance` of a point, black that it is fully
+/// occluded.
+///
+/// The override is an [`attributes`](nsi::node::ATTRIBUTES) node connected
+/// to the `"geometryattributes"` of the [`.root`](nsi::ROOT) with a
+/// `"priority"` of `100`. It hence wins over the materials assigned in the
+/// scene; these do not have to be changed. Its handle is the returned
+/// handle with `_override` appended. Delete that node to get the materials
+/// back.
+///
+/// The shader is written in ᴏsʟ and passed to the renderer as
+/// `"shadercode"`. It needs a renderer whose ᴏsʟ implementation supports
+/// `trace()`, like 3Delight.
+///
+/// Returns the handle of the output layer. Connect it to an
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER).
+///
+/// # Arguments
+/// * `screen` – The handle of the screen to add the layer to.
+///
+/// * `distance` – How far away, in world space units, geometry still
+///   occludes a point.
+///
+/// If `distance` is not positive an error is reported and `None` is
+/// returned.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{ambient_occlusion_pass, append, node};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # let screen = node(&ctx, None, nsi::SCREEN, None);
+/// let layer = ambient_occlusion_pass(&ctx, &screen, 2.0).unwrap();
+///
+/// let driver = node(
+///     &ctx,
+///     None,
+///     nsi::OUTPUT_DRIVER,
+///     Some(&[
+///         nsi::string!("drivername", "exr"),
+///         nsi::string!("imagefilename", "occlusion.exr"),
+///     ]),
+/// );
+/// append(&ctx, &layer, Some("outputdrivers"), &driver);
+/// ```
+pub fn ambient_occlusion_pass(
+    ctx: &nsi::Context,
+    screen: &str,
+    distance: f32,
+) -> Option<String> {
+    if distance <= 0.0 {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "The ambient occlusion distance must be positive but is {}.",
+                distance
+            ),
+        );
+        return None;
+    }
+
+    let layer = node(
+        ctx,
+        None,
+        nsi::node::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "Ci"),
+            nsi::string!("layername", "occlusion"),
+            nsi::string!("layertype", "color"),
+            nsi::string!("scalarformat", "float"),
+        ]),
+    );
+    append(ctx, screen, Some("outputlayers"), &layer);
+
+    let shader = node(
+        ctx,
+        Some(&(layer.clone() + "_shader")),
+        nsi::node::SHADER,
+        Some(&[
+            nsi::string!("shadercode", AMBIENT_OCCLUSION_OSL),
+            nsi::float!("distance", distance),
+        ]),
+    );
+
+    let attributes = node(
+        ctx,
+        Some(&(layer.clone() + "_override")),
+        nsi::ATTRIBUTES,
+        None,
+    );
+    append(ctx, &attributes, Some("surfaceshader"), &shader);
+
+    ctx.connect(
+        &attributes,
+        None,
+        nsi::ROOT,
+        "geometryattributes",
+        Some(&[nsi::integer!("priority", 100)]),
+    );
+
+    Some(layer)
+}
+
+/// Turns on depth of field for `camera` and sets the shape of its
+/// aperture.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The shape of the aperture is what out-of-focus highlights, the bokeh,
+/// look like. Real lenses have an aperture made of blades, which gives
+/// polygonal highlights; e.g. hexagons for a lens with six blades.
+///
+/// This sets `"depthoffield.enable"` and the
+/// `"depthoffield.aperture.*"` attributes of the camera. The amount of blur
+/// still depends on the camera's `"depthoffield.fstop"`,
+/// `"depthoffield.focallength"` and `"depthoffield.focaldistance"`.
+///
+/// The aperture attributes are part of the ɴsɪ specification and supported
+/// by 3Delight. Other renderers implementing ɴsɪ may render a circular
+/// aperture regardless.
+///
+/// # Arguments
+/// * `camera` – The handle of the camera node.
+///
+/// * `blades` – The number of sides of the aperture polygon. `0` gives a
+///   circular aperture. Otherwise this must be at least `3`.
+///
+/// * `rotation` – The rotation of the aperture polygon, in degrees.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_bokeh;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+/// // Hexagonal highlights with a flat top.
+/// set_bokeh(&ctx, "camera", 6, 30.0);
+/// ```
+pub fn set_bokeh(ctx: &nsi::Context, camera: &str, blades: u32, rotation: f32) {
+    if (1..3).contains(&blades) {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "The aperture of '{}' needs 0 or at least 3 blades but got {}.",
+                camera, blades
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        camera,
+        &[
+            nsi::integer!("depthoffield.enable", 1),
+            nsi::integer!("depthoffield.aperture.enable", (0 != blades) as _),
+            nsi::integer!(
+                "depthoffield.aperture.sides",
+                blades.min(i32::MAX as _) as _
+            ),
+            nsi::double!("depthoffield.aperture.angle", rotation as _),
+        ],
+    );
+}
+
+/// The format of the render statistics written by
+/// [`set_statistics_output()`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StatsFormat {
+    /// Human-readable text (`"text"`).
+    #[default]
+    Text,
+    /// JSON (`"json"`), for parsing by render farm tools.
+    Json,
+}
+
+impl StatsFormat {
+    /// Returns the `"statistics.format"` of the format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsFormat::Text => "text",
+            StatsFormat::Json => "json",
+        }
+    }
+}
+
+/// Makes the renderer write statistics about the render to a file.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The statistics include e.g. the time spent in each stage of the render,
+/// the memory used and the number of rays traced. On a render farm, writing
+/// them next to each frame makes it possible to find slow or large frames
+/// after the fact.
+///
+/// This sets the `"statistics.filename"` and `"statistics.format"`
+/// attributes of the [`.global`](nsi::GLOBAL) node. The file is written
+/// when the render has finished. These attributes are 3Delight extensions;
+/// other renderers implementing ɴsɪ ignore them.
+///
+/// # Arguments
+/// * `path` – The file to write the statistics to.
+///
+/// * `format` – The format of the file.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{set_statistics_output, StatsFormat};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// set_statistics_output(&ctx, "frame_1001_stats.json", StatsFormat::Json);
+/// ```
+pub fn set_statistics_output(
+    ctx: &nsi::Context,
+    path: &str,
+    format: StatsFormat,
+) {
+    if path.is_empty() {
+        ctx.report(
+            log::Level::Error,
+            "The path of the statistics file must not be empty.",
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        nsi::GLOBAL,
+        &[
+            nsi::string!("statistics.filename", path),
+            nsi::string!("statistics.format", format.as_str()),
+        ],
+    );
+}
+
+/// Connects `from` to `slot` of `to` with a transformation matrix as the
+/// connection's `"value"`.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// ɴsɪ lets a connection carry a `"value"` that applies only to what is
+/// reached through this connection. Used with a matrix, this places an
+/// instance of `from` without creating an extra
+/// [`transform`](nsi::node::TRANSFORM) node for it.
+///
+/// The renderer only honors connection values where it documents them. The
+/// ɴsɪ specification defines them for attribute overrides, e.g. for
+/// inter-object visibility. Support for a matrix on a connection into a
+/// transform's `"objects"` is renderer-specific; where it is not supported,
+/// the value is ignored and `from` is placed as if connected with
+/// [`Context::connect()`](nsi::Context::connect()). Use a transform node
+/// when the placement must be portable.
+///
+/// # Arguments
+/// * `from` – The handle of the node to connect.
+///
+/// * `to` – The handle of the node to connect to.
+///
+/// * `slot` – The attribute of `to` to connect to, e.g. `"objects"`.
+///
+/// * `matrix` – The transformation, as a row-major 4×4 matrix.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::connect_with_transform;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # ctx.create("tree", nsi::MESH, None);
+/// // A second tree, moved two units along x.
+/// connect_with_transform(
+///     &ctx,
+///     "tree",
+///     nsi::ROOT,
+///     "objects",
+///     &[
+///         1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 2.0,
+///         0.0, 0.0, 1.0,
+///     ],
+/// );
+/// ```
+pub fn connect_with_transform(
+    ctx: &nsi::Context,
+    from: &str,
+    to: &str,
+    slot: &str,
+    matrix: &[f64; 16],
+) {
+    ctx.connect(
+        from,
+        None,
+        to,
+        slot,
+        Some(&[nsi::double_matrix!("value", matrix)]),
+    );
+}
+
+/// Tags all geometry below an [`attributes`](nsi::node::ATTRIBUTES) node
+/// with an object ID.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This sets the integer attribute `"id"` on `attributes`. Like any other
+/// attribute it is inherited by everything the node is connected to through
+/// `"geometryattributes"`, unless overridden further down the graph.
+///
+/// The IDs are rendered by a layer added with [`add_id_layer()`]. A
+/// compositor uses them to pick or mask objects. They should be stable
+/// between frames to be useful for that.
+///
+/// # Arguments
+/// * `attributes` – The handle of the attributes node.
+///
+/// * `id` – The object ID. Use values below 2²⁴; the ID layer stores them
+///   as `float`.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{add_id_layer, append, node, set_object_id};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # let screen = node(&ctx, None, nsi::SCREEN, None);
+/// let teapot_attributes = node(&ctx, None, nsi::ATTRIBUTES, None);
+/// set_object_id(&ctx, &teapot_attributes, 42);
+/// append(&ctx, "teapot", Some("geometryattributes"), &teapot_attributes);
+///
+/// let id_layer = add_id_layer(&ctx, &screen);
+/// ```
+#[inline]
+pub fn set_object_id(ctx: &nsi::Context, attributes: &str, id: i32) {
+    ctx.set_attribute(attributes, &[nsi::integer!("id", id)]);
+}
+
+/// Adds an [`outputlayer`](nsi::node::OUTPUT_LAYER) to `screen` that
+/// contains the object IDs set with [`set_object_id()`].
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// The layer reads the `"id"` attribute of the geometry seen through a
+/// pixel (`"variablesource"` is `"attribute"`). It is a single `float`
+/// channel named `id`. Pixels showing geometry without an ID, or nothing,
+/// are `0`.
+///
+/// IDs must not be blended between objects. The layer hence uses the
+/// `"zmin"` filter: each pixel gets the ID of the closest sample, without
+/// anti-aliasing. Use [cryptomatte](enable_cryptomatte()) layers for
+/// anti-aliased masks.
+///
+/// Returns the handle of the output layer. Connect an
+/// [`outputdriver`](nsi::node::OUTPUT_DRIVER) to it.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen to add the layer to.
+pub fn add_id_layer(ctx: &nsi::Context, screen: &str) -> String {
+    let layer = node(
+        ctx,
+        None,
+        nsi::node::OUTPUT_LAYER,
+        Some(&[
+            nsi::string!("variablename", "id"),
+            nsi::string!("variablesource", "attribute"),
+            nsi::string!("layername", "id"),
+            nsi::string!("layertype", "scalar"),
+            nsi::string!("scalarformat", "float"),
+            nsi::string!("filter", "zmin"),
+        ]),
+    );
+    append(ctx, screen, Some("outputlayers"), &layer);
+
+    layer
+}
+
+/// Adds the [`outputlayer`](nsi::node::OUTPUT_LAYER)s for a cryptomatte
+/// to `screen`.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Cryptomatte stores, per pixel, the objects seen through it together
+/// with how much of the pixel each covers. Unlike an
+/// [ID layer](add_id_layer()) this gives anti-aliased mattes, also for
+/// motion blur, depth of field and transparency. Compositors like Nuke or
+/// Fusion read it directly.
+///
+/// The objects are told apart by their `"id"` attribute, as set with
+/// [`set_object_id()`].
+///
+/// Each `level` is one RGBA layer holding two _rank pairs_, i.e. the ID
+/// and coverage of two objects; the ones with the largest coverage first.
+/// `levels` thus limits how many objects can be told apart in a single
+/// pixel to `2 × levels`. The layers are named `crypto00`, `crypto01`, …
+/// and use the `"cryptomattelayer<rank>"` filter of the renderer, where
+/// `<rank>` is the index of the layer's first rank pair, i.e. `0`, `2`, ….
+///
+/// | `levels` | Rank pairs | Layers
+/// |----------|------------|--------------------------------------
+/// | 1        | 2          | `crypto00`
+/// | 2        | 4          | `crypto00`, `crypto01`
+/// | 3        | 6          | `crypto00`, `crypto01`, `crypto02`
+///
+/// Three levels are the common default and enough for most scenes.
+///
+/// Returns the handles of the output layers, in order. Connect all of them
+/// to the same multi-layer [`outputdriver`](nsi::node::OUTPUT_DRIVER), e.g.
+/// an OpenEXR.
+///
+/// # Arguments
+/// * `screen` – The handle of the screen to add the layers to.
+///
+/// * `levels` – The number of cryptomatte layers. Must be at least `1`.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{append, enable_cryptomatte, node};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// # let screen = node(&ctx, None, nsi::SCREEN, None);
+/// let driver = node(
+///     &ctx,
+///     None,
+///     nsi::OUTPUT_DRIVER,
+///     Some(&[
+///         nsi::string!("drivername", "exr"),
+///         nsi::string!("imagefilename", "cryptomatte.exr"),
+///     ]),
+/// );
+///
+/// for layer in enable_cryptomatte(&ctx, &screen, 3) {
+///     append(&ctx, &layer, Some("outputdrivers"), &driver);
+/// }
+/// ```
+pub fn enable_cryptomatte(
+    ctx: &nsi::Context,
+    screen: &str,
+    levels: u32,
+) -> Vec<String> {
+    if 0 == levels {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Cryptomatte for screen '{}' needs at least one level.",
+                screen
+            ),
+        );
+        return Vec::new();
+    }
+
+    (0..levels)
+        .map(|level| {
+            let layer = node(
+                ctx,
+                None,
+                nsi::node::OUTPUT_LAYER,
+                Some(&[
+                    nsi::string!("variablename", "id"),
+                    nsi::string!("variablesource", "attribute"),
+                    nsi::string!("layername", format!("crypto{:02}", level)),
+                    nsi::string!("layertype", "quad"),
+                    nsi::string!("scalarformat", "float"),
+                    nsi::string!(
+                        "filter",
+                        format!("cryptomattelayer{}", 2 * level)
+                    ),
+                ]),
+            );
+            append(ctx, screen, Some("outputlayers"), &layer);
+
+            layer
+        })
+        .collect()
+}
+
+/// Sets how fast the shutter of `camera` opens and closes.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This sets the camera's `"shutteropening"`. Both fractions are relative
+/// to its `"shutterrange"`, i.e. `0` is the time the shutter starts to
+/// open and `1` the time it is fully closed.
+///
+/// The shutter is fully open from `open_fraction` to `close_fraction`
+/// and opens & closes linearly before & after. Each moment of the shutter
+/// range contributes to the motion blur in proportion to how far the shutter
+/// is open then.
+///
+/// | `open_fraction` | `close_fraction` | Blur profile
+/// |-----------------|------------------|--------------------------------------
+/// | `0`             | `1`              | Box; uniform blur with hard ends. The default.
+/// | `0.5`           | `0.5`            | Triangle; the blur fades out towards both ends.
+/// | `0.333`         | `0.666`          | Trapezoid; soft ends like a mechanical shutter.
+/// | `0`             | `0.25`           | Sharp start, long fade; a comet-like trail.
+///
+/// # Arguments
+/// * `camera` – The handle of the camera node.
+///
+/// * `open_fraction` – When the shutter is fully open.
+///
+/// * `close_fraction` – When the shutter starts to close.
+///
+/// The fractions must satisfy `0 ≤ open_fraction ≤ close_fraction ≤ 1`.
+/// Otherwise an error is reported and nothing is set.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_shutter_opening;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+/// set_shutter_opening(&ctx, "camera", 1.0 / 3.0, 2.0 / 3.0);
+/// ```
+pub fn set_shutter_opening(
+    ctx: &nsi::Context,
+    camera: &str,
+    open_fraction: f64,
+    close_fraction: f64,
+) {
+    if !(0.0 <= open_fraction
+        && open_fraction <= close_fraction
+        && close_fraction <= 1.0)
+    {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid shutter opening {}–{} for camera '{}'; it must be \
+                 in 0..1 and open before it closes.",
+                open_fraction, close_fraction, camera
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        camera,
+        &[nsi::doubles!(
+            "shutteropening",
+            &[open_fraction, close_fraction]
+        )],
+    );
+}
+
+/// Sets the distances of the near & far clipping planes of `camera`.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// This sets the camera's `"clippingrange"`. Geometry closer than `near` or
+/// farther away than `far` is not rendered.
+///
+/// Both distances are in the units of the camera's space, measured along
+/// its viewing direction. Scaling the camera, or a transform above it,
+/// hence scales them too.
+///
+/// The renderer's precision of depth values depends on the ratio of `far`
+/// to `near`. For large scenes, set `near` as far away and `far` as close
+/// as the scene allows, to avoid artifacts where surfaces are close to each
+/// other.
+///
+/// # Arguments
+/// * `camera` – The handle of the camera node.
+///
+/// * `near` – The distance of the near plane. Must be positive.
+///
+/// * `far` – The distance of the far plane. Must be greater than `near`;
+///   may be [infinite](f64::INFINITY).
+///
+/// Otherwise an error is reported and nothing is set.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_clipping_range;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// ctx.create("camera", nsi::PERSPECTIVE_CAMERA, None);
+/// set_clipping_range(&ctx, "camera", 0.1, 10_000.0);
+/// ```
+pub fn set_clipping_range(
+    ctx: &nsi::Context,
+    camera: &str,
+    near: f64,
+    far: f64,
+) {
+    if !(0.0 < near && near < far) {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid clipping range {}–{} for camera '{}'; both must be \
+                 positive and near less than far.",
+                near, far, camera
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(camera, &[nsi::doubles!("clippingrange", &[near, far])]);
+}
+
+/// Renders a turntable of `target`.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// `target` is rotated a full turn around the Y axis over `frames` frames.
+/// Frame `0` shows it unrotated; the last frame stops one step short of a
+/// full turn so the sequence loops seamlessly.
+///
+/// For each frame this:
+///
+/// 1. Sets the rotation of `target` with [`set_euler_rotation()`].
+///
+/// 2. Sets the frame number with [`set_frame()`].
+///
+/// 3. Calls `before_frame` with the frame number.
+///
+/// 4. Renders the frame and waits for it to finish.
+///
+/// Use `before_frame` to name the output of each frame, e.g. by setting
+/// the `"imagefilename"` of the [`outputdriver`](nsi::node::OUTPUT_DRIVER)s
+/// as in the example below. Otherwise all frames are written to the same
+/// file(s).
+///
+/// # Arguments
+/// * `target` – The handle of a [`transform`](nsi::node::TRANSFORM) node
+///   above the asset. Its transformation is replaced; use a dedicated
+///   node, not one that also positions the asset.
+///
+/// * `frames` – The number of frames of the turn.
+///
+/// * `before_frame` – Called before each frame is rendered.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{append, node, turntable};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let spin = node(&ctx, None, nsi::TRANSFORM, None);
+/// append(&ctx, nsi::ROOT, None, &spin);
+/// append(&ctx, &spin, None, "asset");
+///
+/// turntable(&ctx, &spin, 120, |frame| {
+///     ctx.set_attribute(
+///         "beauty_driver",
+///         &[nsi::string!(
+///             "imagefilename",
+///             format!("turntable.{:04}.exr", frame)
+///         )],
+///     );
+/// });
+/// ```
+pub fn turntable(
+    ctx: &nsi::Context,
+    target: &str,
+    frames: u32,
+    mut before_frame: impl FnMut(u32),
+) {
+    for frame in 0..frames {
+        set_euler_rotation(
+            ctx,
+            target,
+            &[0.0, 360.0 * frame as f64 / frames as f64, 0.0],
+        );
+        set_frame(ctx, frame as _);
+
+        before_frame(frame);
+
+        ctx.render_control(nsi::Action::Start, None);
+        ctx.render_control(nsi::Action::Wait, None);
+    }
+}
+
+/// Turns on adaptive sampling.
+///
+/// **Convenience method; not part of the official ɴsɪ API.**
+///
+/// Instead of taking the same number of samples in every pixel, the
+/// renderer starts with `min_samples` and keeps adding samples to a pixel
+/// until its estimated noise is below `noise_threshold`, or `max_samples`
+/// is reached. Flat or well lit parts of an image hence converge quickly
+/// while render time is spent where noise is.
+///
+/// This sets the `"adaptivesampling.enable"`,
+/// `"adaptivesampling.minsamples"`, `"adaptivesampling.maxsamples"` &
+/// `"adaptivesampling.threshold"` attributes of the
+/// [`.global`](nsi::GLOBAL) node.
+///
+/// While adaptive sampling is on, the sample count per pixel is given by
+/// `min_samples` & `max_samples`; the `"oversampling"` of a
+/// [`screen`](nsi::node::SCREEN) does not add to it. Renderers without
+/// support for adaptive sampling ignore these attributes and keep using
+/// `"oversampling"`. Set it to `max_samples` to get the same quality
+/// ceiling with those.
+///
+/// # Arguments
+/// * `min_samples` – The number of samples every pixel gets. Must be
+///   positive.
+///
+/// * `max_samples` – The most samples any pixel gets. Must be at least
+///   `min_samples`.
+///
+/// * `noise_threshold` – The noise a pixel may have to be considered done,
+///   relative to its value; e.g. `0.01` for 1%. Must be positive.
+///
+/// Otherwise an error is reported and nothing is set.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::set_adaptive_sampling;
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// set_adaptive_sampling(&ctx, 16, 1024, 0.01);
+/// ```
+pub fn set_adaptive_sampling(
+    ctx: &nsi::Context,
+    min_samples: u32,
+    max_samples: u32,
+    noise_threshold: f32,
+) {
+    if !(0 < min_samples && min_samples <= max_samples && 0.0 < noise_threshold)
+    {
+        ctx.report(
+            log::Level::Error,
+            &format!(
+                "Invalid adaptive sampling of {}–{} samples with a noise \
+                 threshold of {}.",
+                min_samples, max_samples, noise_threshold
+            ),
+        );
+        return;
+    }
+
+    ctx.set_attribute(
+        nsi::GLOBAL,
+        &[
+            nsi::integer!("adaptivesampling.enable", 1),
+            nsi::integer!("adaptivesampling.minsamples", min_samples as _),
+            nsi::integer!("adaptivesampling.maxsamples", max_samples as _),
+            nsi::float!("adaptivesampling.threshold", noise_threshold),
+        ],
+    );
+}
+
+mod tests;
@@ -0,0 +1,241 @@
+//! Math library agnostic matrix & vector types used by the helpers.
+use core::ops::Deref;
+use ultraviolet as uv;
+
+/// A 4×4 transformation matrix, as written to a
+/// [`transform`](nsi_core::TRANSFORM) node's `"transformationmatrix"`.
+///
+/// The 16 elements are stored in the order ɴsɪ expects them. The matrix
+/// transforms row vectors (`p' = p · M`) and is stored row by row. I.e. the
+/// translation is in elements `12`, `13` & `14`:
+///
+/// ```text
+/// [ m00, m01, m02, 0,
+///   m10, m11, m12, 0,
+///   m20, m21, m22, 0,
+///   tx,  ty,  tz,  1 ]
+/// ```
+///
+/// This is the same memory layout as the column-major matrices of math
+/// libraries that transform column vectors (ultraviolet, glam, nalgebra). So
+/// converting from those does not re-order elements.
+///
+/// Conversions from [`glam`](https://crates.io/crates/glam) and
+/// [`nalgebra`](https://crates.io/crates/nalgebra) matrices are enabled
+/// through the features of the same name.
+///
+/// # Example
+/// ```
+/// # use nsi_toolbelt::Matrix4;
+/// use ultraviolet as uv;
+///
+/// let matrix =
+///     Matrix4::from(uv::DMat4::from_translation(uv::DVec3::new(1., 2., 3.)));
+///
+/// assert_eq!([1., 2., 3.], matrix[12..15]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4(pub [f64; 16]);
+
+impl Matrix4 {
+    /// The identity matrix.
+    pub const IDENTITY: Self = Self([
+        1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1., 0., 0., 0., 0., 1.,
+    ]);
+
+    /// Returns the elements of the matrix.
+    #[inline]
+    pub fn as_array(&self) -> &[f64; 16] {
+        &self.0
+    }
+}
+
+impl Default for Matrix4 {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Deref for Matrix4 {
+    type Target = [f64; 16];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<[f64; 16]> for Matrix4 {
+    #[inline]
+    fn from(matrix: [f64; 16]) -> Self {
+        Self(matrix)
+    }
+}
+
+impl From<Matrix4> for [f64; 16] {
+    #[inline]
+    fn from(matrix: Matrix4) -> Self {
+        matrix.0
+    }
+}
+
+impl From<uv::DMat4> for Matrix4 {
+    #[inline]
+    fn from(matrix: uv::DMat4) -> Self {
+        Self(*matrix.as_array())
+    }
+}
+
+impl From<Matrix4> for uv::DMat4 {
+    #[inline]
+    fn from(matrix: Matrix4) -> Self {
+        let m = matrix.0;
+        uv::DMat4::new(
+            uv::DVec4::new(m[0], m[1], m[2], m[3]),
+            uv::DVec4::new(m[4], m[5], m[6], m[7]),
+            uv::DVec4::new(m[8], m[9], m[10], m[11]),
+            uv::DVec4::new(m[12], m[13], m[14], m[15]),
+        )
+    }
+}
+
+impl From<uv::Mat4> for Matrix4 {
+    #[inline]
+    fn from(matrix: uv::Mat4) -> Self {
+        Self(matrix.as_array().map(|element| element as _))
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DMat4> for Matrix4 {
+    #[inline]
+    fn from(matrix: glam::DMat4) -> Self {
+        Self(matrix.to_cols_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<Matrix4> for glam::DMat4 {
+    #[inline]
+    fn from(matrix: Matrix4) -> Self {
+        glam::DMat4::from_cols_array(&matrix.0)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::Mat4> for Matrix4 {
+    #[inline]
+    fn from(matrix: glam::Mat4) -> Self {
+        Self(matrix.to_cols_array().map(|element| element as _))
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Matrix4<f64>> for Matrix4 {
+    #[inline]
+    fn from(matrix: nalgebra::Matrix4<f64>) -> Self {
+        // nalgebra stores its matrices column-major.
+        Self(matrix.as_slice().try_into().unwrap())
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Matrix4> for nalgebra::Matrix4<f64> {
+    #[inline]
+    fn from(matrix: Matrix4) -> Self {
+        nalgebra::Matrix4::from_column_slice(&matrix.0)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Matrix4<f32>> for Matrix4 {
+    #[inline]
+    fn from(matrix: nalgebra::Matrix4<f32>) -> Self {
+        Self(matrix.cast::<f64>().as_slice().try_into().unwrap())
+    }
+}
+
+/// A three dimensional vector or point.
+///
+/// Conversions from [`glam`](https://crates.io/crates/glam) and
+/// [`nalgebra`](https://crates.io/crates/nalgebra) vectors are enabled
+/// through the features of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector3(pub [f64; 3]);
+
+impl Deref for Vector3 {
+    type Target = [f64; 3];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<[f64; 3]> for Vector3 {
+    #[inline]
+    fn from(vector: [f64; 3]) -> Self {
+        Self(vector)
+    }
+}
+
+impl From<&[f64; 3]> for Vector3 {
+    #[inline]
+    fn from(vector: &[f64; 3]) -> Self {
+        Self(*vector)
+    }
+}
+
+impl From<Vector3> for [f64; 3] {
+    #[inline]
+    fn from(vector: Vector3) -> Self {
+        vector.0
+    }
+}
+
+impl From<uv::DVec3> for Vector3 {
+    #[inline]
+    fn from(vector: uv::DVec3) -> Self {
+        Self([vector.x, vector.y, vector.z])
+    }
+}
+
+impl From<Vector3> for uv::DVec3 {
+    #[inline]
+    fn from(vector: Vector3) -> Self {
+        uv::DVec3::from(vector.0)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::DVec3> for Vector3 {
+    #[inline]
+    fn from(vector: glam::DVec3) -> Self {
+        Self(vector.to_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<Vector3> for glam::DVec3 {
+    #[inline]
+    fn from(vector: Vector3) -> Self {
+        glam::DVec3::from_array(vector.0)
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<nalgebra::Vector3<f64>> for Vector3 {
+    #[inline]
+    fn from(vector: nalgebra::Vector3<f64>) -> Self {
+        Self([vector.x, vector.y, vector.z])
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+impl From<Vector3> for nalgebra::Vector3<f64> {
+    #[inline]
+    fn from(vector: Vector3) -> Self {
+        nalgebra::Vector3::new(vector.0[0], vector.0[1], vector.0[2])
+    }
+}
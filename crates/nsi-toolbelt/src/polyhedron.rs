@@ -0,0 +1,167 @@
+//! Support for sending [`polyhedron_ops`] geometry to an ɴsɪ context.
+use crate::{generate_or_use_handle, Subdivision};
+use nsi_core as nsi;
+use polyhedron_ops::Polyhedron;
+
+/// Extension trait for an [`nsi::Context`] to create nodes from a
+/// [`Polyhedron`].
+///
+/// This is enabled through the `polyhedron` feature.
+pub trait PolyhedronContext {
+    /// Creates a [`mesh`](nsi::MESH) node from a [`Polyhedron`].
+    ///
+    /// The mesh is described with the `"P"`, `"P.indices"` and `"nvertices"`
+    /// attributes. As [`Polyhedron`]s wind their faces clockwise,
+    /// `"clockwisewinding"` is also set.
+    ///
+    /// Nothing else is set on the mesh. To render it as a subdivision
+    /// surface use
+    /// [`subdivision_polyhedron()`](PolyhedronContext::subdivision_polyhedron())
+    /// instead.
+    ///
+    /// # Arguments
+    /// * `polyhedron` – The polyhedron to send to the renderer.
+    ///
+    /// * `handle` – The handle of the mesh node. If [`None`], a random handle
+    ///   is generated.
+    ///
+    /// Returns `handle` for convenience.
+    ///
+    /// # Example
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # use nsi_toolbelt::{append, PolyhedronContext};
+    /// # use polyhedron_ops::Polyhedron;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// let mut polyhedron = Polyhedron::dodecahedron();
+    /// polyhedron.chamfer(None, true);
+    /// polyhedron.normalize();
+    ///
+    /// append(&ctx, nsi::ROOT, None, &ctx.polyhedron(&polyhedron, None));
+    /// ```
+    fn polyhedron(
+        &self,
+        polyhedron: &Polyhedron,
+        handle: Option<&str>,
+    ) -> String;
+
+    /// Creates a [`mesh`](nsi::MESH) node from a [`Polyhedron`] that is
+    /// rendered as a Catmull-Clark subdivision surface.
+    ///
+    /// This is [`polyhedron()`](PolyhedronContext::polyhedron()) plus the
+    /// attributes of `subdivision`. Every edge of the polyhedron is also
+    /// creased with `crease_sharpness`. Semi-sharp creases keep the
+    /// subdivided surface close to the facets of the polyhedron.
+    ///
+    /// # Arguments
+    /// * `polyhedron` – The polyhedron to send to the renderer.
+    ///
+    /// * `handle` – The handle of the mesh node. If [`None`], a random handle
+    ///   is generated.
+    ///
+    /// * `subdivision` – The subdivision rules.
+    ///
+    /// * `crease_sharpness` – The `"subdivision.creasesharpness"` of all
+    ///   edges. If [`None`], `10` is used. `0` creases no edges.
+    ///
+    /// Returns `handle` for convenience.
+    ///
+    /// # Example
+    /// ```
+    /// # use nsi_core as nsi;
+    /// # use nsi_toolbelt::{append, CornerMode, PolyhedronContext, Subdivision};
+    /// # use polyhedron_ops::Polyhedron;
+    /// # let ctx = nsi::Context::new(None).unwrap();
+    /// let polyhedron = Polyhedron::dodecahedron();
+    ///
+    /// let mesh = ctx.subdivision_polyhedron(
+    ///     &polyhedron,
+    ///     None,
+    ///     &Subdivision::new().corner_mode(CornerMode::Sharp),
+    ///     None,
+    /// );
+    /// append(&ctx, nsi::ROOT, None, &mesh);
+    /// ```
+    fn subdivision_polyhedron(
+        &self,
+        polyhedron: &Polyhedron,
+        handle: Option<&str>,
+        subdivision: &Subdivision,
+        crease_sharpness: Option<f32>,
+    ) -> String;
+}
+
+impl PolyhedronContext for nsi::Context<'_> {
+    fn polyhedron(
+        &self,
+        polyhedron: &Polyhedron,
+        handle: Option<&str>,
+    ) -> String {
+        let handle = generate_or_use_handle(handle, Some("polyhedron"));
+        self.create(&handle, nsi::MESH, None);
+
+        let positions = polyhedron
+            .positions()
+            .iter()
+            .flat_map(|point| [point.x, point.y, point.z])
+            .collect::<Vec<_>>();
+
+        let indices = polyhedron
+            .faces()
+            .iter()
+            .flat_map(|face| face.iter().map(|&index| index as i32))
+            .collect::<Vec<_>>();
+
+        let nvertices = polyhedron
+            .faces()
+            .iter()
+            .map(|face| face.len() as i32)
+            .collect::<Vec<_>>();
+
+        self.set_attribute(
+            &handle,
+            &[
+                nsi::points!("P", &positions),
+                nsi::integers!("P.indices", &indices),
+                nsi::integers!("nvertices", &nvertices),
+                nsi::integer!("clockwisewinding", true as _),
+            ],
+        );
+
+        handle
+    }
+
+    fn subdivision_polyhedron(
+        &self,
+        polyhedron: &Polyhedron,
+        handle: Option<&str>,
+        subdivision: &Subdivision,
+        crease_sharpness: Option<f32>,
+    ) -> String {
+        let handle = self.polyhedron(polyhedron, handle);
+        subdivision.apply(self, &handle);
+
+        let crease_sharpness = crease_sharpness.unwrap_or(10.0);
+
+        if 0.0 != crease_sharpness {
+            let edges = polyhedron
+                .to_edges()
+                .iter()
+                .flat_map(|edge| edge.map(|index| index as i32))
+                .collect::<Vec<_>>();
+
+            self.set_attribute(
+                &handle,
+                &[
+                    nsi::integers!("subdivision.creasevertices", &edges),
+                    nsi::floats!(
+                        "subdivision.creasesharpness",
+                        &vec![crease_sharpness; edges.len() / 2]
+                    ),
+                ],
+            );
+        }
+
+        handle
+    }
+}
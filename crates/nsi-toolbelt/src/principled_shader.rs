@@ -0,0 +1,100 @@
+//! A builder for the `dlPrincipled` surface shader of 3Delight.
+use crate::{ev_to_multiplier, node};
+use nsi_core as nsi;
+
+/// Builder for 3Delight's `${DELIGHT}/osl/dlPrincipled` shader.
+///
+/// | Method                                   | Shader input
+/// |------------------------------------------|--------------------------------------------------
+/// | [`emission()`](PrincipledShader::emission()) | `"incandescence"`, `"incandescence_intensity"`
+///
+/// Only inputs that were explicitly set are sent to the renderer. All
+/// others keep the shader's defaults.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{assign_material, PrincipledShader};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let neon = PrincipledShader::new()
+///     .emission([1.0, 0.2, 0.6], 3.0)
+///     .build(&ctx);
+///
+/// assign_material(&ctx, &neon, &["sign"]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrincipledShader {
+    emission_color: Option<[f32; 3]>,
+    emission_intensity: Option<f32>,
+}
+
+impl PrincipledShader {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the surface emit light (`"incandescence"`).
+    ///
+    /// The emitted light is `color` times 2<sup>`intensity_ev`</sup>; see
+    /// [`ev_to_multiplier()`]. I.e. `0` emits `color` as is and each
+    /// stop more doubles the brightness.
+    ///
+    /// Unlike a dedicated light, e.g. a spot or an environment, an emissive
+    /// surface is not sampled directly by the renderer. It only lights the
+    /// scene through global illumination, like any other bright surface.
+    /// This is fine for glowing objects that are mostly seen, e.g. screens
+    /// or neon signs. For small emitters that are meant to light a lot of
+    /// the scene it gives much more noise for the same number of samples;
+    /// use a light for those.
+    #[inline]
+    #[must_use]
+    pub fn emission(mut self, color: [f32; 3], intensity_ev: f32) -> Self {
+        self.emission_color = Some(color);
+        self.emission_intensity = Some(ev_to_multiplier(intensity_ev));
+        self
+    }
+
+    /// Returns the arguments for all shader inputs that were set.
+    pub fn args(&self) -> nsi::ArgVec<'static, 'static> {
+        let mut args = Vec::new();
+
+        if let Some(color) = self.emission_color {
+            args.push(nsi::color!("incandescence", color));
+        }
+
+        if let Some(intensity) = self.emission_intensity {
+            args.push(nsi::float!("incandescence_intensity", intensity));
+        }
+
+        args
+    }
+
+    /// Sets all shader inputs that were set on the shader node `shader`.
+    pub fn apply(&self, ctx: &nsi::Context, shader: &str) {
+        let args = self.args();
+
+        if !args.is_empty() {
+            ctx.set_attribute(shader, &args);
+        }
+    }
+
+    /// Creates the [`shader`](nsi::node::SHADER) node.
+    ///
+    /// Returns its handle. Assign it to geometry with
+    /// [`assign_material()`](crate::assign_material()).
+    pub fn build(&self, ctx: &nsi::Context) -> String {
+        let shader = node(
+            ctx,
+            None,
+            nsi::node::SHADER,
+            Some(&[nsi::string!(
+                "shaderfilename",
+                "${DELIGHT}/osl/dlPrincipled"
+            )]),
+        );
+        self.apply(ctx, &shader);
+
+        shader
+    }
+}
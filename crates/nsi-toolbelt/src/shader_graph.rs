@@ -0,0 +1,197 @@
+//! A builder for ᴏsʟ shader networks.
+use crate::generate_or_use_handle;
+use nsi_core as nsi;
+
+/// A reference to a shader added to a [`ShaderGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeRef(usize);
+
+impl NodeRef {
+    /// Returns the output parameter `name` of the shader.
+    #[inline]
+    pub fn output(self, name: &str) -> Socket {
+        Socket {
+            node: self,
+            name: name.to_string(),
+        }
+    }
+
+    /// Returns the input parameter `name` of the shader.
+    #[inline]
+    pub fn input(self, name: &str) -> Socket {
+        self.output(name)
+    }
+}
+
+/// A parameter of a shader in a [`ShaderGraph`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Socket {
+    node: NodeRef,
+    name: String,
+}
+
+/// An error when connecting shaders in a [`ShaderGraph`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderGraphError {
+    /// The input `input` of the shader with handle `shader` is already
+    /// connected.
+    InputAlreadyConnected { shader: String, input: String },
+}
+
+impl std::fmt::Display for ShaderGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderGraphError::InputAlreadyConnected { shader, input } => {
+                write!(
+                    f,
+                    "input '{}' of '{}' is already connected",
+                    input, shader
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderGraphError {}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Shader {
+    handle: String,
+    path: String,
+}
+
+/// Builder for a network of [`shader`](nsi::SHADER) nodes.
+///
+/// Shaders are added with [`add_shader()`](ShaderGraph::add_shader()) and
+/// wired up with [`connect()`](ShaderGraph::connect()). Handles are generated
+/// for all shaders. Nothing is sent to the renderer until
+/// [`build()`](ShaderGraph::build()) is called.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{node, ShaderGraph};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let mut graph = ShaderGraph::new();
+///
+/// let texture = graph.add_shader("${DELIGHT}/osl/dlTexture");
+/// let surface = graph.add_shader("${DELIGHT}/osl/dlPrincipled");
+///
+/// graph
+///     .connect(texture.output("outColor"), surface.input("i_color"))
+///     .unwrap();
+///
+/// let attributes = node(&ctx, None, nsi::ATTRIBUTES, None);
+/// ctx.connect(&graph.build(&ctx), None, &attributes, "surfaceshader", None);
+///
+/// // Shader parameters are set through the generated handles.
+/// ctx.set_string(graph.handle(texture), "textureFile", "wood.tdl");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShaderGraph {
+    shaders: Vec<Shader>,
+    connections: Vec<(Socket, Socket)>,
+}
+
+impl ShaderGraph {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a shader, loaded from `osl_path`, to the graph.
+    ///
+    /// The path is used as the shader's `"shaderfilename"`.
+    pub fn add_shader(&mut self, osl_path: &str) -> NodeRef {
+        let name = osl_path
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("shader");
+
+        self.shaders.push(Shader {
+            handle: generate_or_use_handle(None, Some(name)),
+            path: osl_path.to_string(),
+        });
+
+        NodeRef(self.shaders.len() - 1)
+    }
+
+    /// Returns the handle generated for the `shader`.
+    ///
+    /// # Panics
+    ///
+    /// If `shader` was not added to this graph.
+    #[inline]
+    pub fn handle(&self, shader: NodeRef) -> &str {
+        &self.shaders[shader.0].handle
+    }
+
+    /// Connects the output `from` of one shader to the input `to` of
+    /// another.
+    ///
+    /// An input can only be driven by one output. Connecting an input a
+    /// second time is an error.
+    pub fn connect(
+        &mut self,
+        from: Socket,
+        to: Socket,
+    ) -> Result<(), ShaderGraphError> {
+        if self.connections.iter().any(|(_, input)| *input == to) {
+            return Err(ShaderGraphError::InputAlreadyConnected {
+                shader: self.handle(to.node).to_string(),
+                input: to.name,
+            });
+        }
+
+        self.connections.push((from, to));
+
+        Ok(())
+    }
+
+    /// Returns the shader at the end of the network.
+    ///
+    /// This is the shader whose outputs are not connected to any other
+    /// shader. If there are several, the one added last is returned.
+    pub fn root(&self) -> Option<NodeRef> {
+        (0..self.shaders.len()).rev().map(NodeRef).find(|&shader| {
+            !self
+                .connections
+                .iter()
+                .any(|(output, _)| output.node == shader)
+        })
+    }
+
+    /// Creates the shader nodes and their connections.
+    ///
+    /// Returns the handle of the [`root()`](ShaderGraph::root()) shader.
+    /// Connect this to the `"surfaceshader"` (or `"displacementshader"`,
+    /// `"volumeshader"`) of an [`attributes`](nsi::ATTRIBUTES) node.
+    ///
+    /// # Panics
+    ///
+    /// If the graph is empty.
+    pub fn build(&self, ctx: &nsi::Context) -> String {
+        let root = self.root().expect("The shader graph is empty.");
+
+        for shader in &self.shaders {
+            ctx.create(
+                &shader.handle,
+                nsi::SHADER,
+                Some(&[nsi::string!("shaderfilename", shader.path.as_str())]),
+            );
+        }
+
+        for (output, input) in &self.connections {
+            ctx.connect(
+                self.handle(output.node),
+                Some(&output.name),
+                self.handle(input.node),
+                &input.name,
+                None,
+            );
+        }
+
+        self.handle(root).to_string()
+    }
+}
@@ -0,0 +1,157 @@
+//! A builder for the subdivision attributes of a `mesh`.
+use nsi_core as nsi;
+
+/// How a vertex where exactly two creases meet is subdivided
+/// (`"subdivision.smoothcreasecorners"`).
+///
+/// This is the _corner vertex rule_ of OpenSubdiv's Catmull-Clark scheme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CornerMode {
+    /// The creases are blended smoothly through the vertex. The default.
+    #[default]
+    Smooth,
+    /// The vertex is treated like an infinitely sharp corner; the creases
+    /// meet in a point.
+    Sharp,
+}
+
+impl CornerMode {
+    /// Returns the value of `"subdivision.smoothcreasecorners"`.
+    #[inline]
+    pub const fn as_nsi(&self) -> i32 {
+        match self {
+            CornerMode::Smooth => 1,
+            CornerMode::Sharp => 0,
+        }
+    }
+}
+
+/// How face-varying data, e.g. texture coordinates, is interpolated on a
+/// subdivision surface
+/// (`"subdivision.facevaryinginterpolation"`).
+///
+/// These are the `FVarLinearInterpolation` rules of OpenSubdiv, in the same
+/// order. They decide which parts of the UV layout are _pinned_, i.e. stay
+/// where they are on the control mesh, while the rest is smoothed like the
+/// surface itself. Pinning more keeps UV borders in place but makes the
+/// layout less smooth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FaceVaryingInterpolation {
+    /// Everything is smoothed, including UV boundaries & corners. The UV
+    /// layout may shrink away from its borders.
+    None,
+    /// Only vertices at corners of the UV layout, i.e. with a single face,
+    /// are pinned.
+    CornersOnly,
+    /// Like [`CornersOnly`](FaceVaryingInterpolation::CornersOnly) but also
+    /// pins vertices where three or more UV islands meet.
+    CornersPlusOne,
+    /// Like [`CornersPlusOne`](FaceVaryingInterpolation::CornersPlusOne) but
+    /// also pins darts and concave corners of the UV layout.
+    CornersPlusTwo,
+    /// All boundaries of the UV layout are interpolated linearly; only the
+    /// interior is smoothed.
+    Boundaries,
+    /// Face-varying data is interpolated linearly everywhere. Use this for
+    /// data that must not be smoothed at all, e.g. a texture atlas packed
+    /// tightly. The default, like OpenSubdiv's `FVAR_LINEAR_ALL`.
+    #[default]
+    All,
+}
+
+impl FaceVaryingInterpolation {
+    /// Returns the value of `"subdivision.facevaryinginterpolation"`.
+    #[inline]
+    pub const fn as_nsi(&self) -> i32 {
+        match self {
+            FaceVaryingInterpolation::None => 0,
+            FaceVaryingInterpolation::CornersOnly => 1,
+            FaceVaryingInterpolation::CornersPlusOne => 2,
+            FaceVaryingInterpolation::CornersPlusTwo => 3,
+            FaceVaryingInterpolation::Boundaries => 4,
+            FaceVaryingInterpolation::All => 5,
+        }
+    }
+}
+
+/// Builder for the subdivision attributes of a
+/// [`mesh`](nsi::node::MESH).
+///
+/// This always sets `"subdivision.scheme"` to `"catmull-clark"`, the only
+/// scheme ɴsɪ defines. The corner & face-varying rules are only sent to the
+/// renderer if they were explicitly set. Otherwise the renderer's defaults
+/// apply. To get a known rule regardless of the renderer, set it
+/// explicitly, e.g. to the [`Default`] of its enum.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{CornerMode, FaceVaryingInterpolation, Subdivision};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// ctx.create("cube", nsi::MESH, None);
+///
+/// Subdivision::new()
+///     .corner_mode(CornerMode::Sharp)
+///     .face_varying_interpolation(FaceVaryingInterpolation::Boundaries)
+///     .apply(&ctx, "cube");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Subdivision {
+    corner_mode: Option<CornerMode>,
+    face_varying_interpolation: Option<FaceVaryingInterpolation>,
+}
+
+impl Subdivision {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how vertices where two creases meet are subdivided.
+    #[inline]
+    #[must_use]
+    pub fn corner_mode(mut self, mode: CornerMode) -> Self {
+        self.corner_mode = Some(mode);
+        self
+    }
+
+    /// Sets how face-varying data is interpolated at the boundaries of its
+    /// layout.
+    #[inline]
+    #[must_use]
+    pub fn face_varying_interpolation(
+        mut self,
+        interpolation: FaceVaryingInterpolation,
+    ) -> Self {
+        self.face_varying_interpolation = Some(interpolation);
+        self
+    }
+
+    /// Returns the arguments for the scheme and all rules that were set.
+    pub fn args(&self) -> nsi::ArgVec<'static, 'static> {
+        let mut args =
+            vec![nsi::string!("subdivision.scheme", "catmull-clark")];
+
+        if let Some(mode) = self.corner_mode {
+            args.push(nsi::integer!(
+                "subdivision.smoothcreasecorners",
+                mode.as_nsi()
+            ));
+        }
+
+        if let Some(interpolation) = self.face_varying_interpolation {
+            args.push(nsi::integer!(
+                "subdivision.facevaryinginterpolation",
+                interpolation.as_nsi()
+            ));
+        }
+
+        args
+    }
+
+    /// Sets the attributes on the mesh node `mesh`.
+    #[inline]
+    pub fn apply(&self, ctx: &nsi::Context, mesh: &str) {
+        ctx.set_attribute(mesh, &self.args());
+    }
+}
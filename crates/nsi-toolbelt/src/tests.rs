@@ -0,0 +1,880 @@
+#[allow(unused_imports)]
+use crate::*;
+#[cfg(test)]
+use nsi::record_stream;
+
+// Returns the handles of the nodes created in `stream`, in order.
+#[cfg(test)]
+fn created_handles(stream: &str) -> Vec<&str> {
+    stream
+        .lines()
+        .filter_map(|line| line.strip_prefix("Create \""))
+        .filter_map(|line| line.split('"').next())
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn test_global_settings_ray_depths() {
+    let args = GlobalSettings::new()
+        .max_diffuse_depth(2)
+        .max_hair_depth(3)
+        .args();
+
+    assert_eq!(
+        vec!["maximumraydepth.diffuse", "maximumraydepth.hair"],
+        args.iter().map(|arg| arg.name()).collect::<Vec<_>>()
+    );
+
+    assert_eq!(5, GlobalSettings::new().max_ray_depth(8).args().len());
+    assert!(GlobalSettings::new().args().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_ev_round_trip() {
+    assert_eq!(1.0, ev_to_multiplier(0.0));
+    assert_eq!(0.5, ev_to_multiplier(-1.0));
+
+    for ev in [-3.5f32, -1.0, 0.0, 0.25, 2.0, 10.0] {
+        assert!((ev - multiplier_to_ev(ev_to_multiplier(ev))).abs() < 1e-5);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_image_aspect_ratio() {
+    assert_eq!(1.5, image_aspect_ratio([1920, 1280], 1.0));
+    // Anamorphic: a 4:3 resolution with 2:1 pixels displays as 8:3.
+    assert!((8.0 / 3.0 - image_aspect_ratio([1440, 1080], 2.0)).abs() < 1e-6);
+    // Tall pixels can turn a landscape resolution into a portrait image.
+    assert!(image_aspect_ratio([1200, 1000], 0.5) < 1.0);
+}
+
+#[cfg(test)]
+#[test]
+fn test_look_at_bounding_box_pixel_aspect() {
+    let bounding_box = [-1.0, -2.0, -1.0, 1.0, 2.0, 1.0];
+    let look_at = |aspect_ratio, pixel_aspect| {
+        record_stream(|ctx| {
+            look_at_bounding_box_perspective_camera(
+                ctx,
+                Some("camera"),
+                [0.0, 0.0, -1.0],
+                [0.0, 1.0, 0.0],
+                35.0,
+                Some(aspect_ratio),
+                pixel_aspect,
+                &bounding_box,
+            );
+        })
+    };
+
+    // A landscape resolution with tall pixels is framed as the portrait
+    // image it displays as.
+    assert_eq!(look_at(0.6, 1.0), look_at(1.2, 0.5));
+    assert_ne!(look_at(1.2, 1.0), look_at(1.2, 0.5));
+}
+
+#[cfg(test)]
+#[test]
+fn test_shader_graph() {
+    let mut graph = ShaderGraph::new();
+    assert_eq!(None, graph.root());
+
+    let noise = graph.add_shader("${DELIGHT}/osl/dlNoise");
+    let texture = graph.add_shader("${DELIGHT}/osl/dlTexture");
+    let surface = graph.add_shader("${DELIGHT}/osl/dlPrincipled");
+
+    assert!(graph.handle(noise).starts_with("dlNoise"));
+
+    graph
+        .connect(texture.output("outColor"), surface.input("i_color"))
+        .unwrap();
+    graph
+        .connect(noise.output("outColor"), texture.input("uvCoord"))
+        .unwrap();
+
+    // An input can only be connected once.
+    assert_eq!(
+        Err(ShaderGraphError::InputAlreadyConnected {
+            shader: graph.handle(surface).to_string(),
+            input: "i_color".to_string(),
+        }),
+        graph.connect(noise.output("outColor"), surface.input("i_color"))
+    );
+
+    // The same output can drive several inputs.
+    graph
+        .connect(noise.output("outColor"), surface.input("roughness"))
+        .unwrap();
+
+    assert_eq!(Some(surface), graph.root());
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_priority_window() {
+    let stream = record_stream(|ctx| {
+        set_priority_window(ctx, "screen", [16, 32], [128, 96]);
+        // Invalid; nothing is emitted.
+        set_priority_window(ctx, "screen", [128, 32], [16, 96]);
+    });
+
+    assert_eq!(
+        "SetAttribute \"screen\" \"prioritywindow\" \"int[2]\" 2 \
+         [ 16 32 128 96 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_screen_framing() {
+    let stream = record_stream(|ctx| {
+        set_overscan(ctx, "screen", [32, 16]);
+        set_screen_window(ctx, "screen", [-2.0, -1.0, 2.0, 1.5]);
+    });
+
+    assert_eq!(
+        "SetAttribute \"screen\" \"overscan\" \"int[2]\" 2 [ 32 16 32 16 ]\n\
+         SetAttribute \"screen\" \"screenwindow\" \"double[2]\" 2 \
+         [ -2.0 -1.0 2.0 1.5 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_handle_seed() {
+    set_handle_seed(Some(42));
+    let handles = (0..4)
+        .map(|_| generate_or_use_handle(None, Some("mesh")))
+        .collect::<Vec<_>>();
+
+    set_handle_seed(Some(42));
+    for handle in &handles {
+        // Handles generated on other threads do not advance the sequence.
+        std::thread::spawn(|| generate_or_use_handle(None, Some("mesh")))
+            .join()
+            .unwrap();
+        assert_eq!(*handle, generate_or_use_handle(None, Some("mesh")));
+    }
+
+    set_handle_seed(None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_add_lpe_layer() {
+    let mut layer = None;
+    let stream = record_stream(|ctx| {
+        layer = add_lpe_layer(ctx, "screen", "C<RD>L", "diffuse_direct");
+        assert!(add_lpe_layer(ctx, "screen", " ", "empty").is_none());
+    });
+    let layer = layer.unwrap();
+
+    assert_eq!(
+        format!(
+            "Create \"{0}\" \"outputlayer\"\n\
+             SetAttribute \"{0}\" \"variablename\" \"string\" 1 [ \"Ci\" ] \
+             \"lightexpression\" \"string\" 1 [ \"C<RD>L\" ] \
+             \"layername\" \"string\" 1 [ \"diffuse_direct\" ] \
+             \"layertype\" \"string\" 1 [ \"color\" ] \
+             \"scalarformat\" \"string\" 1 [ \"float\" ]\n\
+             Connect \"{0}\" \"\" \"screen\" \"outputlayers\"\n",
+            layer
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_add_file_output() {
+    let mut driver = String::new();
+    let stream = record_stream(|ctx| {
+        driver = add_file_output(ctx, "beauty", "beauty.tif", FileDriver::Tiff);
+    });
+
+    assert_eq!(
+        format!(
+            "Create \"{0}\" \"outputdriver\"\n\
+             SetAttribute \"{0}\" \"drivername\" \"string\" 1 [ \"tiff\" ] \
+             \"imagefilename\" \"string\" 1 [ \"beauty.tif\" ]\n\
+             Connect \"{0}\" \"\" \"beauty\" \"outputdrivers\"\n",
+            driver
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_debug_bounding_box() {
+    let stream = record_stream(|ctx| {
+        assert_eq!(
+            "box",
+            debug_bounding_box(ctx, Some("box"), &[0., 0., 0., 1., 2., 3.])
+        );
+    });
+
+    let ["box", attributes, shader] = created_handles(&stream)[..] else {
+        panic!("Unexpected nodes in:\n{}", stream);
+    };
+
+    assert_eq!(
+        format!(
+            "Create \"box\" \"curves\"\n\
+             SetAttribute \"box\" \
+             \"nvertices\" \"int\" 12 [ 2 2 2 2 2 2 2 2 2 2 2 2 ] \
+             \"P\" \"point\" 24 [ \
+             0.0 0.0 0.0 1.0 0.0 0.0 1.0 0.0 0.0 1.0 2.0 0.0 \
+             1.0 2.0 0.0 0.0 2.0 0.0 0.0 2.0 0.0 0.0 0.0 0.0 \
+             0.0 0.0 3.0 1.0 0.0 3.0 1.0 0.0 3.0 1.0 2.0 3.0 \
+             1.0 2.0 3.0 0.0 2.0 3.0 0.0 2.0 3.0 0.0 0.0 3.0 \
+             0.0 0.0 0.0 0.0 0.0 3.0 1.0 0.0 0.0 1.0 0.0 3.0 \
+             1.0 2.0 0.0 1.0 2.0 3.0 0.0 2.0 0.0 0.0 2.0 3.0 ] \
+             \"basis\" \"string\" 1 [ \"linear\" ] \
+             \"width\" \"float\" 1 [ {2:?} ]\n\
+             Connect \"box\" \"\" \".root\" \"objects\"\n\
+             Create \"{0}\" \"attributes\"\n\
+             Connect \"{0}\" \"\" \"box\" \"geometryattributes\"\n\
+             SetAttribute \"{0}\" \
+             \"visibility.diffuse\" \"int\" 1 [ 0 ] \
+             \"visibility.hair\" \"int\" 1 [ 0 ] \
+             \"visibility.reflection\" \"int\" 1 [ 0 ] \
+             \"visibility.refraction\" \"int\" 1 [ 0 ] \
+             \"visibility.shadow\" \"int\" 1 [ 0 ] \
+             \"visibility.specular\" \"int\" 1 [ 0 ] \
+             \"visibility.volume\" \"int\" 1 [ 0 ]\n\
+             Create \"{1}\" \"shader\"\n\
+             SetAttribute \"{1}\" \
+             \"shaderfilename\" \"string\" 1 [ \"${{DELIGHT}}/osl/dlPrincipled\" ] \
+             \"i_color\" \"color\" 1 [ 0.0 0.0 0.0 ] \
+             \"specular_level\" \"float\" 1 [ 0.0 ] \
+             \"incandescence\" \"color\" 1 [ 0.0 1.0 0.0 ] \
+             \"incandescence_intensity\" \"float\" 1 [ 1.0 ]\n\
+             Connect \"{1}\" \"\" \"{0}\" \"surfaceshader\"\n",
+            attributes,
+            shader,
+            0.005 * 14f32.sqrt()
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_texture_memory() {
+    assert_eq!(
+        vec!["texturememory"],
+        GlobalSettings::new()
+            .texture_memory(4096)
+            .args()
+            .iter()
+            .map(|arg| arg.name())
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        GlobalSettings::new().texture_memory(MIN_TEXTURE_MEMORY),
+        GlobalSettings::new().texture_memory(1)
+    );
+
+    let ctx = nsi::Context::new(None).unwrap();
+    set_texture_memory(&ctx, 2048);
+}
+
+#[cfg(test)]
+#[test]
+fn test_width_for_aspect() {
+    assert_eq!(1920, width_for_aspect(1080, 16.0 / 9.0));
+    assert_eq!(1080, width_for_aspect(1080, 1.0));
+    // Portrait.
+    assert_eq!(720, width_for_aspect(1280, 9.0 / 16.0));
+    // Rounds to the nearest pixel.
+    assert_eq!(2581, width_for_aspect(1080, 2.39));
+    assert_eq!(1, width_for_aspect(10, 0.0));
+
+    let resolution = [width_for_aspect(1000, 1.85), 1000];
+    assert!((1.85 - image_aspect_ratio(resolution, 1.0)).abs() < 1e-3);
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_displacement() {
+    let stream = record_stream(|ctx| {
+        assert_eq!(
+            "attributes",
+            set_displacement(ctx, "attributes", 0.1, "bump")
+        );
+        assert_eq!("attributes", set_shading_samples(ctx, "attributes", 16));
+        // Invalid; nothing is emitted.
+        set_shading_samples(ctx, "attributes", 0);
+    });
+
+    assert_eq!(
+        "Connect \"bump\" \"\" \"attributes\" \"displacementshader\"\n\
+         SetAttribute \"attributes\" \"displacement.bound\" \"float\" 1 \
+         [ 0.1 ]\n\
+         SetAttribute \"attributes\" \"shadingsamples\" \"int\" 1 [ 16 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_global_settings_samples() {
+    let args = GlobalSettings::new()
+        .shading_samples(64)
+        .volume_samples(8)
+        .args();
+
+    assert_eq!(
+        vec!["quality.shadingsamples", "quality.volumesamples"],
+        args.iter().map(|arg| arg.name()).collect::<Vec<_>>()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_translation_vector() {
+    let stream = record_stream(|ctx| {
+        translation(ctx, Some("array"), [1.0, 2.0, 3.0]);
+        translation(ctx, Some("ultraviolet"), uv::DVec3::new(1.0, 2.0, 3.0));
+    });
+
+    assert_eq!(
+        ["array", "ultraviolet"]
+            .map(|handle| format!(
+                "Create \"{0}\" \"transform\"\n\
+                 SetAttribute \"{0}\" \"transformationmatrix\" \
+                 \"doublematrix\" 1 [ 1.0 0.0 0.0 0.0 0.0 1.0 0.0 0.0 \
+                 0.0 0.0 1.0 0.0 1.0 2.0 3.0 1.0 ]\n",
+                handle
+            ))
+            .concat(),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_animate_camera() {
+    let stream = record_stream(|ctx| {
+        let mut matrix = Matrix4::IDENTITY.0;
+        let samples = [0.0, 0.25, 0.5].map(|time| {
+            matrix[12] = time;
+            (time, matrix)
+        });
+        animate_camera(ctx, "camera_xform", &samples);
+
+        // Duplicate times are rejected.
+        animate_camera(
+            ctx,
+            "broken_xform",
+            &[(0.0, matrix), (1.0, matrix), (0.0, matrix)],
+        );
+    });
+
+    assert_eq!(
+        ["0.0", "0.25", "0.5"]
+            .map(|time| format!(
+                "SetAttributeAtTime \"camera_xform\" {0} \
+                 \"transformationmatrix\" \"doublematrix\" 1 [ \
+                 1.0 0.0 0.0 0.0 0.0 1.0 0.0 0.0 0.0 0.0 1.0 0.0 \
+                 {0} 0.0 0.0 1.0 ]\n",
+                time
+            ))
+            .concat(),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_fov_conversion() {
+    // 2 · atan(16/9 · tan(45°)).
+    assert!(
+        (121.2839 - fov_vertical_to_horizontal(90.0, 16.0 / 9.0)).abs() < 1e-3
+    );
+    // A square image has the same field of view both ways.
+    assert!((40.0 - fov_vertical_to_horizontal(40.0, 1.0)).abs() < 1e-4);
+    // Portrait: the horizontal field of view is the narrower one.
+    assert!(fov_vertical_to_horizontal(60.0, 0.5) < 60.0);
+
+    for (fov, aspect) in
+        [(30.0, 2.39), (54.4, 1.5), (90.0, 16.0 / 9.0), (100.0, 0.75)]
+    {
+        let round_trip = fov_horizontal_to_vertical(
+            fov_vertical_to_horizontal(fov, aspect),
+            aspect,
+        );
+        assert!((fov - round_trip).abs() < 1e-3);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_focal_length_to_fov() {
+    // 2 · atan(18/50).
+    assert!(
+        (39.5978 - focal_length_to_fov(50.0, SENSOR_WIDTH_FULL_FRAME)).abs()
+            < 1e-3
+    );
+    // A lens as long as half the sensor width gives a right angle.
+    assert!((90.0 - focal_length_to_fov(18.0, 36.0)).abs() < 1e-4);
+    // The same lens is narrower on a smaller sensor.
+    assert!(
+        focal_length_to_fov(50.0, SENSOR_WIDTH_SUPER_35)
+            < focal_length_to_fov(50.0, SENSOR_WIDTH_FULL_FRAME)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_curve_smoothing() {
+    let stream = record_stream(|ctx| {
+        set_curve_smoothing(ctx, "smooth", true);
+        set_curve_width_interpolation(ctx, "smooth", &[3], 0.5, 0.25);
+
+        set_curve_smoothing(ctx, "kinked", false);
+    });
+
+    assert_eq!(
+        "SetAttribute \"smooth\" \"basis\" \"string\" 1 [ \"catmull-rom\" ] \
+         \"extrapolate\" \"int\" 1 [ 1 ]\n\
+         SetAttribute \"smooth\" \"width\" \"float\" 3 [ 0.5 0.375 0.25 ]\n\
+         SetAttribute \"kinked\" \"basis\" \"string\" 1 [ \"linear\" ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_metadata() {
+    let stream = record_stream(|ctx| {
+        set_frame(ctx, 1001);
+        set_render_metadata(ctx, "shot", "sq010_sh020");
+        set_render_metadata(ctx, "", "ignored");
+    });
+
+    assert_eq!(
+        "SetAttribute \".global\" \"frame\" \"double\" 1 [ 1001.0 ]\n\
+         SetAttribute \".global\" \"metadata.shot\" \"string\" 1 [ \"sq010_sh020\" ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_dither() {
+    let stream = record_stream(|ctx| {
+        set_dither(ctx, "beauty", true);
+        set_filter_width(ctx, "beauty", 2.5);
+        set_filter_width(ctx, "beauty", -1.0);
+    });
+
+    assert_eq!(
+        "SetAttribute \"beauty\" \"dithering\" \"int\" 1 [ 1 ]\n\
+         SetAttribute \"beauty\" \"filterwidth\" \"double\" 1 [ 2.5 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_ambient_occlusion_pass() {
+    let mut layer = None;
+    let stream = record_stream(|ctx| {
+        layer = ambient_occlusion_pass(ctx, "screen", 2.0);
+        assert!(ambient_occlusion_pass(ctx, "screen", 0.0).is_none());
+    });
+    let layer = layer.unwrap();
+
+    assert_eq!(
+        format!(
+            "Create \"{0}\" \"outputlayer\"\n\
+             SetAttribute \"{0}\" \"variablename\" \"string\" 1 [ \"Ci\" ] \
+             \"layername\" \"string\" 1 [ \"occlusion\" ] \
+             \"layertype\" \"string\" 1 [ \"color\" ] \
+             \"scalarformat\" \"string\" 1 [ \"float\" ]\n\
+             Connect \"{0}\" \"\" \"screen\" \"outputlayers\"\n\
+             Create \"{0}_shader\" \"shader\"\n\
+             SetAttribute \"{0}_shader\" \"shadercode\" \"string\" 1 [ \"{1}\" ] \
+             \"distance\" \"float\" 1 [ 2.0 ]\n\
+             Create \"{0}_override\" \"attributes\"\n\
+             Connect \"{0}_shader\" \"\" \"{0}_override\" \"surfaceshader\"\n\
+             Connect \"{0}_override\" \"\" \".root\" \"geometryattributes\" \
+             \"priority\" \"int\" 1 [ 100 ]\n",
+            layer,
+            AMBIENT_OCCLUSION_OSL.replace('"', "\\\"")
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_volume_shader_args() {
+    assert!(VolumeShader::new().args().is_empty());
+
+    let shader = VolumeShader::new()
+        .density(8.0)
+        .emission_ramp(2.0, &[0.0, 1.0], &[[0.0; 3], [1.0, 0.5, 0.0]])
+        .velocity_scale(0.5);
+    let args = shader.args();
+    let names = args.iter().map(|arg| arg.name()).collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            "density",
+            "emissionramp_intensity",
+            "emissionramp_color_curve_Knots",
+            "emissionramp_color_curve_Colors",
+            "emissionramp_color_curve_Interp",
+        ],
+        names
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_bokeh() {
+    let stream = record_stream(|ctx| {
+        set_bokeh(ctx, "hexagonal", 6, 30.0);
+        set_bokeh(ctx, "circular", 0, 0.0);
+        set_bokeh(ctx, "broken", 2, 0.0);
+    });
+
+    assert_eq!(
+        "SetAttribute \"hexagonal\" \"depthoffield.enable\" \"int\" 1 [ 1 ] \
+         \"depthoffield.aperture.enable\" \"int\" 1 [ 1 ] \
+         \"depthoffield.aperture.sides\" \"int\" 1 [ 6 ] \
+         \"depthoffield.aperture.angle\" \"double\" 1 [ 30.0 ]\n\
+         SetAttribute \"circular\" \"depthoffield.enable\" \"int\" 1 [ 1 ] \
+         \"depthoffield.aperture.enable\" \"int\" 1 [ 0 ] \
+         \"depthoffield.aperture.sides\" \"int\" 1 [ 0 ] \
+         \"depthoffield.aperture.angle\" \"double\" 1 [ 0.0 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_statistics_output() {
+    let stream = record_stream(|ctx| {
+        set_statistics_output(ctx, "stats.json", StatsFormat::Json);
+        set_statistics_output(ctx, "", StatsFormat::Text);
+    });
+
+    assert_eq!(
+        "SetAttribute \".global\" \
+         \"statistics.filename\" \"string\" 1 [ \"stats.json\" ] \
+         \"statistics.format\" \"string\" 1 [ \"json\" ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_connect_with_transform() {
+    let stream = record_stream(|ctx| {
+        connect_with_transform(
+            ctx,
+            "tree",
+            nsi::ROOT,
+            "objects",
+            &[
+                1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+                2.5, 0.0, 0.0, 1.0,
+            ],
+        );
+    });
+
+    assert_eq!(
+        "Connect \"tree\" \"\" \".root\" \"objects\" \
+         \"value\" \"doublematrix\" 1 [ 1.0 0.0 0.0 0.0 0.0 1.0 0.0 0.0 \
+         0.0 0.0 1.0 0.0 2.5 0.0 0.0 1.0 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_subdivision_args() {
+    let names = |subdivision: Subdivision| {
+        subdivision
+            .args()
+            .iter()
+            .map(|arg| arg.name().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec!["subdivision.scheme"], names(Subdivision::new()));
+    assert_eq!(
+        vec![
+            "subdivision.scheme",
+            "subdivision.smoothcreasecorners",
+            "subdivision.facevaryinginterpolation",
+        ],
+        names(
+            Subdivision::new()
+                .corner_mode(CornerMode::Sharp)
+                .face_varying_interpolation(FaceVaryingInterpolation::All)
+        )
+    );
+
+    assert_eq!(0, CornerMode::Sharp.as_nsi());
+    assert_eq!(1, CornerMode::default().as_nsi());
+    assert_eq!(5, FaceVaryingInterpolation::default().as_nsi());
+    assert_eq!(4, FaceVaryingInterpolation::Boundaries.as_nsi());
+}
+
+#[cfg(test)]
+#[test]
+fn test_object_id() {
+    let mut layer = String::new();
+    let stream = record_stream(|ctx| {
+        set_object_id(ctx, "teapot_attributes", 42);
+        layer = add_id_layer(ctx, "screen");
+    });
+
+    assert_eq!(
+        format!(
+            "SetAttribute \"teapot_attributes\" \"id\" \"int\" 1 [ 42 ]\n\
+             Create \"{0}\" \"outputlayer\"\n\
+             SetAttribute \"{0}\" \"variablename\" \"string\" 1 [ \"id\" ] \
+             \"variablesource\" \"string\" 1 [ \"attribute\" ] \
+             \"layername\" \"string\" 1 [ \"id\" ] \
+             \"layertype\" \"string\" 1 [ \"scalar\" ] \
+             \"scalarformat\" \"string\" 1 [ \"float\" ] \
+             \"filter\" \"string\" 1 [ \"zmin\" ]\n\
+             Connect \"{0}\" \"\" \"screen\" \"outputlayers\"\n",
+            layer
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_enable_cryptomatte() {
+    let mut layers = Vec::new();
+    let stream = record_stream(|ctx| {
+        assert!(enable_cryptomatte(ctx, "screen", 0).is_empty());
+        layers = enable_cryptomatte(ctx, "screen", 3);
+    });
+
+    assert_eq!(3, layers.len());
+    assert_eq!(
+        layers
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| format!(
+                "Create \"{0}\" \"outputlayer\"\n\
+                 SetAttribute \"{0}\" \"variablename\" \"string\" 1 [ \"id\" ] \
+                 \"variablesource\" \"string\" 1 [ \"attribute\" ] \
+                 \"layername\" \"string\" 1 [ \"crypto0{1}\" ] \
+                 \"layertype\" \"string\" 1 [ \"quad\" ] \
+                 \"scalarformat\" \"string\" 1 [ \"float\" ] \
+                 \"filter\" \"string\" 1 [ \"cryptomattelayer{2}\" ]\n\
+                 Connect \"{0}\" \"\" \"screen\" \"outputlayers\"\n",
+                layer,
+                level,
+                2 * level
+            ))
+            .collect::<String>(),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_euler_rotation() {
+    let stream = record_stream(|ctx| {
+        set_euler_rotation(ctx, "x", &[90.0, 0.0, 0.0]);
+        set_euler_rotation(ctx, "y", &[0.0, 90.0, 0.0]);
+        set_euler_rotation(ctx, "xy", &[90.0, 90.0, 0.0]);
+    });
+
+    // Rows are the images of the X, Y & Z axes; points are row vectors.
+    let expected = [
+        (
+            "x",
+            [
+                1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        ),
+        (
+            "y",
+            [
+                0.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        ),
+        // First around X, then around Y.
+        (
+            "xy",
+            [
+                0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0,
+            ],
+        ),
+    ];
+
+    let lines = stream.lines().collect::<Vec<_>>();
+    assert_eq!(expected.len(), lines.len());
+
+    for ((transform, matrix), line) in expected.iter().zip(lines) {
+        let prefix = format!(
+            "SetAttribute \"{0}\" \"transformationmatrix\" \
+             \"doublematrix\" 1 [ ",
+            transform
+        );
+        let values = line
+            .strip_prefix(&prefix)
+            .and_then(|values| values.strip_suffix(" ]"))
+            .unwrap_or_else(|| panic!("Unexpected call: {0}", line))
+            .split(' ')
+            .map(|value| value.parse::<f64>().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(16, values.len());
+        for (value, expected) in values.iter().zip(matrix) {
+            assert!(
+                (value - expected).abs() < 1.0e-12,
+                "Rotation of '{0}' is {1:?}",
+                transform,
+                values
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_shutter_opening() {
+    let stream = record_stream(|ctx| {
+        set_shutter_opening(ctx, "camera", 0.75, 0.25);
+        set_shutter_opening(ctx, "camera", -0.1, 0.5);
+        set_shutter_opening(ctx, "camera", 0.5, f64::NAN);
+        set_shutter_opening(ctx, "camera", 0.25, 0.75);
+    });
+
+    assert_eq!(
+        "SetAttribute \"camera\" \"shutteropening\" \"double\" 2 \
+         [ 0.25 0.75 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_principled_shader_emission() {
+    assert!(PrincipledShader::new().args().is_empty());
+
+    let mut shader = String::new();
+    let stream = record_stream(|ctx| {
+        shader = PrincipledShader::new()
+            .emission([1.0, 0.5, 0.25], 2.0)
+            .build(ctx);
+    });
+
+    assert_eq!(
+        format!(
+            "Create \"{0}\" \"shader\"\n\
+             SetAttribute \"{0}\" \"shaderfilename\" \"string\" 1 \
+             [ \"${{DELIGHT}}/osl/dlPrincipled\" ]\n\
+             SetAttribute \"{0}\" \"incandescence\" \"color\" 1 \
+             [ 1.0 0.5 0.25 ] \"incandescence_intensity\" \"float\" 1 [ 4.0 ]\n",
+            shader
+        ),
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_clipping_range() {
+    let stream = record_stream(|ctx| {
+        set_clipping_range(ctx, "camera", 0.0, 100.0);
+        set_clipping_range(ctx, "camera", 10.0, 1.0);
+        set_clipping_range(ctx, "camera", f64::NAN, 1.0);
+        set_clipping_range(ctx, "camera", 0.5, 250.0);
+    });
+
+    assert_eq!(
+        "SetAttribute \"camera\" \"clippingrange\" \"double\" 2 \
+         [ 0.5 250.0 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_turntable() {
+    let mut frames = Vec::new();
+    let stream = record_stream(|ctx| {
+        turntable(ctx, "spin", 4, |frame| frames.push(frame));
+    });
+
+    assert_eq!(vec![0, 1, 2, 3], frames);
+
+    // Render control is not traced; each frame sets the rotation & frame.
+    let lines = stream.lines().collect::<Vec<_>>();
+    assert_eq!(2 * frames.len(), lines.len());
+    for (frame, lines) in frames.iter().zip(lines.chunks(2)) {
+        assert!(lines[0].starts_with(
+            "SetAttribute \"spin\" \"transformationmatrix\" \
+             \"doublematrix\" 1 ["
+        ));
+        assert_eq!(
+            format!(
+                "SetAttribute \".global\" \"frame\" \"double\" 1 [ {0:?} ]",
+                *frame as f64
+            ),
+            lines[1]
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_adaptive_sampling() {
+    let stream = record_stream(|ctx| {
+        set_adaptive_sampling(ctx, 64, 16, 0.01);
+        set_adaptive_sampling(ctx, 0, 16, 0.01);
+        set_adaptive_sampling(ctx, 16, 64, 0.0);
+        set_adaptive_sampling(ctx, 16, 64, f32::NAN);
+        set_adaptive_sampling(ctx, 16, 16, 0.05);
+    });
+
+    assert_eq!(
+        "SetAttribute \".global\" \"adaptivesampling.enable\" \"int\" 1 [ 1 ] \
+         \"adaptivesampling.minsamples\" \"int\" 1 [ 16 ] \
+         \"adaptivesampling.maxsamples\" \"int\" 1 [ 16 ] \
+         \"adaptivesampling.threshold\" \"float\" 1 [ 0.05 ]\n",
+        stream
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_set_crop() {
+    let stream = record_stream(|ctx| {
+        set_crop(ctx, "screen", [0.5, 0.25], [0.25, 0.75]);
+        set_crop(ctx, "screen", [0.25, 0.25], [0.75, 1.5]);
+        set_crop(ctx, "screen", [-0.25, 0.0], [0.75, 0.75]);
+        set_crop(ctx, "screen", [f32::NAN, 0.0], [0.75, 0.75]);
+        set_crop(ctx, "screen", [0.125, 0.25], [0.5, 0.75]);
+    });
+
+    assert_eq!(
+        "SetAttribute \"screen\" \"crop\" \"float[2]\" 2 \
+         [ 0.125 0.25 0.5 0.75 ]\n",
+        stream
+    );
+}
@@ -0,0 +1,67 @@
+//! A trait for sending user geometry to an ɴsɪ context.
+use nsi_core as nsi;
+
+/// Geometry that can create the ɴsɪ node(s) describing it.
+///
+/// Implement this for your own geometry types to use them like the
+/// geometry this crate supports out of the box.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// use nsi_toolbelt::{append, generate_or_use_handle, ToNsi};
+///
+/// /// Unconnected triangles.
+/// struct TriangleSoup {
+///     /// Three points per triangle.
+///     points: Vec<[f32; 3]>,
+/// }
+///
+/// impl ToNsi for TriangleSoup {
+///     fn to_nsi(&self, ctx: &nsi::Context, handle: Option<&str>) -> String {
+///         let handle = generate_or_use_handle(handle, Some("soup"));
+///
+///         ctx.create(&handle, nsi::MESH, None);
+///         ctx.set_attribute(
+///             &handle,
+///             &[
+///                 nsi::points!("P", &self.points.concat()),
+///                 nsi::integers!(
+///                     "nvertices",
+///                     &vec![3; self.points.len() / 3]
+///                 ),
+///             ],
+///         );
+///
+///         handle
+///     }
+/// }
+///
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let soup = TriangleSoup {
+///     points: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+/// };
+///
+/// append(&ctx, nsi::ROOT, None, &soup.to_nsi(&ctx, None));
+/// ```
+pub trait ToNsi {
+    /// Creates the node(s) for `self`.
+    ///
+    /// # Arguments
+    /// * `handle` – The handle of the node to create. If [`None`], a random
+    ///   handle is generated.
+    ///
+    /// Returns the handle of the node to connect to the scene.
+    fn to_nsi(&self, ctx: &nsi::Context, handle: Option<&str>) -> String;
+}
+
+#[cfg(feature = "polyhedron")]
+impl ToNsi for polyhedron_ops::Polyhedron {
+    /// See [`PolyhedronContext::polyhedron()`](crate::PolyhedronContext::polyhedron()).
+    #[inline]
+    fn to_nsi(&self, ctx: &nsi::Context, handle: Option<&str>) -> String {
+        use crate::PolyhedronContext;
+
+        ctx.polyhedron(self, handle)
+    }
+}
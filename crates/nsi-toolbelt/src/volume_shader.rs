@@ -0,0 +1,213 @@
+//! A builder for the `vdbVolume` shader of 3Delight.
+use crate::{append, node};
+use nsi_core as nsi;
+
+/// The interpolation of the knots of an emission ramp set with
+/// [`VolumeShader::emission_ramp()`]; a spline through the knots.
+const RAMP_INTERPOLATION: i32 = 3;
+
+/// Builder for 3Delight's `${DELIGHT}/osl/vdbVolume` shader.
+///
+/// This covers the inputs of the shader needed for most smoke & fire
+/// volumes:
+///
+/// | Method                                                       | Shader input
+/// |--------------------------------------------------------------|-----------------------------------
+/// | [`density()`](VolumeShader::density())                       | `"density"`
+/// | [`multiple_scattering()`](VolumeShader::multiple_scattering()) | `"multiple_scattering_intensity"`
+/// | [`emission_ramp()`](VolumeShader::emission_ramp())           | `"emissionramp_intensity"`, `"emissionramp_color_curve_Knots"`, `"emissionramp_color_curve_Colors"`, `"emissionramp_color_curve_Interp"`
+///
+/// Only inputs that were explicitly set are sent to the renderer. All
+/// others keep the shader's defaults. Which grids of the
+/// [`volume`](nsi::node::VOLUME) drive the inputs is set on the volume
+/// node itself, e.g. `"densitygrid"` or `"temperaturegrid"`.
+///
+/// # Example
+/// ```
+/// # use nsi_core as nsi;
+/// # use nsi_toolbelt::{node, VolumeShader};
+/// # let ctx = nsi::Context::new(None).unwrap();
+/// let volume = node(
+///     &ctx,
+///     None,
+///     nsi::VOLUME,
+///     Some(&[
+///         nsi::string!("vdbfilename", "explosion.vdb"),
+///         nsi::string!("densitygrid", "density"),
+///         nsi::string!("emissionintensitygrid", "flames"),
+///     ]),
+/// );
+///
+/// let shader = VolumeShader::new()
+///     .density(8.0)
+///     .multiple_scattering(0.44)
+///     .emission_ramp(
+///         1.0,
+///         &[0.0, 0.1, 0.8, 1.0],
+///         &[[0.0; 3], [0.0; 3], [0.83, 0.04, 0.0], [1.0, 0.59, 0.06]],
+///     )
+///     .velocity_scale(0.5)
+///     .build(&ctx, &volume);
+///
+/// // Tweak later, e.g. in an interactive session.
+/// VolumeShader::new().density(4.0).apply(&ctx, &shader);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VolumeShader {
+    density: Option<f32>,
+    multiple_scattering: Option<f32>,
+    emission_intensity: Option<f32>,
+    emission_knots: Vec<f32>,
+    emission_colors: Vec<f32>,
+    emission_interpolation: Vec<i32>,
+    velocity_scale: Option<f64>,
+}
+
+impl VolumeShader {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the multiplier for the values of the density grid
+    /// (`"density"`).
+    ///
+    /// Higher values make the volume more opaque.
+    #[inline]
+    #[must_use]
+    pub fn density(mut self, density: f32) -> Self {
+        self.density = Some(density);
+        self
+    }
+
+    /// Sets how much light is scattered more than once inside the volume
+    /// (`"multiple_scattering_intensity"`).
+    ///
+    /// Dense clouds & smoke look flat without this. It is only rendered
+    /// with a [`max_volume_depth()`](crate::GlobalSettings::max_volume_depth())
+    /// greater than `0`.
+    #[inline]
+    #[must_use]
+    pub fn multiple_scattering(mut self, intensity: f32) -> Self {
+        self.multiple_scattering = Some(intensity);
+        self
+    }
+
+    /// Sets the ramp mapping the emission grid to a color
+    /// (`"emissionramp_*"`).
+    ///
+    /// The `colors` are at the positions `knots`, in the range `0..1`, of
+    /// the ramp. They are interpolated with a spline. The result is multiplied
+    /// by `intensity`.
+    ///
+    /// # Panics
+    ///
+    /// If `knots` & `colors` differ in length.
+    #[must_use]
+    pub fn emission_ramp(
+        mut self,
+        intensity: f32,
+        knots: &[f32],
+        colors: &[[f32; 3]],
+    ) -> Self {
+        assert_eq!(
+            knots.len(),
+            colors.len(),
+            "The emission ramp has {} knots but {} colors.",
+            knots.len(),
+            colors.len()
+        );
+
+        self.emission_intensity = Some(intensity);
+        self.emission_knots = knots.to_vec();
+        self.emission_colors = colors.concat();
+        self.emission_interpolation = vec![RAMP_INTERPOLATION; knots.len()];
+        self
+    }
+
+    /// Sets the factor for the velocity grid used for motion blur.
+    ///
+    /// This is not a shader input but the `"velocityscale"` of the
+    /// [`volume`](nsi::node::VOLUME) node. It is hence only set by
+    /// [`build()`](VolumeShader::build()).
+    #[inline]
+    #[must_use]
+    pub fn velocity_scale(mut self, scale: f64) -> Self {
+        self.velocity_scale = Some(scale);
+        self
+    }
+
+    /// Returns the arguments for all shader inputs that were set.
+    pub fn args(&self) -> nsi::ArgVec<'_, 'static> {
+        let mut args = [
+            ("density", self.density),
+            ("multiple_scattering_intensity", self.multiple_scattering),
+            ("emissionramp_intensity", self.emission_intensity),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| nsi::float!(name, value)))
+        .collect::<Vec<_>>();
+
+        if !self.emission_knots.is_empty() {
+            let knots = self.emission_knots.len();
+
+            args.extend([
+                nsi::floats!(
+                    "emissionramp_color_curve_Knots",
+                    &self.emission_knots
+                )
+                .array_len(knots),
+                nsi::colors!(
+                    "emissionramp_color_curve_Colors",
+                    &self.emission_colors
+                )
+                .array_len(knots),
+                nsi::integers!(
+                    "emissionramp_color_curve_Interp",
+                    &self.emission_interpolation
+                )
+                .array_len(knots),
+            ]);
+        }
+
+        args
+    }
+
+    /// Sets all shader inputs that were set on the shader node `shader`.
+    pub fn apply(&self, ctx: &nsi::Context, shader: &str) {
+        let args = self.args();
+
+        if !args.is_empty() {
+            ctx.set_attribute(shader, &args);
+        }
+    }
+
+    /// Creates the shader and assigns it to `volume`.
+    ///
+    /// This creates a [`shader`](nsi::node::SHADER) node and an
+    /// [`attributes`](nsi::node::ATTRIBUTES) node connected to the
+    /// `"volumeshader"` & `"geometryattributes"` slots. A
+    /// [`velocity_scale()`](VolumeShader::velocity_scale()) is set on
+    /// `volume`.
+    ///
+    /// Returns the handle of the shader.
+    pub fn build(&self, ctx: &nsi::Context, volume: &str) -> String {
+        let shader = node(
+            ctx,
+            None,
+            nsi::node::SHADER,
+            Some(&[nsi::string!("shaderfilename", "${DELIGHT}/osl/vdbVolume")]),
+        );
+        self.apply(ctx, &shader);
+
+        let attributes = node(ctx, None, nsi::node::ATTRIBUTES, None);
+        append(ctx, &attributes, Some("volumeshader"), &shader);
+        append(ctx, volume, Some("geometryattributes"), &attributes);
+
+        if let Some(scale) = self.velocity_scale {
+            ctx.set_attribute(volume, &[nsi::double!("velocityscale", scale)]);
+        }
+
+        shader
+    }
+}
@@ -0,0 +1,145 @@
+//! Demonstrates interactive progressive rendering (IPR).
+//!
+//! The scene is edited while the renderer is running through an
+//! `InteractiveSession`, which pushes each edit to the render.
+use nsi_core as nsi;
+use nsi_toolbelt::*;
+use std::{thread, time::Duration};
+
+pub fn main() {
+    let ctx = nsi::Context::new(None).unwrap();
+
+    // A sphere, rendered as a single particle.
+    append(
+        &ctx,
+        nsi::ROOT,
+        None,
+        append(
+            &ctx,
+            &node(
+                &ctx,
+                Some("sphere"),
+                nsi::PARTICLES,
+                Some(&[
+                    nsi::points!("P", &[0.0, 0.0, 0.0]),
+                    nsi::floats!("width", &[2.0]),
+                ]),
+            ),
+            Some("geometryattributes"),
+            append(
+                &ctx,
+                &node(&ctx, None, nsi::ATTRIBUTES, None),
+                Some("surfaceshader"),
+                &node(
+                    &ctx,
+                    Some("sphere_shader"),
+                    nsi::SHADER,
+                    Some(&[
+                        nsi::string!(
+                            "shaderfilename",
+                            "${DELIGHT}/osl/dlPrincipled"
+                        ),
+                        nsi::float!("roughness", 0.3),
+                    ]),
+                ),
+            )
+            .0,
+        )
+        .0,
+    );
+
+    // Something to light the sphere with.
+    append(
+        &ctx,
+        nsi::ROOT,
+        None,
+        &nsi_3delight::environment_sky(&ctx, None, None, None, None, None).0,
+    );
+
+    append(
+        &ctx,
+        nsi::ROOT,
+        None,
+        append(
+            &ctx,
+            &translation(&ctx, None, [0.0, 0.0, 5.0]),
+            None,
+            append(
+                &ctx,
+                &node(
+                    &ctx,
+                    None,
+                    nsi::PERSPECTIVE_CAMERA,
+                    Some(&[nsi::float!("fov", 35.0)]),
+                ),
+                Some("screens"),
+                append(
+                    &ctx,
+                    &node(
+                        &ctx,
+                        None,
+                        nsi::SCREEN,
+                        Some(&[
+                            nsi::integers!("resolution", &[640, 480])
+                                .array_len(2),
+                            nsi::integer!("oversampling", 16),
+                        ]),
+                    ),
+                    Some("outputlayers"),
+                    append(
+                        &ctx,
+                        &node(
+                            &ctx,
+                            None,
+                            nsi::OUTPUT_LAYER,
+                            Some(&[
+                                nsi::string!("variablename", "Ci"),
+                                nsi::integer!("withalpha", 1),
+                                nsi::string!("scalarformat", "float"),
+                            ]),
+                        ),
+                        Some("outputdrivers"),
+                        &node(
+                            &ctx,
+                            None,
+                            nsi::OUTPUT_DRIVER,
+                            Some(&[nsi::string!(
+                                "drivername",
+                                FileDriver::IDisplay.as_str()
+                            )]),
+                        ),
+                    )
+                    .0,
+                )
+                .0,
+            )
+            .0,
+        )
+        .0,
+    );
+
+    // Starts an interactive render that keeps running across edits.
+    let session = ctx.interactive_session();
+
+    // Cycle the color of the sphere while the renderer is running.
+    for step in 0..36 {
+        let angle = (step * 10) as f32 * core::f32::consts::PI / 180.0;
+
+        // The edit is pushed to the running render when the closure returns.
+        session.edit(|ctx| {
+            ctx.set_color(
+                "sphere_shader",
+                "i_color",
+                [
+                    0.5 + 0.5 * angle.cos(),
+                    0.5 + 0.5 * (angle + 2.094).cos(),
+                    0.5 + 0.5 * (angle + 4.189).cos(),
+                ],
+            );
+        });
+
+        thread::sleep(Duration::from_millis(250));
+    }
+
+    session.stop();
+}
@@ -13,6 +13,16 @@ mod render;
 use render::*;
 
 pub fn main() {
+    // The display transform for the PNG can be given as the 1st argument:
+    // `srgb` (default), `rec709` or `raw`. With `raw` only the EXR is written.
+    let display_transform = std::env::args()
+        .nth(1)
+        .map(|name| {
+            DisplayTransform::from_name(&name)
+                .expect("Display transform must be srgb, rec709 or raw.")
+        })
+        .unwrap_or_default();
+
     let quantized_pixel_data = Arc::new(Mutex::new(Vec::new()));
 
     // Open closure.
@@ -33,58 +43,6 @@ pub fn main() {
         },
     );
 
-    // Write closure.
-    // Called for each bucket or scanline of pixels that have been
-    // rendered.
-    // Bucket size is commonly 16x16 pixels but this is not guaranteed
-    // by the API.
-    // The pixel_data will contain a full buffer of all the pixels that
-    // were finished so far.
-    let write = nsi::output::WriteCallback::new(
-        |_name: &str,
-         width: usize,
-         _height: usize,
-         x_min: usize,
-         x_max_plus_one: usize,
-         y_min: usize,
-         y_max_plus_one: usize,
-         pixel_format: &nsi::output::PixelFormat,
-         pixel_data: &[f32]| {
-            let mut quantized_pixel_data = quantized_pixel_data.lock().unwrap();
-
-            for scanline in y_min..y_max_plus_one {
-                let y_offset = scanline * width;
-
-                for index in y_offset + x_min..y_offset + x_max_plus_one {
-                    let index = index * pixel_format.channels();
-                    let alpha = pixel_data[index + 3];
-
-                    // Ignore pixels with zero alpha.
-                    if 0.0 != alpha {
-                        // Unpremultiply the color – this is needed
-                        // or else the color profile transform will
-                        // yield wrong results for pixels with
-                        // non-opaque alpha. Furthermore PNG wants
-                        // unpremultiplied pixels and that is what
-                        // we will write the 8bit data to, at the end.
-                        quantized_pixel_data[index + 0] =
-                            (linear_to_srgb(pixel_data[index + 0] / alpha)
-                                * 255.0) as _;
-                        quantized_pixel_data[index + 1] =
-                            (linear_to_srgb(pixel_data[index + 1] / alpha)
-                                * 255.0) as _;
-                        quantized_pixel_data[index + 2] =
-                            (linear_to_srgb(pixel_data[index + 2] / alpha)
-                                * 255.0) as _;
-                        quantized_pixel_data[index + 3] = (alpha * 255.0) as _;
-                    }
-                }
-            }
-
-            nsi::output::Error::None
-        },
-    );
-
     // We need to remember the pixel buffer dimensions to write the
     // PNG out below.
     let mut dimensions = (0u32, 0u32);
@@ -130,7 +88,18 @@ pub fn main() {
     polyhedron.normalize();
 
     // The next call blocks until the render has finished.
-    nsi_render(32, &polyhedron, open, write, finish);
+    nsi_render(
+        32,
+        &polyhedron,
+        display_transform,
+        &quantized_pixel_data,
+        open,
+        finish,
+    );
+
+    if !display_transform.is_display_referred() {
+        return;
+    }
 
     // We can shed the Arc and the Mutex now that nsi_render() is done.
     let quantized_pixel_data = Arc::<_>::try_unwrap(quantized_pixel_data)
@@ -138,8 +107,8 @@ pub fn main() {
         .into_inner()
         .unwrap();
 
-    // Write out the display-referred, u8 quantized data we prepared
-    // in the write closure above as a PNG.
+    // Write out the display-referred, u8 quantized data nsi_render()
+    // prepared as a PNG.
     let path = format!("{}.png", polyhedron.name());
     let file = File::create(Path::new(&path)).unwrap();
     let ref mut writer = BufWriter::new(file);
@@ -153,17 +122,3 @@ pub fn main() {
         .write_image_data(&quantized_pixel_data)
         .expect("Error writing PNG.");
 }
-
-/// Linear to (0..1 clamped) sRGB conversion – bad choice but cheap.
-#[inline]
-fn linear_to_srgb(x: f32) -> f32 {
-    if x <= 0.0 {
-        0.0
-    } else if x >= 1.0 {
-        1.0
-    } else if x < 0.0031308 {
-        x * 12.92
-    } else {
-        x.powf(1.0 / 2.4) * 1.055 - 0.055
-    }
-}
@@ -1,18 +1,85 @@
 use crate::p_ops;
 use nsi_3delight as nsi_3dl;
 use nsi_core as nsi;
+use nsi_tb::PolyhedronContext;
 use nsi_toolbelt as nsi_tb;
+use std::sync::Mutex;
+
+/// How linear, rendered pixels are turned into display-referred 8bit ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum DisplayTransform {
+    /// The sRGB transfer function.
+    #[default]
+    Srgb,
+    /// The ITU-R BT.709 transfer function.
+    Rec709,
+    /// No transform. Only the linear data is written, e.g. to an EXR.
+    Raw,
+}
+
+impl DisplayTransform {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "srgb" => Some(Self::Srgb),
+            "rec709" => Some(Self::Rec709),
+            "raw" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+
+    /// Whether this produces display-referred pixels.
+    pub(crate) fn is_display_referred(&self) -> bool {
+        Self::Raw != *self
+    }
+
+    /// Converts linear, premultiplied pixels with the alpha in channel `3` to
+    /// unpremultiplied 8bit ones.
+    ///
+    /// For [`DisplayTransform::Raw`] this does nothing.
+    pub(crate) fn encode(&self, src: &[f32], dst: &mut [u8], channels: usize) {
+        match self {
+            Self::Srgb => nsi::output::srgb_encode(src, dst, channels, Some(3)),
+            Self::Rec709 => src
+                .chunks_exact(channels)
+                .zip(dst.chunks_exact_mut(channels))
+                .for_each(|(src, dst)| {
+                    let alpha = src[3];
+                    for (index, (&src, dst)) in
+                        src.iter().zip(dst.iter_mut()).enumerate()
+                    {
+                        let value = if 3 == index {
+                            alpha
+                        } else if 0.0 == alpha {
+                            0.0
+                        } else {
+                            linear_to_rec709(src / alpha)
+                        };
+                        *dst = (value.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+                    }
+                }),
+            Self::Raw => (),
+        }
+    }
+}
+
+fn linear_to_rec709(x: f32) -> f32 {
+    if x < 0.018 {
+        x * 4.5
+    } else {
+        1.099 * x.powf(0.45) - 0.099
+    }
+}
 
 fn nsi_camera<'a>(
     c: &nsi::Context<'a>,
     name: &str,
     open: nsi::output::OpenCallback,
-    write: nsi::output::WriteCallback,
+    write: Option<nsi::output::WriteCallback>,
     finish: nsi::output::FinishCallback,
 ) {
     // Setup a camera TRANSFORM.
     c.create("camera_xform", nsi::TRANSFORM, None);
-    c.connect("camera_xform", None, nsi::ROOT, "objects", None);
+    c.attach_to_root("camera_xform");
     c.set_attribute(
         "camera_xform",
         &[nsi::double_matrix!(
@@ -53,23 +120,24 @@ fn nsi_camera<'a>(
     c.create("driver", nsi::OUTPUT_DRIVER, None);
     c.connect("driver", None, "beauty", "outputdrivers", None);
 
-    c.set_attribute(
-        "driver",
-        &[
-            nsi::string!("drivername", nsi::output::FERRIS),
-            nsi::string!("imagefilename", name),
-            nsi::integer!("associatealpha", 1),
-            nsi::callback!("callback.open", open),
-            nsi::callback!("callback.write", write),
-            nsi::callback!("callback.finish", finish),
-        ],
-    );
+    let mut args = vec![
+        nsi::string!("drivername", nsi::output::FERRIS),
+        nsi::string!("imagefilename", name),
+        nsi::integer!("associatealpha", 1),
+        nsi::callback!("callback.open", open),
+        nsi::callback!("callback.finish", finish),
+    ];
+    if let Some(write) = write {
+        args.push(nsi::callback!("callback.write", write));
+    }
+
+    c.set_attribute("driver", &args);
 }
 
 fn nsi_reflective_ground(c: &nsi::Context) {
     // Floor.
     c.create("ground_xform_0", nsi::TRANSFORM, None);
-    c.connect("ground_xform_0", None, nsi::ROOT, "objects", None);
+    c.attach_to_root("ground_xform_0");
     c.set_attribute(
         "ground_xform_0",
         &[nsi::double_matrix!(
@@ -156,12 +224,18 @@ fn nsi_material(c: &nsi::Context, name: &str) {
     );
 }
 
+/// Renders `polyhedron`.
+///
+/// Unless `display_transform` is [`DisplayTransform::Raw`], the pixels are
+/// also encoded with it into `display_pixels` while they arrive. The buffer
+/// must have been sized for the image, e.g. by `open`.
 pub(crate) fn nsi_render<'a>(
     samples: u32,
     polyhedron: &p_ops::Polyhedron,
-    open: nsi::output::OpenCallback,
-    write: nsi::output::WriteCallback,
-    finish: nsi::output::FinishCallback,
+    display_transform: DisplayTransform,
+    display_pixels: &'a Mutex<Vec<u8>>,
+    open: nsi::output::OpenCallback<'a>,
+    finish: nsi::output::FinishCallback<'a>,
 ) {
     let ctx = nsi::Context::new(None) //&[nsi::string!("streamfilename", "stdout")])
         .expect("Could not create NSI rendering context.");
@@ -176,6 +250,48 @@ pub(crate) fn nsi_render<'a>(
         ],
     );
 
+    // Write closure.
+    // Called for each bucket or scanline of pixels that have been
+    // rendered.
+    // Bucket size is commonly 16x16 pixels but this is not guaranteed
+    // by the API.
+    // The pixel_data will contain a full buffer of all the pixels that
+    // were finished so far.
+    let write = display_transform.is_display_referred().then(|| {
+        nsi::output::WriteCallback::new(
+            move |_name: &str,
+                  width: usize,
+                  _height: usize,
+                  x_min: usize,
+                  x_max_plus_one: usize,
+                  y_min: usize,
+                  y_max_plus_one: usize,
+                  pixel_format: &nsi::output::PixelFormat,
+                  pixel_data: &[f32]| {
+                let mut display_pixels = display_pixels.lock().unwrap();
+
+                for scanline in y_min..y_max_plus_one {
+                    let channels = pixel_format.channels();
+                    let start = (scanline * width + x_min) * channels;
+                    let end = (scanline * width + x_max_plus_one) * channels;
+
+                    // Unpremultiply the color – this is needed or else the
+                    // color profile transform will yield wrong results for
+                    // pixels with non-opaque alpha. Furthermore PNG wants
+                    // unpremultiplied pixels and that is what we will write
+                    // the 8bit data to, at the end.
+                    display_transform.encode(
+                        &pixel_data[start..end],
+                        &mut display_pixels[start..end],
+                        channels,
+                    );
+                }
+
+                nsi::output::Error::None
+            },
+        )
+    });
+
     nsi_camera(&ctx, &polyhedron.name(), open, write, finish);
 
     nsi_tb::append(
@@ -194,7 +310,13 @@ pub(crate) fn nsi_render<'a>(
         .0,
     );
 
-    let name = polyhedron.to_nsi(&ctx, None, None, None, None);
+    // Render this as a C-C subdivison surface with semi-sharp creases.
+    let name = ctx.subdivision_polyhedron(
+        polyhedron,
+        None,
+        &nsi_tb::Subdivision::new().corner_mode(nsi_tb::CornerMode::Sharp),
+        None,
+    );
     nsi_tb::append(&ctx, nsi::ROOT, None, &name);
 
     nsi_material(&ctx, &name);
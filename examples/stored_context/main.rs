@@ -0,0 +1,66 @@
+//! Demonstrates storing a context in a struct and setting attributes on it
+//! later.
+//!
+//! The pending edits are kept as `OwnedArg`s. These own their data so the
+//! struct does not need to borrow anything besides the context itself.
+use nsi_core as nsi;
+
+struct Scene {
+    ctx: nsi::Context<'static>,
+    // Edits per node, sent to the renderer by `commit()`.
+    pending: Vec<(String, Vec<nsi::OwnedArg>)>,
+}
+
+impl Scene {
+    fn new() -> Self {
+        let ctx = nsi::Context::new(None).unwrap();
+
+        ctx.create("ground", nsi::MESH, None);
+        ctx.connect("ground", None, nsi::ROOT, "objects", None);
+
+        Self {
+            ctx,
+            pending: Vec::new(),
+        }
+    }
+
+    fn set(&mut self, handle: &str, arg: nsi::OwnedArg) {
+        match self.pending.iter_mut().find(|(h, _)| h == handle) {
+            Some((_, args)) => args.push(arg),
+            None => self.pending.push((handle.to_string(), vec![arg])),
+        }
+    }
+
+    // The data is computed here and dropped when this returns. The owned
+    // arguments keep a copy until commit() is called.
+    fn set_ground(&mut self, size: f32) {
+        let positions = [[-1.0, 0.0, -1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, 1.0]]
+            .iter()
+            .chain(&[[1.0, 0.0, -1.0]])
+            .flat_map(|p| p.map(|c| c * size))
+            .collect::<Vec<f32>>();
+
+        self.set(
+            "ground",
+            nsi::OwnedArg::new("P", nsi::OwnedArgData::Points(positions)),
+        );
+        self.set("ground", nsi::OwnedArg::new("nvertices", vec![4].into()));
+    }
+
+    fn commit(&mut self) {
+        for (handle, args) in self.pending.drain(..) {
+            self.ctx.set_attribute_owned(&handle, args);
+        }
+    }
+}
+
+pub fn main() {
+    let mut scene = Scene::new();
+
+    scene.set_ground(10.0);
+    scene.commit();
+
+    // Later edits go through the same struct.
+    scene.set_ground(20.0);
+    scene.commit();
+}
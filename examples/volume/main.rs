@@ -26,78 +26,42 @@ pub fn main() {
         .0,
     );
 
+    let volume = node(
+        &ctx,
+        None,
+        nsi::node::VOLUME,
+        Some(&[
+            nsi::string!("vdbfilename", VDB_ASSET),
+            nsi::string!("temperaturegrid", "temperature"),
+            nsi::string!("densitygrid", "density"),
+            nsi::string!("emissionintensitygrid", "flames"),
+        ]),
+    );
+
+    VolumeShader::new()
+        .density(8.0)
+        .multiple_scattering(0.44)
+        .emission_ramp(
+            1.0,
+            &[0.0, 0.09034268, 0.83800625, 1.0],
+            &[
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [0.832, 0.0416, 0.0],
+                [1.0, 0.5935334, 0.061999976],
+            ],
+        )
+        .build(&ctx, &volume);
+
     append(
         &ctx,
         ".root",
         None,
         append(
             &ctx,
-            &rotation(&ctx, None, 135.0, &[0.0, 1.0, 0.0]),
+            &rotation(&ctx, None, 135.0, [0.0, 1.0, 0.0]),
             None,
-            &append(
-                &ctx,
-                &node(
-                    &ctx,
-                    None,
-                    nsi::node::VOLUME,
-                    Some(&[
-                        nsi::string!("vdbfilename", VDB_ASSET),
-                        nsi::string!("temperaturegrid", "temperature"),
-                        nsi::string!("densitygrid", "density"),
-                        nsi::string!("emissionintensitygrid", "flames"),
-                    ]),
-                ),
-                Some("geometryattributes"),
-                &append(
-                    &ctx,
-                    &node(&ctx, None, nsi::node::ATTRIBUTES, None),
-                    Some("volumeshader"),
-                    &node(
-                        &ctx,
-                        None,
-                        nsi::node::SHADER,
-                        Some(&[
-                            nsi::string!(
-                                "shaderfilename",
-                                "${DELIGHT}/osl/vdbVolume"
-                            ),
-                            nsi::float!("density", 8.0),
-                            nsi::float!("multiple_scattering_intensity", 0.44),
-                            nsi::float!("emissionramp_intensity", 1.0),
-                            nsi::floats!(
-                                "emissionramp_color_curve_Knots",
-                                &[0.0, 0.09034268, 0.83800625, 1.0]
-                            )
-                            .array_len(4),
-                            nsi::colors!(
-                                "emissionramp_color_curve_Colors",
-                                &[
-                                    0.,
-                                    0.,
-                                    0.,
-                                    0.,
-                                    0.,
-                                    0.,
-                                    0.832,
-                                    0.0416,
-                                    0.,
-                                    1.,
-                                    0.5935334,
-                                    0.061999976
-                                ]
-                            )
-                            .array_len(4),
-                            nsi::integers!(
-                                "emissionramp_color_curve_Interp",
-                                &[3, 3, 3, 3,]
-                            )
-                            .array_len(4),
-                        ]),
-                    ),
-                )
-                .0,
-            )
-            .0,
+            &volume,
         )
         .0,
     );
@@ -117,11 +81,12 @@ pub fn main() {
                 &ctx,
                 None,
                 // Direction.
-                &[0.0, -0.1, -1.0],
+                [0.0, -0.1, -1.0],
                 // Up.
-                &[0.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
                 field_of_view,
                 Some(2.0),
+                1.0,
                 // Bounding box to frame.
                 &vdbq::DlOpenVdbQuery::new(VDB_ASSET)
                     .unwrap()
@@ -174,7 +139,10 @@ pub fn main() {
                             &ctx,
                             Some("driver"),
                             nsi::node::OUTPUT_DRIVER,
-                            Some(&[nsi::string!("drivername", "idisplay")]),
+                            Some(&[nsi::string!(
+                                "drivername",
+                                FileDriver::IDisplay.as_str()
+                            )]),
                         ),
                     )
                     .0,
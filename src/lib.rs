@@ -105,6 +105,11 @@
 //! Demonstrates using the [`FnStatus`] callback closure during rendering and a
 //! channel for communicating between main- and rendering thread(s).
 //!
+//! ### IPR
+//!
+//! Demonstrates editing a scene while an interactive render is running and
+//! pushing the edits to the renderer with [`Context::flush()`].
+//!
 //! ### Jupyter
 //!
 //! Render directly into a Jupyter notebook.
@@ -143,6 +148,13 @@
 //!
 //! * [`toolbelt`] -- Add convenience methods that work with a [`Context`].
 //!
+//! * `nsi-polyhedron` -- Add [`polyhedron-ops`](https://crates.io/crates/polyhedron-ops)
+//!   support to the [`toolbelt`]. This allows sending a `Polyhedron` to a
+//!   [`Context`] via `ctx.polyhedron()`.
+//!
+//! * `glam`, `nalgebra` -- Add conversions from the matrix & vector types of
+//!   these crates to the ones used by the [`toolbelt`].
+//!
 //! * [`delight`] -- Add some nodes & shaders specifi to 3Delight.
 //!
 //! * `nightly` -- Enable some unstable features (suggested if you build with a